@@ -1,4 +1,4 @@
-use ethercat_esi::EtherCatInfo;
+use ethercat_esi::{EsiDocument, ParseOptions};
 use std::{
     env,
     fs::File,
@@ -14,8 +14,15 @@ fn main() -> io::Result<()> {
             let mut xml_file = File::open(file_name)?;
             let mut xml_string = String::new();
             xml_file.read_to_string(&mut xml_string)?;
-            let info = EtherCatInfo::from_xml_str(&xml_string)?;
-            println!("{:#?}", info);
+            let opts = ParseOptions {
+                lenient: true,
+                ..Default::default()
+            };
+            let (doc, warnings) = EsiDocument::from_xml_str_with_options(&xml_string, &opts)?;
+            for w in warnings {
+                eprintln!("Warning: {}", w);
+            }
+            println!("{:#?}", doc);
         }
     }
     Ok(())
@@ -0,0 +1,120 @@
+use ethercat_esi::{diff, validate, EtherCatInfo, Severity};
+use std::{env, fs, io, process};
+
+const USAGE: &str = "\
+Usage: esi <command> [options] <file>...
+
+Commands:
+  validate [--deny-warnings] <file>...   Check ESI files for semantic errors
+  diff [--json] <old> <new>              Show the differences of two ESI files
+
+Exit codes:
+  0  success (no errors, no differences)
+  1  differences found
+  2  invalid usage or unreadable file
+  3  validation errors found";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let code = match args.first().map(String::as_str) {
+        Some("validate") => run_validate(&args[1..]),
+        Some("diff") => run_diff(&args[1..]),
+        Some("-h") | Some("--help") => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    };
+    process::exit(code);
+}
+
+fn read_esi(file_name: &str) -> io::Result<EtherCatInfo> {
+    let xml_string = fs::read_to_string(file_name)?;
+    EtherCatInfo::from_xml_str(&xml_string)
+}
+
+/// Split the arguments into the given flags and the file names.
+///
+/// Returns `None` if there is an unknown flag.
+fn parse_args<'a>(args: &'a [String], known: &[&str]) -> Option<(Vec<&'a str>, Vec<&'a str>)> {
+    let (flags, files): (Vec<_>, Vec<_>) = args
+        .iter()
+        .map(String::as_str)
+        .partition(|a| a.starts_with("--"));
+    if let Some(flag) = flags.iter().find(|f| !known.contains(f)) {
+        eprintln!("Unknown option `{}`", flag);
+        return None;
+    }
+    Some((flags, files))
+}
+
+fn run_validate(args: &[String]) -> i32 {
+    let (flags, files) = match parse_args(args, &["--deny-warnings"]) {
+        Some(args) => args,
+        None => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let deny_warnings = flags.contains(&"--deny-warnings");
+    if files.is_empty() {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+    let mut code = 0;
+    for file_name in files {
+        let info = match read_esi(file_name) {
+            Ok(info) => info,
+            Err(err) => {
+                eprintln!("{}: {}", file_name, err);
+                return 2;
+            }
+        };
+        for d in validate(&info) {
+            println!("{}: {}", file_name, d);
+            if d.severity == Severity::Error || deny_warnings {
+                code = 3;
+            }
+        }
+    }
+    code
+}
+
+fn run_diff(args: &[String]) -> i32 {
+    let (flags, files) = match parse_args(args, &["--json"]) {
+        Some(args) => args,
+        None => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let json = flags.contains(&"--json");
+    if files.len() != 2 {
+        eprintln!("{}", USAGE);
+        return 2;
+    }
+    let mut infos = vec![];
+    for file_name in files {
+        match read_esi(file_name) {
+            Ok(info) => infos.push(info),
+            Err(err) => {
+                eprintln!("{}: {}", file_name, err);
+                return 2;
+            }
+        }
+    }
+    let d = diff(&infos[0], &infos[1]);
+    if json {
+        println!("{}", d.to_json());
+    } else {
+        print!("{}", d);
+    }
+    if d.is_empty() {
+        0
+    } else {
+        1
+    }
+}
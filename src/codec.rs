@@ -0,0 +1,427 @@
+//! Typed access to process data.
+
+use crate::{data_type::data_type_from_str, layout::*, structs::*};
+use ethercat_types::{DataType, Value};
+use std::io::{Error, ErrorKind, Result};
+
+/// Reads inputs from and writes outputs to the process data of a slave.
+///
+/// The conversion of each value follows the data type and bit length
+/// of the PDO entry. Entries of type `BIT2` to `BIT8` are represented
+/// as [`Value::U8`]. Signed entries that are shorter than their data type
+/// are sign extended, integers that don't fit into the bit length of an
+/// entry are rejected.
+#[derive(Debug, Clone)]
+pub struct ProcessImageCodec {
+    layout: ProcessImageLayout,
+}
+
+impl ProcessImageCodec {
+    /// Create a codec for the given PDOs of the device.
+    ///
+    /// See [`Device::process_image_layout`] for the PDO assignment.
+    pub fn new(device: &Device, pdos: &[PdoIdx]) -> Result<Self> {
+        Ok(Self::from_layout(device.process_image_layout(pdos)?))
+    }
+
+    pub fn from_layout(layout: ProcessImageLayout) -> Self {
+        ProcessImageCodec { layout }
+    }
+
+    pub fn layout(&self) -> &ProcessImageLayout {
+        &self.layout
+    }
+
+    /// Read an input value.
+    pub fn read(&self, inputs: &[u8], idx: PdoEntryIdx) -> Result<Value> {
+        decode(inputs, self.entry(Direction::Input, idx)?)
+    }
+
+    /// Read an input value by the name of its PDO entry.
+    pub fn read_by_name(&self, inputs: &[u8], name: &str) -> Result<Value> {
+        decode(inputs, self.entry_by_name(Direction::Input, name)?)
+    }
+
+    /// Write an output value.
+    pub fn write(&self, outputs: &mut [u8], idx: PdoEntryIdx, value: &Value) -> Result<()> {
+        encode(outputs, self.entry(Direction::Output, idx)?, value)
+    }
+
+    /// Write an output value by the name of its PDO entry.
+    pub fn write_by_name(&self, outputs: &mut [u8], name: &str, value: &Value) -> Result<()> {
+        encode(outputs, self.entry_by_name(Direction::Output, name)?, value)
+    }
+
+    fn entry(&self, direction: Direction, idx: PdoEntryIdx) -> Result<&EntryLayout> {
+        self.layout
+            .entries(direction)
+            .find(|e| e.entry_idx == idx)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::NotFound,
+                    format!(
+                        "{:?} entry 0x{:04X}:{:02X} not found",
+                        direction,
+                        u16::from(idx.idx),
+                        u8::from(idx.sub_idx)
+                    ),
+                )
+            })
+    }
+
+    fn entry_by_name(&self, direction: Direction, name: &str) -> Result<&EntryLayout> {
+        let mut entries = self
+            .layout
+            .entries(direction)
+            .filter(|e| e.name.iter().any(|(n, _)| n == name));
+        let entry = entries.next().ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("{:?} entry `{}` not found", direction, name),
+            )
+        })?;
+        if entries.next().is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{:?} entry name `{}` is ambiguous", direction, name),
+            ));
+        }
+        Ok(entry)
+    }
+}
+
+/// Data type of an entry, derived from its bit length if it is unknown.
+pub(crate) fn data_type(e: &EntryLayout) -> DataType {
+    if let Some(dt) = e.data_type.as_deref().and_then(data_type_from_str) {
+        return dt;
+    }
+    match e.bit_len {
+        1 => DataType::Bool,
+        8 => DataType::U8,
+        16 => DataType::U16,
+        32 => DataType::U32,
+        64 => DataType::U64,
+        _ => DataType::Raw,
+    }
+}
+
+fn decode(data: &[u8], e: &EntryLayout) -> Result<Value> {
+    let bytes = get_bits(data, e.offset.byte * 8 + e.offset.bit as usize, e.bit_len)?;
+    let uint = || {
+        let mut buf = [0; 8];
+        let n = bytes.len().min(8);
+        buf[..n].copy_from_slice(&bytes[..n]);
+        u64::from_le_bytes(buf)
+    };
+    let int = || {
+        let shift = 64 - e.bit_len.clamp(1, 64) as u32;
+        ((uint() << shift) as i64) >> shift
+    };
+    let value = match data_type(e) {
+        DataType::Bool | DataType::Bit1 => Value::Bool(uint() != 0),
+        DataType::Bit2
+        | DataType::Bit3
+        | DataType::Bit4
+        | DataType::Bit5
+        | DataType::Bit6
+        | DataType::Bit7
+        | DataType::Bit8
+        | DataType::U8 => Value::U8(uint() as u8),
+        DataType::Byte => Value::Byte(uint() as u8),
+        DataType::I8 => Value::I8(int() as i8),
+        DataType::I16 => Value::I16(int() as i16),
+        DataType::I32 => Value::I32(int() as i32),
+        DataType::I64 => Value::I64(int()),
+        DataType::U16 => Value::U16(uint() as u16),
+        DataType::U32 => Value::U32(uint() as u32),
+        DataType::U64 => Value::U64(uint()),
+        DataType::F32 => Value::F32(f32::from_bits(uint() as u32)),
+        DataType::F64 => Value::F64(f64::from_bits(uint())),
+        DataType::String => {
+            let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+            Value::String(String::from_utf8_lossy(&bytes[..len]).into_owned())
+        }
+        DataType::U8Array => Value::U8Array(bytes),
+        DataType::U16Array => Value::U16Array(
+            bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect(),
+        ),
+        _ => Value::Raw(bytes),
+    };
+    Ok(value)
+}
+
+fn encode(data: &mut [u8], e: &EntryLayout, value: &Value) -> Result<()> {
+    let bytes = value_to_bytes(data_type(e), value)?;
+    if bytes.len() > (e.bit_len + 7) / 8 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{:?} does not fit into {} bits", value, e.bit_len),
+        ));
+    }
+    if let Some((v, signed)) = integer(value) {
+        let bits = e.bit_len.clamp(1, 64) as u32;
+        let (min, max) = if signed {
+            (-(1_i128 << (bits - 1)), (1_i128 << (bits - 1)) - 1)
+        } else {
+            (0, (1_i128 << bits) - 1)
+        };
+        if v < min || v > max {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{:?} is out of range for {} bits", value, e.bit_len),
+            ));
+        }
+    }
+    set_bits(
+        data,
+        e.offset.byte * 8 + e.offset.bit as usize,
+        e.bit_len,
+        &bytes,
+    )
+}
+
+/// Little endian representation of a value of the given data type.
+pub(crate) fn value_to_bytes(dt: DataType, value: &Value) -> Result<Vec<u8>> {
+    let bytes = match (dt, value) {
+        (DataType::Bool, Value::Bool(v)) | (DataType::Bit1, Value::Bool(v)) => vec![*v as u8],
+        (
+            DataType::Bit2
+            | DataType::Bit3
+            | DataType::Bit4
+            | DataType::Bit5
+            | DataType::Bit6
+            | DataType::Bit7
+            | DataType::Bit8
+            | DataType::U8,
+            Value::U8(v),
+        ) => vec![*v],
+        (DataType::Byte, Value::Byte(v)) => vec![*v],
+        (DataType::I8, Value::I8(v)) => v.to_le_bytes().to_vec(),
+        (DataType::I16, Value::I16(v)) => v.to_le_bytes().to_vec(),
+        (DataType::I32, Value::I32(v)) => v.to_le_bytes().to_vec(),
+        (DataType::I64, Value::I64(v)) => v.to_le_bytes().to_vec(),
+        (DataType::U16, Value::U16(v)) => v.to_le_bytes().to_vec(),
+        (DataType::U32, Value::U32(v)) => v.to_le_bytes().to_vec(),
+        (DataType::U64, Value::U64(v)) => v.to_le_bytes().to_vec(),
+        (DataType::F32, Value::F32(v)) => v.to_le_bytes().to_vec(),
+        (DataType::F64, Value::F64(v)) => v.to_le_bytes().to_vec(),
+        (DataType::String, Value::String(v)) => v.as_bytes().to_vec(),
+        (DataType::U8Array, Value::U8Array(v)) => v.clone(),
+        (DataType::U16Array, Value::U16Array(v)) => {
+            v.iter().flat_map(|x| x.to_le_bytes()).collect()
+        }
+        (_, Value::Raw(v)) => v.clone(),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{:?} does not match data type {:?}", value, dt),
+            ))
+        }
+    };
+    Ok(bytes)
+}
+
+/// An integer value and whether its type is signed.
+fn integer(value: &Value) -> Option<(i128, bool)> {
+    let integer = match *value {
+        Value::U8(v) | Value::Byte(v) => (i128::from(v), false),
+        Value::U16(v) => (i128::from(v), false),
+        Value::U32(v) => (i128::from(v), false),
+        Value::U64(v) => (i128::from(v), false),
+        Value::I8(v) => (i128::from(v), true),
+        Value::I16(v) => (i128::from(v), true),
+        Value::I32(v) => (i128::from(v), true),
+        Value::I64(v) => (i128::from(v), true),
+        _ => return None,
+    };
+    Some(integer)
+}
+
+/// Copy `len` bits starting at bit `pos` (LSB first).
+fn get_bits(data: &[u8], pos: usize, len: usize) -> Result<Vec<u8>> {
+    check_len(data, pos, len)?;
+    if pos % 8 == 0 && len % 8 == 0 {
+        return Ok(data[pos / 8..(pos + len) / 8].to_vec());
+    }
+    let mut bytes = vec![0; (len + 7) / 8];
+    for i in 0..len {
+        let bit = (data[(pos + i) / 8] >> ((pos + i) % 8)) & 1;
+        bytes[i / 8] |= bit << (i % 8);
+    }
+    Ok(bytes)
+}
+
+/// Overwrite `len` bits starting at bit `pos` (LSB first).
+///
+/// Missing source bits are written as zero.
+fn set_bits(data: &mut [u8], pos: usize, len: usize, src: &[u8]) -> Result<()> {
+    check_len(data, pos, len)?;
+    for i in 0..len {
+        let bit = src.get(i / 8).map(|b| (b >> (i % 8)) & 1).unwrap_or(0);
+        let byte = &mut data[(pos + i) / 8];
+        let mask = 1 << ((pos + i) % 8);
+        if bit == 1 {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+    Ok(())
+}
+
+fn check_len(data: &[u8], pos: usize, len: usize) -> Result<()> {
+    if (pos + len + 7) / 8 > data.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Process data of {} bytes is too short for {} bits at bit offset {}",
+                data.len(),
+                len,
+                pos
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethercat_types::{self as ec, Offset};
+
+    fn entry(idx: u16, name: &str, byte: usize, bit: u32, bit_len: usize, dt: &str) -> EntryLayout {
+        EntryLayout {
+            pdo_idx: PdoIdx::from(0x1A00),
+            entry_idx: PdoEntryIdx::new(idx, 1),
+            name: LocalizedText::from(name),
+            data_type: Some(dt.to_string()),
+            bit_len,
+            direction: Direction::Input,
+            sm_offset: Offset { byte, bit },
+            offset: Offset { byte, bit },
+        }
+    }
+
+    fn codec(direction: Direction) -> ProcessImageCodec {
+        let mut entries = vec![
+            entry(0x6000, "Flag", 0, 0, 1, "BOOL"),
+            entry(0x6001, "Bits", 0, 1, 2, "BIT2"),
+            entry(0x6002, "Temperature", 0, 3, 16, "INT"),
+            entry(0x6003, "Counter", 3, 0, 32, "UDINT"),
+            entry(0x6004, "Value", 7, 0, 32, "REAL"),
+            entry(0x6005, "Text", 11, 0, 32, "STRING(4)"),
+            entry(0x6006, "Offset", 15, 0, 12, "INT"),
+        ];
+        for e in &mut entries {
+            e.direction = direction;
+        }
+        ProcessImageCodec::from_layout(ProcessImageLayout {
+            sms: vec![SmLayout {
+                sm: ec::SmIdx::from(3),
+                direction,
+                pdos: vec![PdoIdx::from(0x1A00)],
+                offset: 0,
+                size: 17,
+                entries,
+            }],
+            output_size: 0,
+            input_size: 17,
+        })
+    }
+
+    #[test]
+    fn read_inputs() {
+        let c = codec(Direction::Input);
+        let mut inputs = [0u8; 17];
+        inputs[0] = 0b1110_0101;
+        inputs[1] = 0b1111_1111;
+        inputs[2] = 0b0000_0111;
+        inputs[3..7].copy_from_slice(&0xDEAD_BEEF_u32.to_le_bytes());
+        inputs[7..11].copy_from_slice(&1.5_f32.to_le_bytes());
+        inputs[11..15].copy_from_slice(b"ab\0\0");
+
+        assert_eq!(
+            c.read(&inputs, PdoEntryIdx::new(0x6000, 1)).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(c.read_by_name(&inputs, "Bits").unwrap(), Value::U8(0b10));
+        // 0b1111_1111_1111_1100 (bits 3..18)
+        assert_eq!(
+            c.read_by_name(&inputs, "Temperature").unwrap(),
+            Value::I16(-4)
+        );
+        assert_eq!(
+            c.read_by_name(&inputs, "Counter").unwrap(),
+            Value::U32(0xDEAD_BEEF)
+        );
+        assert_eq!(c.read_by_name(&inputs, "Value").unwrap(), Value::F32(1.5));
+        assert_eq!(
+            c.read_by_name(&inputs, "Text").unwrap(),
+            Value::String("ab".to_string())
+        );
+        assert_eq!(c.read_by_name(&inputs, "Offset").unwrap(), Value::I16(0));
+        inputs[15..17].copy_from_slice(&0x0FFE_u16.to_le_bytes());
+        assert_eq!(c.read_by_name(&inputs, "Offset").unwrap(), Value::I16(-2));
+        inputs[15..17].copy_from_slice(&0x07FF_u16.to_le_bytes());
+        assert_eq!(c.read_by_name(&inputs, "Offset").unwrap(), Value::I16(2047));
+        assert!(c.read_by_name(&inputs, "Foo").is_err());
+        assert!(c.read_by_name(&inputs[..10], "Text").is_err());
+    }
+
+    #[test]
+    fn write_outputs() {
+        let c = codec(Direction::Output);
+        let mut outputs = [0xFF_u8; 17];
+        c.write_by_name(&mut outputs, "Flag", &Value::Bool(false))
+            .unwrap();
+        c.write_by_name(&mut outputs, "Bits", &Value::U8(0b01))
+            .unwrap();
+        c.write_by_name(&mut outputs, "Temperature", &Value::I16(-4))
+            .unwrap();
+        c.write(&mut outputs, PdoEntryIdx::new(0x6003, 1), &Value::U32(7))
+            .unwrap();
+        c.write_by_name(&mut outputs, "Text", &Value::String("x".to_string()))
+            .unwrap();
+
+        assert_eq!(outputs[0], 0b1110_0010);
+        assert_eq!(outputs[1], 0b1111_1111);
+        assert_eq!(outputs[2], 0b1111_1111);
+        assert_eq!(outputs[3..7], 7_u32.to_le_bytes());
+        assert_eq!(&outputs[11..15], b"x\0\0\0");
+
+        c.write_by_name(&mut outputs, "Offset", &Value::I16(-2))
+            .unwrap();
+        assert_eq!(outputs[15..17], [0xFE, 0xFF]);
+        c.write_by_name(&mut outputs, "Offset", &Value::I16(-2048))
+            .unwrap();
+        assert_eq!(outputs[15..17], [0x00, 0xF8]);
+
+        assert!(c
+            .write_by_name(&mut outputs, "Counter", &Value::I16(1))
+            .is_err());
+        assert!(c
+            .write_by_name(&mut outputs, "Text", &Value::String("abcde".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn reject_values_out_of_range() {
+        let c = codec(Direction::Output);
+        let mut outputs = [0_u8; 17];
+        for (name, value) in &[
+            ("Bits", Value::U8(4)),
+            ("Offset", Value::I16(2048)),
+            ("Offset", Value::I16(-2049)),
+        ] {
+            let err = c.write_by_name(&mut outputs, name, value).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+        assert_eq!(outputs, [0; 17]);
+        c.write_by_name(&mut outputs, "Bits", &Value::U8(3))
+            .unwrap();
+        assert_eq!(outputs[0], 0b0000_0110);
+    }
+}
@@ -0,0 +1,622 @@
+//! Generation of Rust code for process data.
+
+use crate::{codec::data_type, layout::*, structs::*, EtherCatInfo};
+use ethercat_types::DataType;
+use std::{
+    fmt::{self, Write},
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Generate process data structs for a device of an ESI file.
+///
+/// This is meant to be called from a build script, e.g.
+///
+/// ```rust,no_run
+/// use ethercat_esi::{generate_process_data, PdoIdx};
+/// use std::{env, fs, path::Path};
+///
+/// let pdos = [PdoIdx::from(0x1600), PdoIdx::from(0x1A00)];
+/// let code = generate_process_data("esi/Device.xml", 0x0001_2345, &pdos).unwrap();
+/// let out = Path::new(&env::var("OUT_DIR").unwrap()).join("process_data.rs");
+/// fs::write(out, code).unwrap();
+/// println!("cargo:rerun-if-changed=esi/Device.xml");
+/// ```
+///
+/// If the file describes several revisions of the product,
+/// the latest one is used.
+/// See [`Device::process_data_code`] for the generated code.
+pub fn generate_process_data(
+    path: impl AsRef<Path>,
+    product_code: u32,
+    pdos: &[PdoIdx],
+) -> Result<String> {
+    let xml = fs::read_to_string(path)?;
+    let info = EtherCatInfo::from_xml_str(&xml)?;
+    let device = info
+        .description
+        .devices
+        .iter()
+        .filter(|d| d.product_code == Some(product_code))
+        .max_by_key(|d| d.revision_no)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("Device with product code 0x{:08X} not found", product_code),
+            )
+        })?;
+    device.process_data_code(pdos)
+}
+
+impl Device {
+    /// Generate Rust code for the process data of the given PDOs.
+    ///
+    /// The code contains an `Outputs` and an `Inputs` struct that wrap
+    /// the bytes of the respective area (see [`Device::process_image_layout`]).
+    /// Each PDO entry gets a getter and, for outputs, a setter that is
+    /// named after the entry. `BOOL` and `BITn` entries are accessed as
+    /// bit fields, entries that don't fit into an integer as byte arrays.
+    pub fn process_data_code(&self, pdos: &[PdoIdx]) -> Result<String> {
+        let layout = self.process_image_layout(pdos)?;
+        let mut code = String::new();
+        writeln!(
+            code,
+            "// Generated by ethercat-esi from device `{}`. Do not edit.",
+            self.desc
+        )
+        .map_err(|e| Error::new(ErrorKind::Other, e))?;
+        for direction in [Direction::Output, Direction::Input] {
+            let accessors = layout
+                .entries(direction)
+                .map(|e| Accessor::new(self, e))
+                .collect::<Result<Vec<_>>>()?;
+            write_struct(&mut code, self, &layout, direction, accessors)
+                .map_err(|e| Error::new(ErrorKind::Other, e))?;
+        }
+        Ok(code)
+    }
+}
+
+/// Rust type of an entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Bool,
+    Unsigned(usize),
+    Signed(usize),
+    Float(usize),
+    Bytes(usize),
+}
+
+impl Kind {
+    fn of(e: &EntryLayout) -> Result<Self> {
+        use DataType as D;
+        let kind = match data_type(e) {
+            D::Bool | D::Bit1 if e.bit_len == 1 => Kind::Bool,
+            D::Bit2 | D::Bit3 | D::Bit4 | D::Bit5 | D::Bit6 | D::Bit7 | D::Bit8 | D::Byte => {
+                Kind::Unsigned(8)
+            }
+            D::U8 => Kind::Unsigned(8),
+            D::U16 => Kind::Unsigned(16),
+            D::U24 | D::U32 => Kind::Unsigned(32),
+            D::U40 | D::U48 | D::U56 | D::U64 => Kind::Unsigned(64),
+            D::I8 => Kind::Signed(8),
+            D::I16 => Kind::Signed(16),
+            D::I24 | D::I32 => Kind::Signed(32),
+            D::I40 | D::I48 | D::I56 | D::I64 => Kind::Signed(64),
+            D::F32 => Kind::Float(32),
+            D::F64 => Kind::Float(64),
+            _ if e.bit_len % 8 == 0 && e.bit_len > 64 => Kind::Bytes(e.bit_len / 8),
+            _ => match e.bit_len {
+                1..=8 => Kind::Unsigned(8),
+                9..=16 => Kind::Unsigned(16),
+                17..=32 => Kind::Unsigned(32),
+                33..=64 => Kind::Unsigned(64),
+                _ => Kind::Bytes((e.bit_len + 7) / 8),
+            },
+        };
+        let fits = match kind {
+            Kind::Bool => true,
+            Kind::Unsigned(w) | Kind::Signed(w) => e.bit_len <= w,
+            Kind::Float(w) => e.bit_len == w,
+            Kind::Bytes(n) => e.bit_len == n * 8 && e.offset.bit == 0,
+        };
+        if !fits {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Entry {} with {} bits can't be represented as {}",
+                    entry_idx(e),
+                    e.bit_len,
+                    kind.rust_type()
+                ),
+            ));
+        }
+        Ok(kind)
+    }
+
+    fn rust_type(self) -> String {
+        match self {
+            Kind::Bool => "bool".to_string(),
+            Kind::Unsigned(w) => format!("u{}", w),
+            Kind::Signed(w) => format!("i{}", w),
+            Kind::Float(w) => format!("f{}", w),
+            Kind::Bytes(n) => format!("[u8; {}]", n),
+        }
+    }
+}
+
+/// Getter and setter of a single entry.
+struct Accessor {
+    name: String,
+    doc: Vec<String>,
+    kind: Kind,
+    getter: Vec<String>,
+    setter: Vec<String>,
+}
+
+impl Accessor {
+    fn new(device: &Device, e: &EntryLayout) -> Result<Self> {
+        let kind = Kind::of(e)?;
+        let (getter, setter) = access_code(e, kind)?;
+        let name = name(&e.name);
+        let mut doc = vec![];
+        if !name.is_empty() {
+            doc.push(single_line(&name));
+            doc.push(String::new());
+        }
+        let pdo = device
+            .rx_pdo
+            .iter()
+            .chain(device.tx_pdo.iter())
+            .find(|p| p.idx == e.pdo_idx);
+        let pdo_name = pdo.and_then(|p| p.name.default_text()).unwrap_or_default();
+        let mut info = format!("`{}` of PDO `0x{:04X}`", entry_idx(e), u16::from(e.pdo_idx));
+        if !pdo_name.is_empty() {
+            info.push_str(&format!(" ({})", single_line(pdo_name)));
+        }
+        if let Some(dt) = &e.data_type {
+            info.push_str(&format!(", `{}`", dt));
+        }
+        info.push_str(&format!(
+            ", {} bit(s) at offset {}.{}",
+            e.bit_len, e.offset.byte, e.offset.bit
+        ));
+        doc.push(info);
+        Ok(Accessor {
+            name: identifier(&name),
+            doc,
+            kind,
+            getter,
+            setter,
+        })
+    }
+}
+
+fn write_struct(
+    code: &mut String,
+    device: &Device,
+    layout: &ProcessImageLayout,
+    direction: Direction,
+    mut accessors: Vec<Accessor>,
+) -> fmt::Result {
+    let (struct_name, size) = match direction {
+        Direction::Output => ("Outputs", layout.output_size),
+        Direction::Input => ("Inputs", layout.input_size),
+    };
+    let pdos: Vec<_> = layout
+        .sms
+        .iter()
+        .filter(|sm| sm.direction == direction)
+        .flat_map(|sm| sm.pdos.iter())
+        .map(|idx| format!("`0x{:04X}`", u16::from(*idx)))
+        .collect();
+
+    disambiguate(&mut accessors, layout, direction);
+
+    writeln!(code)?;
+    writeln!(code, "/// {} of `{}`.", struct_name, device.desc)?;
+    let device_name = device.name.default_text().unwrap_or_default();
+    if !device_name.is_empty() {
+        writeln!(code, "///")?;
+        writeln!(code, "/// {}", single_line(device_name))?;
+    }
+    if !pdos.is_empty() {
+        writeln!(code, "///")?;
+        writeln!(code, "/// PDOs: {}", pdos.join(", "))?;
+    }
+    // `Default` is only implemented for arrays of up to 32 elements.
+    if size <= 32 {
+        writeln!(
+            code,
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]"
+        )?;
+        writeln!(code, "pub struct {}(pub [u8; {}]);", struct_name, size)?;
+    } else {
+        writeln!(code, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]")?;
+        writeln!(code, "pub struct {}(pub [u8; {}]);", struct_name, size)?;
+        writeln!(code)?;
+        writeln!(code, "impl Default for {} {{", struct_name)?;
+        writeln!(code, "    fn default() -> Self {{")?;
+        writeln!(code, "        Self([0; {}])", size)?;
+        writeln!(code, "    }}")?;
+        writeln!(code, "}}")?;
+    }
+    writeln!(code)?;
+    writeln!(code, "impl {} {{", struct_name)?;
+    writeln!(code, "    /// Size in bytes.")?;
+    write!(code, "    pub const SIZE: usize = {};", size)?;
+    for a in &accessors {
+        let ty = a.kind.rust_type();
+        writeln!(code)?;
+        writeln!(code)?;
+        for line in &a.doc {
+            writeln!(
+                code,
+                "    ///{}{}",
+                if line.is_empty() { "" } else { " " },
+                line
+            )?;
+        }
+        writeln!(code, "    pub fn {}(&self) -> {} {{", a.name, ty)?;
+        for line in &a.getter {
+            writeln!(code, "        {}", line)?;
+        }
+        write!(code, "    }}")?;
+        if direction == Direction::Output {
+            writeln!(code)?;
+            writeln!(code)?;
+            writeln!(code, "    /// Set `{}`.", a.name.trim_end_matches('_'))?;
+            writeln!(
+                code,
+                "    pub fn {}(&mut self, v: {}) {{",
+                setter_name(&a.name),
+                ty
+            )?;
+            for line in &a.setter {
+                writeln!(code, "        {}", line)?;
+            }
+            write!(code, "    }}")?;
+        }
+    }
+    writeln!(code)?;
+    writeln!(code, "}}")
+}
+
+/// Append the entry index to names whose getter or setter has the same
+/// name as another method (e.g. the getter of `Set X` and the setter of `X`).
+fn disambiguate(accessors: &mut [Accessor], layout: &ProcessImageLayout, direction: Direction) {
+    let methods = |name: &str| {
+        let mut methods = vec![name.to_string()];
+        if direction == Direction::Output {
+            methods.push(setter_name(name));
+        }
+        methods
+    };
+    let entries: Vec<_> = layout.entries(direction).collect();
+    let all: Vec<_> = accessors.iter().flat_map(|a| methods(&a.name)).collect();
+    for (a, e) in accessors.iter_mut().zip(entries) {
+        let ambiguous = methods(&a.name)
+            .iter()
+            .any(|m| all.iter().filter(|n| *n == m).count() > 1);
+        if a.name.is_empty() || ambiguous {
+            let idx = format!(
+                "{:04x}_{:02x}",
+                u16::from(e.entry_idx.idx),
+                u8::from(e.entry_idx.sub_idx)
+            );
+            a.name = if a.name.is_empty() {
+                format!("entry_{}", idx)
+            } else {
+                format!("{}_{}", a.name.trim_end_matches('_'), idx)
+            };
+        }
+    }
+}
+
+fn setter_name(name: &str) -> String {
+    format!("set_{}", name.trim_end_matches('_'))
+}
+
+/// Statements of the getter and the setter.
+fn access_code(e: &EntryLayout, kind: Kind) -> Result<(Vec<String>, Vec<String>)> {
+    let byte = e.offset.byte;
+    let bit = e.offset.bit as usize;
+    let len = e.bit_len;
+
+    if kind == Kind::Bool {
+        let mask = format!("0x{:02X}", 1 << bit);
+        return Ok((
+            vec![format!("self.0[{}] & {} != 0", byte, mask)],
+            vec![
+                "if v {".to_string(),
+                format!("    self.0[{}] |= {};", byte, mask),
+                "} else {".to_string(),
+                format!("    self.0[{}] &= !{};", byte, mask),
+                "}".to_string(),
+            ],
+        ));
+    }
+    if let Kind::Bytes(n) = kind {
+        let range = format!("{}..{}", byte, byte + n);
+        return Ok((
+            vec![
+                format!("let mut v = [0; {}];", n),
+                format!("v.copy_from_slice(&self.0[{}]);", range),
+                "v".to_string(),
+            ],
+            vec![format!("self.0[{}].copy_from_slice(&v);", range)],
+        ));
+    }
+
+    let ty = kind.rust_type();
+    let width = match kind {
+        Kind::Unsigned(w) | Kind::Signed(w) | Kind::Float(w) => w,
+        _ => unreachable!(),
+    };
+
+    // Byte aligned values of the full width
+    if bit == 0 && len == width {
+        if width == 8 {
+            return Ok(match kind {
+                Kind::Signed(_) => (
+                    vec![format!("self.0[{}] as i8", byte)],
+                    vec![format!("self.0[{}] = v as u8;", byte)],
+                ),
+                _ => (
+                    vec![format!("self.0[{}]", byte)],
+                    vec![format!("self.0[{}] = v;", byte)],
+                ),
+            });
+        }
+        let bytes: Vec<_> = (byte..byte + width / 8)
+            .map(|i| format!("self.0[{}]", i))
+            .collect();
+        return Ok((
+            vec![format!("{}::from_le_bytes([{}])", ty, bytes.join(", "))],
+            vec![format!(
+                "self.0[{}..{}].copy_from_slice(&v.to_le_bytes());",
+                byte,
+                byte + width / 8
+            )],
+        ));
+    }
+
+    // Everything else is read from and written to an `u64`
+    if bit + len > 64 {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Entry {} with {} bits at bit offset {} is not supported",
+                entry_idx(e),
+                len,
+                bit
+            ),
+        ));
+    }
+    let n = (bit + len + 7) / 8;
+    let range = format!("{}..{}", byte, byte + n);
+    let mask = if len == 64 {
+        "u64::MAX".to_string()
+    } else {
+        format!("0x{:X}", (1_u64 << len) - 1)
+    };
+    let shifted = |x: &str| {
+        if bit == 0 {
+            x.to_string()
+        } else {
+            format!("({} << {})", x, bit)
+        }
+    };
+    let load = vec![
+        "let mut buf = [0; 8];".to_string(),
+        format!("buf[..{}].copy_from_slice(&self.0[{}]);", n, range),
+    ];
+
+    let raw = if bit == 0 {
+        format!("u64::from_le_bytes(buf) & {}", mask)
+    } else {
+        format!("(u64::from_le_bytes(buf) >> {}) & {}", bit, mask)
+    };
+    let mut getter = load.clone();
+    getter.push(format!("let raw = {};", raw));
+    getter.push(match kind {
+        Kind::Unsigned(64) => "raw".to_string(),
+        Kind::Unsigned(_) => format!("raw as {}", ty),
+        Kind::Signed(w) => {
+            let extended = format!("((raw << {0}) as i64 >> {0})", 64 - len);
+            if w == 64 {
+                extended
+            } else {
+                format!("{} as {}", extended, ty)
+            }
+        }
+        Kind::Float(32) => "f32::from_bits(raw as u32)".to_string(),
+        _ => "f64::from_bits(raw)".to_string(),
+    });
+
+    let value = match kind {
+        Kind::Unsigned(64) => "v".to_string(),
+        Kind::Unsigned(_) => "u64::from(v)".to_string(),
+        Kind::Signed(_) => "v as u64".to_string(),
+        Kind::Float(32) => "u64::from(v.to_bits())".to_string(),
+        _ => "v.to_bits()".to_string(),
+    };
+    let mut setter = load;
+    setter.push(format!(
+        "let raw = (u64::from_le_bytes(buf) & !{}) | {};",
+        shifted(&mask),
+        shifted(&format!("({} & {})", value, mask))
+    ));
+    setter.push(format!(
+        "self.0[{}].copy_from_slice(&raw.to_le_bytes()[..{}]);",
+        range, n
+    ));
+    Ok((getter, setter))
+}
+
+fn entry_idx(e: &EntryLayout) -> String {
+    format!(
+        "0x{:04X}:{:02X}",
+        u16::from(e.entry_idx.idx),
+        u8::from(e.entry_idx.sub_idx)
+    )
+}
+
+fn name(names: &LocalizedText) -> String {
+    names.default_text().unwrap_or_default().trim().to_string()
+}
+
+fn single_line(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Convert a name like `Status Word` or `TxPDO Toggle` into a snake case
+/// identifier (`status_word`, `tx_pdo_toggle`).
+fn identifier(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut id = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !id.is_empty() && !id.ends_with('_') {
+                id.push('_');
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 && !id.is_empty() && !id.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower)
+            {
+                id.push('_');
+            }
+        }
+        id.push(c.to_ascii_lowercase());
+    }
+    let id = id.trim_end_matches('_').to_string();
+    if id.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", id)
+    } else if KEYWORDS.contains(&id.as_str()) {
+        format!("{}_", id)
+    } else {
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, sm};
+
+    fn entry(idx: u16, name: &str, bit_len: usize, dt: &str) -> PdoEntry {
+        PdoEntry {
+            name: LocalizedText::from(name),
+            data_type: Some(dt.to_string()),
+            ..fixtures::entry(idx, 1, bit_len)
+        }
+    }
+
+    fn pdo(idx: u16, sm: u8, entries: Vec<PdoEntry>) -> Pdo {
+        Pdo {
+            name: LocalizedText::from("Channel 1"),
+            ..fixtures::pdo(idx, sm, entries)
+        }
+    }
+
+    fn device() -> Device {
+        Device {
+            name: vec![("Terminal".to_string(), Some(1033))].into(),
+            desc: "EL0000".to_string(),
+            product_code: Some(1),
+            sm: vec![sm(0x1000, None); 4],
+            rx_pdo: vec![pdo(
+                0x1600,
+                2,
+                vec![
+                    entry(0x7000, "Enable", 1, "BOOL"),
+                    entry(0x7001, "Mode", 3, "BIT3"),
+                    entry(0x7002, "Set Value", 16, "INT"),
+                    entry(0, "", 4, ""),
+                ],
+            )],
+            tx_pdo: vec![pdo(
+                0x1A00,
+                3,
+                vec![
+                    entry(0x6000, "Status", 16, "UINT"),
+                    entry(0x6001, "Value", 32, "REAL"),
+                    entry(0x6002, "Value", 32, "DINT"),
+                ],
+            )],
+            ..Device::default()
+        }
+    }
+
+    #[test]
+    fn snake_case_identifiers() {
+        assert_eq!(identifier("Status Word"), "status_word");
+        assert_eq!(identifier("TxPDO Toggle"), "tx_pdo_toggle");
+        assert_eq!(identifier("Channel 1.Value"), "channel_1_value");
+        assert_eq!(identifier("AI Standard Ch.1"), "ai_standard_ch_1");
+        assert_eq!(identifier("1st"), "_1st");
+        assert_eq!(identifier("Type"), "type_");
+    }
+
+    #[test]
+    fn generate_structs() {
+        let pdos = [0x1600, 0x1A00].map(PdoIdx::from);
+        let code = device().process_data_code(&pdos).unwrap();
+
+        assert!(code.contains("pub struct Outputs(pub [u8; 3]);"));
+        assert!(code.contains("pub struct Inputs(pub [u8; 10]);"));
+        assert!(code.contains("/// PDOs: `0x1600`"));
+        assert!(code.contains(
+            "    /// Enable\n    ///\n    /// `0x7000:01` of PDO `0x1600` (Channel 1), `BOOL`, 1 bit(s) at offset 0.0\n    pub fn enable(&self) -> bool {\n        self.0[0] & 0x01 != 0\n"
+        ));
+        assert!(code.contains("pub fn set_enable(&mut self, v: bool) {"));
+        assert!(code.contains("pub fn mode(&self) -> u8 {"));
+        assert!(code.contains("let raw = (u64::from_le_bytes(buf) >> 1) & 0x7;"));
+        assert!(code.contains("pub fn set_set_value(&mut self, v: i16) {"));
+        assert!(code.contains("((raw << 48) as i64 >> 48) as i16"));
+        assert!(code.contains("u16::from_le_bytes([self.0[0], self.0[1]])"));
+        assert!(code.contains("pub fn value_6001_01(&self) -> f32 {"));
+        assert!(code.contains("pub fn value_6002_01(&self) -> i32 {"));
+        assert!(!code.contains("set_status"));
+    }
+
+    #[test]
+    fn disambiguate_getters_and_setters() {
+        let mut d = device();
+        d.rx_pdo[0].entries[0].name = LocalizedText::from("Value");
+        d.rx_pdo[0].entries[1].name = LocalizedText::from("Set Value");
+        d.rx_pdo[0].entries[2].name = LocalizedText::from("Type");
+        let code = d.process_data_code(&[PdoIdx::from(0x1600)]).unwrap();
+        for method in &[
+            "value_7000_01(&self)",
+            "set_value_7000_01(&mut self",
+            "set_value_7001_01(&self)",
+            "set_set_value_7001_01(&mut self",
+            "type_(&self)",
+            "set_type(&mut self",
+        ] {
+            assert!(code.contains(&format!("pub fn {}", method)), "{}", method);
+        }
+    }
+
+    #[test]
+    fn unsupported_entries() {
+        let mut d = device();
+        d.tx_pdo[0].entries[1].bit_len = 16;
+        assert!(d.process_data_code(&[PdoIdx::from(0x1A00)]).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+//! Slave configuration in terms of `ethercat-types`.
+//!
+//! `ethercat-types` has no structure for sync managers,
+//! so [`SmConfig`] is defined here.
+
+use crate::{layout::*, structs::*};
+use ethercat_types::{self as ec, PdoEntryPos, PdoPos};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Result},
+};
+
+/// Sync manager with its assigned PDOs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmConfig {
+    pub idx: SmIdx,
+    pub direction: Direction,
+    /// Watchdog mode according to the control byte (`None` means default).
+    pub watchdog: Option<bool>,
+    pub pdos: Vec<PdoConfig>,
+}
+
+/// PDO with its mapped entries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdoConfig {
+    pub info: ec::PdoInfo,
+    pub entries: Vec<ec::PdoEntryInfo>,
+}
+
+impl Device {
+    /// PDOs that are assigned to a sync manager by the ESI.
+    pub fn assigned_pdos(&self) -> Vec<PdoIdx> {
+        self.rx_pdo
+            .iter()
+            .chain(self.tx_pdo.iter())
+            .filter(|p| p.sm.is_some())
+            .map(|p| p.idx)
+            .collect()
+    }
+
+    /// Sync manager configuration of the PDOs assigned by the ESI
+    /// (see [`Device::assigned_pdos`]).
+    pub fn sync_manager_config(&self) -> Result<Vec<SmConfig>> {
+        self.sync_manager_config_for(&self.assigned_pdos())
+    }
+
+    /// Sync manager configuration of the given PDOs.
+    ///
+    /// Only sync managers with assigned PDOs are included.
+    /// The PDOs are assigned as in [`Device::process_image_layout`].
+    pub fn sync_manager_config_for(&self, pdos: &[PdoIdx]) -> Result<Vec<SmConfig>> {
+        let layout = self.process_image_layout(pdos)?;
+        layout
+            .sms
+            .iter()
+            .map(|l| {
+                let sm = &self.sm[usize::from(u8::from(l.sm))];
+                let pdos = l
+                    .pdos
+                    .iter()
+                    .enumerate()
+                    .map(|(pos, idx)| {
+                        let pdo = self
+                            .rx_pdo
+                            .iter()
+                            .chain(self.tx_pdo.iter())
+                            .find(|p| p.idx == *idx)
+                            .expect("PDO of the layout");
+                        let mut cfg = PdoConfig::try_from(pdo)?;
+                        cfg.info.pos = PdoPos::from(u8::try_from(pos).map_err(|_| {
+                            Error::new(
+                                ErrorKind::InvalidData,
+                                format!("Too many PDOs assigned to SM {}", u8::from(l.sm)),
+                            )
+                        })?);
+                        Ok(cfg)
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(SmConfig {
+                    idx: l.sm,
+                    direction: sm.direction().unwrap_or(l.direction),
+                    watchdog: sm.watchdog(),
+                    pdos,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The position of the PDO is set to 0.
+impl TryFrom<&Pdo> for ec::PdoInfo {
+    type Error = Error;
+    fn try_from(pdo: &Pdo) -> Result<Self> {
+        let sm = pdo.sm.ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "PDO 0x{:04X} is not assigned to a sync manager",
+                    u16::from(pdo.idx)
+                ),
+            )
+        })?;
+        let entry_count = u8::try_from(pdo.entries.len()).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("PDO 0x{:04X} has too many entries", u16::from(pdo.idx)),
+            )
+        })?;
+        Ok(ec::PdoInfo {
+            sm,
+            pos: PdoPos::from(0),
+            idx: ec::Idx::from(u16::from(pdo.idx)),
+            entry_count,
+            name: pdo.name.default_text().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// The position of the entry is set to 0.
+impl TryFrom<&PdoEntry> for ec::PdoEntryInfo {
+    type Error = Error;
+    fn try_from(e: &PdoEntry) -> Result<Self> {
+        let bit_len = u8::try_from(e.bit_len).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Bit length {} of entry 0x{:04X}:{:02X} is too large",
+                    e.bit_len,
+                    u16::from(e.entry_idx.idx),
+                    u8::from(e.entry_idx.sub_idx)
+                ),
+            )
+        })?;
+        Ok(ec::PdoEntryInfo {
+            pos: PdoEntryPos::from(0),
+            entry_idx: e.entry_idx,
+            bit_len,
+            name: e.name.default_text().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+impl TryFrom<&Pdo> for PdoConfig {
+    type Error = Error;
+    fn try_from(pdo: &Pdo) -> Result<Self> {
+        let entries = pdo.entries.iter().enumerate().map(|(pos, e)| {
+            let mut info = ec::PdoEntryInfo::try_from(e)?;
+            // `pos` fits because `PdoInfo::entry_count` does
+            info.pos = PdoEntryPos::from(pos as u8);
+            Ok(info)
+        });
+        let info = ec::PdoInfo::try_from(pdo)?;
+        Ok(PdoConfig {
+            info,
+            entries: entries.collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, sm};
+
+    fn entry(idx: u16, sub_idx: u8, bit_len: usize) -> PdoEntry {
+        PdoEntry {
+            name: LocalizedText::from("Value"),
+            ..fixtures::entry(idx, sub_idx, bit_len)
+        }
+    }
+
+    fn pdo(idx: u16, sm: Option<u8>, entries: Vec<PdoEntry>) -> Pdo {
+        Pdo {
+            sm: sm.map(SmIdx::from),
+            name: LocalizedText::from("Channel"),
+            ..fixtures::pdo(idx, 0, entries)
+        }
+    }
+
+    fn device() -> Device {
+        Device {
+            desc: "Foo".to_string(),
+            sm: vec![
+                sm(0x1000, Some(0x26)),
+                sm(0x1000, Some(0x22)),
+                sm(0x1000, Some(0x64)),
+                sm(0x1000, Some(0x20)),
+            ],
+            rx_pdo: vec![pdo(0x1600, Some(2), vec![entry(0x7000, 1, 16)])],
+            tx_pdo: vec![
+                pdo(0x1A00, Some(3), vec![entry(0x6000, 1, 8), entry(0, 0, 8)]),
+                pdo(0x1A01, Some(3), vec![entry(0x6010, 1, 32)]),
+                pdo(0x1A02, None, vec![entry(0x6020, 1, 32)]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pdo_info_from_pdo() {
+        let d = device();
+        let info = ec::PdoInfo::try_from(&d.rx_pdo[0]).unwrap();
+        assert_eq!(info.sm, SmIdx::from(2));
+        assert_eq!(info.idx, ec::Idx::from(0x1600));
+        assert_eq!(info.entry_count, 1);
+        assert_eq!(info.name, "Channel");
+        assert!(ec::PdoInfo::try_from(&d.tx_pdo[2]).is_err());
+        assert!(ec::PdoEntryInfo::try_from(&entry(0x6000, 1, 256)).is_err());
+    }
+
+    #[test]
+    fn sync_manager_config() {
+        let d = device();
+        assert_eq!(d.assigned_pdos().len(), 3);
+        let cfg = d.sync_manager_config().unwrap();
+        assert_eq!(cfg.len(), 2);
+        assert_eq!(cfg[0].idx, SmIdx::from(2));
+        assert_eq!(cfg[0].direction, Direction::Output);
+        assert_eq!(cfg[0].watchdog, Some(true));
+        assert_eq!(cfg[1].direction, Direction::Input);
+        assert_eq!(cfg[1].watchdog, Some(false));
+        assert_eq!(cfg[1].pdos.len(), 2);
+        assert_eq!(cfg[1].pdos[1].info.pos, PdoPos::from(1));
+        assert_eq!(cfg[1].pdos[0].entries[1].pos, PdoEntryPos::from(1));
+        assert_eq!(
+            cfg[1].pdos[0].entries[1].entry_idx,
+            ec::PdoEntryIdx::new(0, 0)
+        );
+    }
+}
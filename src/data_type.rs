@@ -0,0 +1,131 @@
+use ethercat_types::DataType;
+
+/// Parse an ESI data type name (e.g. `UINT`, `BIT2` or `STRING(8)`).
+pub(crate) fn data_type_from_str(name: &str) -> Option<DataType> {
+    let name = name.trim();
+    if array_len(name).is_some() {
+        return Some(DataType::U8Array);
+    }
+    if name.starts_with("STRING(") {
+        return Some(DataType::String);
+    }
+    let dt = match name {
+        "BOOL" | "BIT" => DataType::Bool,
+        "BIT1" => DataType::Bit1,
+        "BIT2" => DataType::Bit2,
+        "BIT3" => DataType::Bit3,
+        "BIT4" => DataType::Bit4,
+        "BIT5" => DataType::Bit5,
+        "BIT6" => DataType::Bit6,
+        "BIT7" => DataType::Bit7,
+        "BIT8" => DataType::Bit8,
+        "BYTE" => DataType::Byte,
+        "SINT" | "INT8" => DataType::I8,
+        "INT" | "INT16" => DataType::I16,
+        "INT24" => DataType::I24,
+        "DINT" | "INT32" => DataType::I32,
+        "INT40" => DataType::I40,
+        "INT48" => DataType::I48,
+        "INT56" => DataType::I56,
+        "LINT" | "INT64" => DataType::I64,
+        "USINT" | "UINT8" => DataType::U8,
+        "UINT" | "WORD" | "UINT16" => DataType::U16,
+        "UINT24" => DataType::U24,
+        "UDINT" | "DWORD" | "UINT32" => DataType::U32,
+        "UINT40" => DataType::U40,
+        "UINT48" => DataType::U48,
+        "UINT56" => DataType::U56,
+        "ULINT" | "LWORD" | "UINT64" => DataType::U64,
+        "REAL" | "FLOAT" => DataType::F32,
+        "LREAL" | "DOUBLE" => DataType::F64,
+        "TIME_OF_DAY" => DataType::TimeOfDay,
+        "TIME_DIFFERENCE" => DataType::TimeDifference,
+        _ => return None,
+    };
+    Some(dt)
+}
+
+/// Number of bits a value of the named ESI data type occupies.
+///
+/// Returns `None` if the type is unknown or has no fixed size.
+pub(crate) fn bit_len_from_str(name: &str) -> Option<usize> {
+    let name = name.trim();
+    if let Some(len) = array_len(name) {
+        return Some(len * 8);
+    }
+    if let Some(len) = name
+        .strip_prefix("STRING(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        return len.trim().parse::<usize>().ok().map(|n| n * 8);
+    }
+    let len = match data_type_from_str(name)? {
+        DataType::Bool | DataType::Bit1 => 1,
+        DataType::Bit2 => 2,
+        DataType::Bit3 => 3,
+        DataType::Bit4 => 4,
+        DataType::Bit5 => 5,
+        DataType::Bit6 => 6,
+        DataType::Bit7 => 7,
+        DataType::Bit8 | DataType::Byte | DataType::I8 | DataType::U8 => 8,
+        DataType::I16 | DataType::U16 => 16,
+        DataType::I24 | DataType::U24 => 24,
+        DataType::I32 | DataType::U32 | DataType::F32 => 32,
+        DataType::I40 | DataType::U40 => 40,
+        DataType::I48 | DataType::U48 | DataType::TimeOfDay | DataType::TimeDifference => 48,
+        DataType::I56 | DataType::U56 => 56,
+        DataType::I64 | DataType::U64 | DataType::F64 => 64,
+        _ => return None,
+    };
+    Some(len)
+}
+
+/// Number of elements of an `ARRAY [a..b] OF BYTE` type.
+fn array_len(name: &str) -> Option<usize> {
+    let range = name
+        .strip_prefix("ARRAY")?
+        .trim_start()
+        .strip_prefix('[')?
+        .split(']')
+        .next()?;
+    let elem = name.rsplit("OF").next()?.trim();
+    if elem != "BYTE" && elem != "USINT" {
+        return None;
+    }
+    let mut bounds = range.split("..");
+    let lower = bounds.next()?.trim().parse::<usize>().ok()?;
+    let upper = bounds.next()?.trim().parse::<usize>().ok()?;
+    upper.checked_sub(lower).map(|n| n + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_data_type_names() {
+        assert_eq!(data_type_from_str("BOOL"), Some(DataType::Bool));
+        assert_eq!(data_type_from_str("BIT2"), Some(DataType::Bit2));
+        assert_eq!(data_type_from_str("UINT"), Some(DataType::U16));
+        assert_eq!(data_type_from_str("UINT32"), Some(DataType::U32));
+        assert_eq!(data_type_from_str("REAL"), Some(DataType::F32));
+        assert_eq!(data_type_from_str("STRING(8)"), Some(DataType::String));
+        assert_eq!(
+            data_type_from_str("ARRAY [0..63] OF BYTE"),
+            Some(DataType::U8Array)
+        );
+        assert_eq!(data_type_from_str("DT1018"), None);
+    }
+
+    #[test]
+    fn bit_len_of_data_type_names() {
+        assert_eq!(bit_len_from_str("BOOL"), Some(1));
+        assert_eq!(bit_len_from_str("BIT4"), Some(4));
+        assert_eq!(bit_len_from_str("USINT"), Some(8));
+        assert_eq!(bit_len_from_str("DINT"), Some(32));
+        assert_eq!(bit_len_from_str("LREAL"), Some(64));
+        assert_eq!(bit_len_from_str("STRING(10)"), Some(80));
+        assert_eq!(bit_len_from_str("ARRAY [0..15] OF BYTE"), Some(128));
+        assert_eq!(bit_len_from_str("DT1018"), None);
+    }
+}
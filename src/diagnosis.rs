@@ -0,0 +1,427 @@
+//! Diagnosis history messages (object 0x10F3, ETG.1020).
+
+use crate::structs::*;
+use ethercat_types::Value;
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    io::{Error, ErrorKind, Result},
+};
+
+/// Size of the fixed part of a message (code, flags, text ID, time stamp).
+const HEADER_LEN: usize = 16;
+
+/// Severity of a diagnosis message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagType {
+    Info,
+    Warning,
+    Error,
+    Other(u8),
+}
+
+/// Parameter of a diagnosis message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagParam {
+    Value(Value),
+    /// Reference to another text of the [`DiagTexts`].
+    TextId(u16),
+}
+
+/// A message of the diagnosis history as read from object 0x10F3.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagHistoryMessage {
+    pub diag_code: u32,
+    pub r#type: DiagType,
+    /// The time stamp is a local time of the device.
+    pub local_time: bool,
+    pub text_id: u16,
+    /// Time stamp in ns.
+    pub time_stamp: u64,
+    pub params: Vec<DiagParam>,
+}
+
+impl DiagHistoryMessage {
+    /// Decode a message (the content of one of the subindices 6 and up).
+    ///
+    /// Bits 8-15 of the flags give the number of parameters after the
+    /// fixed part, each preceded by its own flags. The remaining bytes
+    /// (e.g. padding of a fixed size entry) are ignored.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_LEN {
+            return Err(invalid(format!(
+                "Diagnosis message too short ({} bytes)",
+                data.len()
+            )));
+        }
+        let flags = u16::from_le_bytes([data[4], data[5]]);
+        let mut params = vec![];
+        let mut rest = &data[HEADER_LEN..];
+        for _ in 0..flags >> 8 {
+            let (param, len) = param_from_bytes(rest)?;
+            params.push(param);
+            rest = &rest[len..];
+        }
+        Ok(DiagHistoryMessage {
+            diag_code: u32::from_le_bytes(data[0..4].try_into().unwrap()),
+            r#type: match flags & 0x0F {
+                0 => DiagType::Info,
+                1 => DiagType::Warning,
+                2 => DiagType::Error,
+                t => DiagType::Other(t as u8),
+            },
+            local_time: flags & 0x10 != 0,
+            text_id: u16::from_le_bytes([data[6], data[7]]),
+            time_stamp: u64::from_le_bytes(data[8..16].try_into().unwrap()),
+            params,
+        })
+    }
+}
+
+/// Decode one parameter, returns it with the number of consumed bytes.
+fn param_from_bytes(data: &[u8]) -> Result<(DiagParam, usize)> {
+    let bytes = |len: usize| {
+        data.get(2..2 + len)
+            .ok_or_else(|| invalid("Diagnosis message parameter truncated"))
+    };
+    if data.len() < 2 {
+        return Err(invalid("Diagnosis message parameter truncated"));
+    }
+    let flags = u16::from_le_bytes([data[0], data[1]]);
+    let info = usize::from(flags & 0x0FFF);
+    let param = match flags >> 12 {
+        0 => {
+            let (len, value): (usize, fn(&[u8]) -> Value) = match info {
+                0x01 => (1, |b| Value::Bool(b[0] != 0)),
+                0x02 => (1, |b| Value::I8(b[0] as i8)),
+                0x03 => (2, |b| Value::I16(i16::from_le_bytes([b[0], b[1]]))),
+                0x04 => (4, |b| Value::I32(i32::from_le_bytes(b.try_into().unwrap()))),
+                0x05 => (1, |b| Value::U8(b[0])),
+                0x06 => (2, |b| Value::U16(u16::from_le_bytes([b[0], b[1]]))),
+                0x07 => (4, |b| Value::U32(u32::from_le_bytes(b.try_into().unwrap()))),
+                0x08 => (4, |b| Value::F32(f32::from_le_bytes(b.try_into().unwrap()))),
+                0x11 => (8, |b| Value::F64(f64::from_le_bytes(b.try_into().unwrap()))),
+                0x15 => (8, |b| Value::I64(i64::from_le_bytes(b.try_into().unwrap()))),
+                0x1B => (8, |b| Value::U64(u64::from_le_bytes(b.try_into().unwrap()))),
+                dt => {
+                    return Err(invalid(format!(
+                        "Unsupported data type 0x{:04X} of diagnosis message parameter",
+                        dt
+                    )))
+                }
+            };
+            return Ok((DiagParam::Value(value(bytes(len)?)), 2 + len));
+        }
+        1 => DiagParam::Value(Value::U8Array(bytes(info)?.to_vec())),
+        2 => {
+            let b = bytes(info)?;
+            let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+            DiagParam::Value(Value::String(
+                String::from_utf8_lossy(&b[..end]).into_owned(),
+            ))
+        }
+        3 => {
+            let units: Vec<_> = bytes(info)?
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .take_while(|&c| c != 0)
+                .collect();
+            DiagParam::Value(Value::String(String::from_utf16_lossy(&units)))
+        }
+        4 => {
+            let b = bytes(2)?;
+            return Ok((DiagParam::TextId(u16::from_le_bytes([b[0], b[1]])), 4));
+        }
+        t => {
+            return Err(invalid(format!(
+                "Unknown type {} of diagnosis message parameter",
+                t
+            )))
+        }
+    };
+    Ok((param, 2 + info))
+}
+
+/// Lookup table of the diagnosis texts of a device by text ID.
+#[derive(Debug, Clone, Default)]
+pub struct DiagTexts<'a> {
+    texts: HashMap<u16, &'a LocalizedText>,
+}
+
+impl Device {
+    /// The diagnosis texts of all profiles of the device.
+    ///
+    /// If a text ID is used more than once, the first text is taken.
+    pub fn diag_texts(&self) -> DiagTexts<'_> {
+        let mut texts = HashMap::new();
+        for m in self.profiles.iter().flat_map(|p| &p.diag_messages) {
+            texts.entry(m.text_id).or_insert(&m.text);
+        }
+        DiagTexts { texts }
+    }
+}
+
+impl<'a> DiagTexts<'a> {
+    pub fn get(&self, text_id: u16) -> Option<&'a LocalizedText> {
+        self.texts.get(&text_id).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    /// Human readable text of a message.
+    ///
+    /// The text is taken in the first available language of the fallback
+    /// chain (see [`LocalizedText::resolve`]). Its placeholders (`%d`,
+    /// `%u`, `%x`, `%X`, `%s`, `%c`, `%f`) are replaced by the parameters
+    /// in order, text ID parameters by the referenced text. Placeholders
+    /// without parameter are kept. Messages with an unknown text ID are
+    /// shown with their ID and parameters.
+    pub fn format(&self, msg: &DiagHistoryMessage, fallback: &[u16]) -> String {
+        match self.text(msg.text_id, fallback) {
+            Some(text) => self.substitute(text, &msg.params, fallback),
+            None => {
+                let params: Vec<_> = msg
+                    .params
+                    .iter()
+                    .map(|p| self.param_to_string(p, 'd', fallback))
+                    .collect();
+                format!(
+                    "Unknown text ID 0x{:04X} ({})",
+                    msg.text_id,
+                    params.join(", ")
+                )
+            }
+        }
+    }
+
+    fn text(&self, text_id: u16, fallback: &[u16]) -> Option<&'a str> {
+        self.get(text_id).and_then(|t| t.resolve(fallback))
+    }
+
+    fn substitute(&self, text: &str, params: &[DiagParam], fallback: &[u16]) -> String {
+        let mut out = String::new();
+        let mut params = params.iter();
+        let mut rest = text;
+        while let Some(pos) = rest.find('%') {
+            out.push_str(&rest[..pos]);
+            rest = &rest[pos + 1..];
+            if let Some(r) = rest.strip_prefix('%') {
+                out.push('%');
+                rest = r;
+                continue;
+            }
+            // Flags, width and precision are ignored.
+            let spec_len = rest
+                .find(|c: char| !(c.is_ascii_digit() || "-+ #.l".contains(c)))
+                .unwrap_or(rest.len());
+            let conv = rest[spec_len..].chars().next();
+            match conv {
+                Some(c) if "diuxXscf".contains(c) => match params.next() {
+                    Some(p) => out.push_str(&self.param_to_string(p, c, fallback)),
+                    None => {
+                        out.push('%');
+                        out.push_str(&rest[..spec_len + 1]);
+                    }
+                },
+                _ => {
+                    out.push('%');
+                    continue;
+                }
+            }
+            rest = &rest[spec_len + 1..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    fn param_to_string(&self, param: &DiagParam, conv: char, fallback: &[u16]) -> String {
+        match param {
+            DiagParam::TextId(id) => self
+                .text(*id, fallback)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("0x{:04X}", id)),
+            DiagParam::Value(v) => value_to_string(v, conv),
+        }
+    }
+}
+
+fn value_to_string(v: &Value, conv: char) -> String {
+    let uint = match v {
+        Value::Bool(v) => Some(*v as u64),
+        Value::I8(v) => Some(*v as u8 as u64),
+        Value::I16(v) => Some(*v as u16 as u64),
+        Value::I32(v) => Some(*v as u32 as u64),
+        Value::I64(v) => Some(*v as u64),
+        Value::U8(v) | Value::Byte(v) => Some(u64::from(*v)),
+        Value::U16(v) => Some(u64::from(*v)),
+        Value::U32(v) => Some(u64::from(*v)),
+        Value::U64(v) => Some(*v),
+        _ => None,
+    };
+    match (conv, uint) {
+        ('x', Some(u)) => return format!("{:x}", u),
+        ('X', Some(u)) => return format!("{:X}", u),
+        ('c', Some(u)) => {
+            if let Some(c) = std::char::from_u32(u as u32) {
+                return c.to_string();
+            }
+        }
+        _ => {}
+    }
+    match v {
+        Value::Bool(v) => (*v as u8).to_string(),
+        Value::I8(v) => v.to_string(),
+        Value::I16(v) => v.to_string(),
+        Value::I32(v) => v.to_string(),
+        Value::I64(v) => v.to_string(),
+        Value::U8(v) | Value::Byte(v) => v.to_string(),
+        Value::U16(v) => v.to_string(),
+        Value::U32(v) => v.to_string(),
+        Value::U64(v) => v.to_string(),
+        Value::F32(v) => v.to_string(),
+        Value::F64(v) => v.to_string(),
+        Value::String(v) => v.clone(),
+        Value::U8Array(b) | Value::Raw(b) => b.iter().map(|b| format!("{:02X}", b)).collect(),
+        v => format!("{:?}", v),
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(flags: u16, text_id: u16) -> Vec<u8> {
+        let mut b = vec![];
+        b.extend_from_slice(&0xE000_0000_u32.to_le_bytes());
+        b.extend_from_slice(&flags.to_le_bytes());
+        b.extend_from_slice(&text_id.to_le_bytes());
+        b.extend_from_slice(&1_000_000_u64.to_le_bytes());
+        b
+    }
+
+    fn texts(messages: &[(u16, &str)]) -> Device {
+        let diag_messages = messages
+            .iter()
+            .map(|(text_id, text)| DiagMessage {
+                text_id: *text_id,
+                text: LocalizedText::from(*text),
+            })
+            .collect();
+        Device {
+            profiles: vec![Profile {
+                diag_messages,
+                ..Profile::default()
+            }],
+            ..Device::default()
+        }
+    }
+
+    #[test]
+    fn decode_message() {
+        let mut b = header(0x0602, 0x0011);
+        b.extend_from_slice(&[0x05, 0x00, 2]);
+        b.extend_from_slice(&[0x06, 0x00, 0x00, 0x10]);
+        b.extend_from_slice(&[0x03, 0x20, b'E', b'C', 0]);
+        b.extend_from_slice(&[0x04, 0x30, b'O', 0, b'K', 0]);
+        b.extend_from_slice(&[0x02, 0x10, 0xAB, 0xCD]);
+        b.extend_from_slice(&[0x00, 0x40, 0x12, 0x00]);
+        let msg = DiagHistoryMessage::from_bytes(&b).unwrap();
+        assert_eq!(msg.diag_code, 0xE000_0000);
+        assert_eq!(msg.r#type, DiagType::Error);
+        assert!(!msg.local_time);
+        assert_eq!(msg.text_id, 0x0011);
+        assert_eq!(msg.time_stamp, 1_000_000);
+        assert_eq!(
+            msg.params,
+            vec![
+                DiagParam::Value(Value::U8(2)),
+                DiagParam::Value(Value::U16(0x1000)),
+                DiagParam::Value(Value::String("EC".to_string())),
+                DiagParam::Value(Value::String("OK".to_string())),
+                DiagParam::Value(Value::U8Array(vec![0xAB, 0xCD])),
+                DiagParam::TextId(0x0012),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_messages() {
+        assert!(DiagHistoryMessage::from_bytes(&[0; 15]).is_err());
+        let mut b = header(0x0100, 1);
+        b.extend_from_slice(&[0x07, 0x00, 1, 2]);
+        let err = DiagHistoryMessage::from_bytes(&b).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "Diagnosis message parameter truncated");
+        let mut b = header(0x0100, 1);
+        b.extend_from_slice(&[0x09, 0x00, 0]);
+        assert!(DiagHistoryMessage::from_bytes(&b).is_err());
+    }
+
+    #[test]
+    fn ignore_padding_of_messages() {
+        let mut b = header(0x0111, 0x0011);
+        b.extend_from_slice(&[0x05, 0x00, 2]);
+        b.resize(32, 0);
+        let msg = DiagHistoryMessage::from_bytes(&b).unwrap();
+        assert_eq!(msg.r#type, DiagType::Warning);
+        assert!(msg.local_time);
+        assert_eq!(msg.params, vec![DiagParam::Value(Value::U8(2))]);
+
+        let mut b = header(0x0000, 0x0013);
+        b.resize(32, 0);
+        let msg = DiagHistoryMessage::from_bytes(&b).unwrap();
+        assert!(msg.params.is_empty());
+    }
+
+    #[test]
+    fn format_messages() {
+        let device = texts(&[
+            (0x0011, "Sync Manager %d invalid address (%x)"),
+            (0x0012, "%s: 100%% (%c)"),
+            (0x0013, "Overtemperature"),
+        ]);
+        let texts = device.diag_texts();
+        assert_eq!(texts.len(), 3);
+        let msg = |text_id, params| DiagHistoryMessage {
+            diag_code: 0xE000_0000,
+            r#type: DiagType::Info,
+            local_time: false,
+            text_id,
+            time_stamp: 0,
+            params,
+        };
+        let sm = msg(
+            0x0011,
+            vec![
+                DiagParam::Value(Value::U8(2)),
+                DiagParam::Value(Value::U16(0xFF0)),
+            ],
+        );
+        assert_eq!(
+            texts.format(&sm, &[]),
+            "Sync Manager 2 invalid address (ff0)"
+        );
+        let nested = msg(
+            0x0012,
+            vec![DiagParam::TextId(0x0013), DiagParam::Value(Value::U8(b'A'))],
+        );
+        assert_eq!(texts.format(&nested, &[]), "Overtemperature: 100% (A)");
+        let missing = msg(0x0011, vec![DiagParam::Value(Value::I8(-1))]);
+        assert_eq!(
+            texts.format(&missing, &[]),
+            "Sync Manager -1 invalid address (%x)"
+        );
+        let unknown = msg(0x0100, vec![DiagParam::Value(Value::U32(7))]);
+        assert_eq!(texts.format(&unknown, &[]), "Unknown text ID 0x0100 (7)");
+    }
+}
@@ -0,0 +1,701 @@
+//! Structural comparison of ESI documents.
+
+use crate::structs::*;
+use std::{fmt, hash::Hash};
+
+/// Kind of a [`Change`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// A single difference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Location of the added, removed or changed item
+    /// (e.g. `EK1100 [0x044c2c52 rev 0x00110000]/rx_pdo[0x1600]/name`).
+    pub path: String,
+    /// Previous value of a changed property.
+    pub old: Option<String>,
+    /// New value of a changed property.
+    pub new: Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ChangeKind::Added => write!(f, "+ {}", self.path),
+            ChangeKind::Removed => write!(f, "- {}", self.path),
+            ChangeKind::Changed => write!(
+                f,
+                "~ {}: {} -> {}",
+                self.path,
+                self.old.as_deref().unwrap_or("(none)"),
+                self.new.as_deref().unwrap_or("(none)")
+            ),
+        }
+    }
+}
+
+/// Differences between two ESI documents or devices.
+///
+/// The [`Display`](fmt::Display) implementation writes one change per line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Report as JSON object with a `changes` array.
+    pub fn to_json(&self) -> String {
+        let changes: Vec<_> = self
+            .changes
+            .iter()
+            .map(|c| {
+                format!(
+                    r#"{{"kind":"{}","path":{},"old":{},"new":{}}}"#,
+                    c.kind.as_str(),
+                    json_string(&c.path),
+                    c.old.as_deref().map(json_string).unwrap_or("null".into()),
+                    c.new.as_deref().map(json_string).unwrap_or("null".into())
+                )
+            })
+            .collect();
+        format!(r#"{{"changes":[{}]}}"#, changes.join(","))
+    }
+
+    fn added(&mut self, path: String) {
+        self.push(ChangeKind::Added, path, None, None);
+    }
+
+    fn removed(&mut self, path: String) {
+        self.push(ChangeKind::Removed, path, None, None);
+    }
+
+    fn push(&mut self, kind: ChangeKind, path: String, old: Option<String>, new: Option<String>) {
+        self.changes.push(Change {
+            kind,
+            path,
+            old,
+            new,
+        });
+    }
+
+    fn compare<T: PartialEq + fmt::Debug>(&mut self, path: &str, name: &str, old: T, new: T) {
+        if old != new {
+            self.push(
+                ChangeKind::Changed,
+                format!("{}/{}", path, name),
+                Some(format!("{:?}", old)),
+                Some(format!("{:?}", new)),
+            );
+        }
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in &self.changes {
+            writeln!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two ESI documents.
+///
+/// Devices are matched by product code and revision number
+/// (or by their type if they have no product code), modules by their type,
+/// PDOs and dictionary objects by their index, dictionary data types by
+/// their name and profiles by position.
+pub fn diff(old: &EtherCatInfo, new: &EtherCatInfo) -> Diff {
+    let mut d = Diff::default();
+    d.compare("vendor", "id", old.vendor.id, new.vendor.id);
+    diff_lists(
+        &mut d,
+        "",
+        &old.description.devices,
+        &new.description.devices,
+        device_key,
+        device_label,
+        diff_device,
+    );
+    diff_lists(
+        &mut d,
+        "",
+        &old.description.modules,
+        &new.description.modules,
+        |m| m.r#type.clone(),
+        |m| format!("module {}", m.r#type),
+        diff_module,
+    );
+    d
+}
+
+/// Compare two devices, e.g. two revisions of the same product.
+pub fn diff_devices(old: &Device, new: &Device) -> Diff {
+    let mut d = Diff::default();
+    d.compare("", "product_code", old.product_code, new.product_code);
+    d.compare("", "revision_no", old.revision_no, new.revision_no);
+    diff_device(&mut d, "", old, new);
+    for c in &mut d.changes {
+        c.path = c.path.trim_start_matches('/').to_string();
+    }
+    d
+}
+
+/// Compare two definitions of a module.
+pub(crate) fn diff_modules(old: &Module, new: &Module) -> Diff {
+    let mut d = Diff::default();
+    diff_module(&mut d, "", old, new);
+    for c in &mut d.changes {
+        c.path = c.path.trim_start_matches('/').to_string();
+    }
+    d
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum DeviceKey {
+    Identity(u32, Option<u32>),
+    Type(String),
+}
+
+pub(crate) fn device_key(d: &Device) -> DeviceKey {
+    match d.product_code {
+        Some(p) => DeviceKey::Identity(p, d.revision_no),
+        None => DeviceKey::Type(d.desc.clone()),
+    }
+}
+
+pub(crate) fn device_label(d: &Device) -> String {
+    match (d.product_code, d.revision_no) {
+        (Some(p), Some(r)) => format!("{} [0x{:08x} rev 0x{:08x}]", d.desc, p, r),
+        (Some(p), None) => format!("{} [0x{:08x}]", d.desc, p),
+        _ => d.desc.clone(),
+    }
+}
+
+fn diff_device(d: &mut Diff, path: &str, old: &Device, new: &Device) {
+    d.compare(path, "type", &old.desc, &new.desc);
+    d.compare(path, "name", &old.name, &new.name);
+    d.compare(path, "physics", &old.physics, &new.physics);
+    d.compare(path, "group_type", &old.group_type, &new.group_type);
+    let info = |d: &Device| d.info.clone().unwrap_or_default();
+    let (old_info, new_info) = (info(old), info(new));
+    d.compare(
+        path,
+        "info/state_machine_timeouts",
+        old_info.state_machine_timeouts,
+        new_info.state_machine_timeouts,
+    );
+    d.compare(
+        path,
+        "info/mailbox_timeouts",
+        old_info.mailbox_timeouts,
+        new_info.mailbox_timeouts,
+    );
+    d.compare(path, "ebus_current", old.ebus_current(), new.ebus_current());
+    let max = old.sm.len().max(new.sm.len());
+    for i in 0..max {
+        let sm_path = format!("{}/sm[{}]", path, i);
+        match (old.sm.get(i), new.sm.get(i)) {
+            (Some(o), Some(n)) => {
+                d.compare(&sm_path, "enable", o.enable, n.enable);
+                d.compare(&sm_path, "start_address", o.start_address, n.start_address);
+                d.compare(&sm_path, "control_byte", o.control_byte, n.control_byte);
+                d.compare(&sm_path, "default_size", o.default_size, n.default_size);
+                d.compare(&sm_path, "virtual", o.r#virtual, n.r#virtual);
+            }
+            (None, Some(_)) => d.added(sm_path),
+            (Some(_), None) => d.removed(sm_path),
+            (None, None) => unreachable!(),
+        }
+    }
+    diff_pdos(d, path, "rx_pdo", &old.rx_pdo, &new.rx_pdo);
+    diff_pdos(d, path, "tx_pdo", &old.tx_pdo, &new.tx_pdo);
+    diff_profiles(d, path, &old.profiles, &new.profiles);
+}
+
+fn diff_module(d: &mut Diff, path: &str, old: &Module, new: &Module) {
+    d.compare(path, "name", &old.name, &new.name);
+    diff_pdos(d, path, "rx_pdo", &old.rx_pdo, &new.rx_pdo);
+    diff_pdos(d, path, "tx_pdo", &old.tx_pdo, &new.tx_pdo);
+    diff_profiles(d, path, &old.profiles, &new.profiles);
+}
+
+fn diff_profiles(d: &mut Diff, path: &str, old: &[Profile], new: &[Profile]) {
+    let max = old.len().max(new.len());
+    for i in 0..max {
+        let profile_path = format!("{}/profiles[{}]", path, i);
+        match (old.get(i), new.get(i)) {
+            (Some(o), Some(n)) => diff_profile(d, &profile_path, o, n),
+            (None, Some(_)) => d.added(profile_path),
+            (Some(_), None) => d.removed(profile_path),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_profile(d: &mut Diff, path: &str, old: &Profile, new: &Profile) {
+    d.compare(path, "profile_no", old.profile_no, new.profile_no);
+    let data_types = |p: &Profile| p.dictionary.as_ref().map(|d| d.data_types.clone());
+    diff_lists(
+        d,
+        path,
+        &data_types(old).unwrap_or_default(),
+        &data_types(new).unwrap_or_default(),
+        |t| t.name.clone(),
+        |t| format!("data_types[{}]", t.name),
+        diff_data_type,
+    );
+    let objects = |p: &Profile| p.dictionary.as_ref().map(|d| d.objects.clone());
+    diff_lists(
+        d,
+        path,
+        &objects(old).unwrap_or_default(),
+        &objects(new).unwrap_or_default(),
+        |o| u16::from(o.idx),
+        |o| format!("objects[0x{:04x}]", u16::from(o.idx)),
+        diff_object,
+    );
+}
+
+fn diff_data_type(d: &mut Diff, path: &str, old: &DataTypeDef, new: &DataTypeDef) {
+    d.compare(path, "base_type", &old.base_type, &new.base_type);
+    d.compare(path, "bit_size", old.bit_size, new.bit_size);
+    d.compare(path, "array_info", old.array_info, new.array_info);
+    diff_lists(
+        d,
+        path,
+        &old.sub_items,
+        &new.sub_items,
+        |s| s.name.clone(),
+        |s| format!("sub_items[{}]", s.name),
+        diff_sub_item,
+    );
+    diff_lists(
+        d,
+        path,
+        &old.enum_info,
+        &new.enum_info,
+        |e| e.value,
+        |e| format!("enum_info[{}]", e.value),
+        |d, path, old, new| d.compare(path, "text", &old.text, &new.text),
+    );
+}
+
+fn diff_sub_item(d: &mut Diff, path: &str, old: &SubItemDef, new: &SubItemDef) {
+    d.compare(
+        path,
+        "sub_idx",
+        old.sub_idx.map(u8::from),
+        new.sub_idx.map(u8::from),
+    );
+    d.compare(path, "type", &old.r#type, &new.r#type);
+    d.compare(path, "bit_size", old.bit_size, new.bit_size);
+    d.compare(path, "bit_offs", old.bit_offs, new.bit_offs);
+    let access = |s: &SubItemDef| s.flags.as_ref().and_then(|f| f.access.clone());
+    d.compare(path, "flags/access", access(old), access(new));
+}
+
+fn diff_object(d: &mut Diff, path: &str, old: &Object, new: &Object) {
+    d.compare(path, "name", &old.name, &new.name);
+    d.compare(path, "type", &old.r#type, &new.r#type);
+    d.compare(path, "bit_size", old.bit_size, new.bit_size);
+    let flags = |o: &Object| o.flags.clone().unwrap_or_default();
+    let (old_flags, new_flags) = (flags(old), flags(new));
+    d.compare(path, "flags/access", old_flags.access, new_flags.access);
+    d.compare(
+        path,
+        "flags/category",
+        old_flags.category,
+        new_flags.category,
+    );
+    d.compare(
+        path,
+        "flags/pdo_mapping",
+        old_flags.pdo_mapping,
+        new_flags.pdo_mapping,
+    );
+    d.compare(
+        path,
+        "flags/sdo_access",
+        old_flags.sdo_access,
+        new_flags.sdo_access,
+    );
+    // Compare the bytes, the notation of the hex string doesn't matter.
+    let default_data = |o: &Object| {
+        let data = o.info.as_ref()?.default_data.as_ref()?;
+        Some(match data.to_bytes() {
+            Ok(bytes) => bytes.iter().map(|b| format!("{:02X}", b)).collect(),
+            Err(_) => data.0.clone(),
+        })
+    };
+    d.compare(path, "default_data", default_data(old), default_data(new));
+}
+
+fn diff_pdos(d: &mut Diff, path: &str, name: &str, old: &[Pdo], new: &[Pdo]) {
+    diff_lists(
+        d,
+        path,
+        old,
+        new,
+        |p| u16::from(p.idx),
+        |p| format!("{}[0x{:04x}]", name, u16::from(p.idx)),
+        diff_pdo,
+    );
+}
+
+fn diff_pdo(d: &mut Diff, path: &str, old: &Pdo, new: &Pdo) {
+    d.compare(path, "name", &old.name, &new.name);
+    d.compare(path, "sm", old.sm.map(u8::from), new.sm.map(u8::from));
+    d.compare(path, "fixed", old.fixed, new.fixed);
+    d.compare(path, "mandatory", old.mandatory, new.mandatory);
+    let key = |e: &PdoEntry| (u16::from(e.entry_idx.idx), u8::from(e.entry_idx.sub_idx));
+    diff_lists(
+        d,
+        path,
+        &old.entries,
+        &new.entries,
+        key,
+        |e| {
+            let (idx, sub_idx) = key(e);
+            format!("entries[0x{:04x}:{:02x}]", idx, sub_idx)
+        },
+        diff_entry,
+    );
+    let order = |entries: &[PdoEntry], other: &[PdoEntry]| -> Vec<_> {
+        entries
+            .iter()
+            .map(key)
+            .filter(|k| other.iter().any(|e| key(e) == *k))
+            .collect()
+    };
+    if order(&old.entries, &new.entries) != order(&new.entries, &old.entries) {
+        d.push(
+            ChangeKind::Changed,
+            format!("{}/entries", path),
+            Some("previous order".to_string()),
+            Some("reordered".to_string()),
+        );
+    }
+}
+
+fn diff_entry(d: &mut Diff, path: &str, old: &PdoEntry, new: &PdoEntry) {
+    d.compare(path, "name", &old.name, &new.name);
+    d.compare(path, "bit_len", old.bit_len, new.bit_len);
+    d.compare(path, "data_type", &old.data_type, &new.data_type);
+}
+
+/// Match the items of two lists by key and report added and removed ones.
+///
+/// Items with the same key (e.g. gaps in a PDO) are matched in order.
+fn diff_lists<T, K: PartialEq>(
+    d: &mut Diff,
+    path: &str,
+    old: &[T],
+    new: &[T],
+    key: impl Fn(&T) -> K,
+    label: impl Fn(&T) -> String,
+    diff_item: fn(&mut Diff, &str, &T, &T),
+) {
+    let keyed = |items: &[T]| -> Vec<(K, usize)> {
+        let mut keys: Vec<(K, usize)> = vec![];
+        for item in items {
+            let k = key(item);
+            let n = keys.iter().filter(|(other, _)| *other == k).count();
+            keys.push((k, n));
+        }
+        keys
+    };
+    let old_keys = keyed(old);
+    let new_keys = keyed(new);
+    let item_path = |item: &T| {
+        if path.is_empty() {
+            label(item)
+        } else {
+            format!("{}/{}", path, label(item))
+        }
+    };
+    for (o, k) in old.iter().zip(&old_keys) {
+        match new_keys.iter().position(|other| other == k) {
+            Some(i) => diff_item(d, &item_path(o), o, &new[i]),
+            None => d.removed(item_path(o)),
+        }
+    }
+    for (n, k) in new.iter().zip(&new_keys) {
+        if !old_keys.contains(k) {
+            d.added(item_path(n));
+        }
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, entry};
+
+    fn pdo(idx: u16, entries: Vec<PdoEntry>) -> Pdo {
+        fixtures::pdo(idx, 2, entries)
+    }
+
+    fn device(desc: &str, product_code: u32, revision_no: u32) -> Device {
+        Device {
+            desc: desc.to_string(),
+            product_code: Some(product_code),
+            revision_no: Some(revision_no),
+            rx_pdo: vec![pdo(0x1600, vec![entry(0x7000, 1, 1), entry(0, 0, 7)])],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_two_revisions() {
+        let old = device("EL2002", 0x07d23052, 0x0010_0000);
+        let mut new = device("EL2002", 0x07d23052, 0x0011_0000);
+        new.rx_pdo[0].entries[0].bit_len = 8;
+        new.rx_pdo[0].entries.pop();
+        new.rx_pdo[0].entries.push(entry(0x7010, 1, 8));
+        new.tx_pdo.push(pdo(0x1A00, vec![]));
+
+        let d = diff_devices(&old, &new);
+        let lines: Vec<_> = d.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "~ revision_no: Some(1048576) -> Some(1114112)",
+                "~ rx_pdo[0x1600]/entries[0x7000:01]/bit_len: 1 -> 8",
+                "- rx_pdo[0x1600]/entries[0x0000:00]",
+                "+ rx_pdo[0x1600]/entries[0x7010:01]",
+                "+ tx_pdo[0x1a00]",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_documents() {
+        let mut old = EtherCatInfo {
+            vendor: Vendor {
+                id: 2,
+                ..Vendor::default()
+            },
+            ..EtherCatInfo::default()
+        };
+        let mut new = old.clone();
+        old.description.devices = vec![
+            device("EL2002", 1, 1),
+            device("EL2004", 2, 1),
+            device("EL2008", 3, 1),
+        ];
+        new.description.devices = vec![device("EL2008", 3, 1), device("EL2002", 1, 2)];
+        new.description.devices[0].name = LocalizedText::from("8 Ch. \"Dig\" Out");
+
+        let d = diff(&old, &new);
+        assert_eq!(d.changes.len(), 4);
+        assert_eq!(
+            d.to_string(),
+            "- EL2002 [0x00000001 rev 0x00000001]\n\
+             - EL2004 [0x00000002 rev 0x00000001]\n\
+             ~ EL2008 [0x00000003 rev 0x00000001]/name: [] -> [(\"8 Ch. \\\"Dig\\\" Out\", None)]\n\
+             + EL2002 [0x00000001 rev 0x00000002]\n"
+        );
+        assert!(d.to_json().starts_with(
+            r#"{"changes":[{"kind":"removed","path":"EL2002 [0x00000001 rev 0x00000001]","old":null,"new":null},"#
+        ));
+        assert!(d
+            .to_json()
+            .contains(r#""old":"[]","new":"[(\"8 Ch. \\\"Dig\\\" Out\", None)]"}"#));
+        assert!(diff(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn diff_dictionary_objects() {
+        let object = |idx: u16, bit_size: usize, access: &str, default_data: &str| Object {
+            idx: Idx::from(idx),
+            depend_on_slot: false,
+            name: LocalizedText::from("Object"),
+            r#type: format!("UINT{}", bit_size),
+            bit_size,
+            info: Some(ObjectInfo {
+                default_data: Some(HexBinary(default_data.to_string())),
+                ..ObjectInfo::default()
+            }),
+            flags: Some(ObjectFlags {
+                access: Some(access.to_string()),
+                ..ObjectFlags::default()
+            }),
+        };
+        let profile = |objects: Vec<Object>| Profile {
+            profile_no: Some(5001),
+            dictionary: Some(Dictionary {
+                objects,
+                ..Dictionary::default()
+            }),
+            ..Profile::default()
+        };
+        let mut old = device("EL2002", 1, 1);
+        let mut new = old.clone();
+        old.profiles = vec![profile(vec![
+            object(0x1000, 32, "ro", "01 00 00 00"),
+            object(0x8000, 16, "rw", "0a00"),
+            object(0x8001, 16, "rw", "0000"),
+        ])];
+        new.profiles = vec![profile(vec![
+            object(0x1000, 32, "ro", "01000000"),
+            object(0x8000, 32, "ro", "0B000000"),
+            object(0x8002, 16, "rw", "0000"),
+        ])];
+
+        let d = diff_devices(&old, &new);
+        let lines: Vec<_> = d.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "~ profiles[0]/objects[0x8000]/type: \"UINT16\" -> \"UINT32\"",
+                "~ profiles[0]/objects[0x8000]/bit_size: 16 -> 32",
+                "~ profiles[0]/objects[0x8000]/flags/access: Some(\"rw\") -> Some(\"ro\")",
+                "~ profiles[0]/objects[0x8000]/default_data: Some(\"0A00\") -> Some(\"0B000000\")",
+                "- profiles[0]/objects[0x8001]",
+                "+ profiles[0]/objects[0x8002]",
+            ]
+        );
+        assert!(d.to_json().contains(
+            r#"{"kind":"added","path":"profiles[0]/objects[0x8002]","old":null,"new":null}"#
+        ));
+
+        new.profiles.clear();
+        assert_eq!(diff_devices(&old, &new).to_string(), "- profiles[0]\n");
+    }
+
+    #[test]
+    fn diff_dictionary_data_types() {
+        let sub_item = |sub_idx: u8, name: &str, r#type: &str, bit_size: usize| SubItemDef {
+            sub_idx: Some(SubIdx::from(sub_idx)),
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            bit_size,
+            bit_offs: 16 * usize::from(sub_idx),
+            flags: None,
+        };
+        let enum_value = |value: i64, text: &str| EnumValue {
+            text: LocalizedText::from(text),
+            value,
+        };
+        let data_type = |name: &str, bit_size: usize| DataTypeDef {
+            name: name.to_string(),
+            base_type: None,
+            bit_size,
+            array_info: None,
+            sub_items: vec![],
+            enum_info: vec![],
+        };
+        let profile = |data_types: Vec<DataTypeDef>| Profile {
+            dictionary: Some(Dictionary {
+                data_types,
+                ..Dictionary::default()
+            }),
+            ..Profile::default()
+        };
+        let mut old = device("EL2002", 1, 1);
+        let mut new = old.clone();
+        old.profiles = vec![profile(vec![
+            DataTypeDef {
+                sub_items: vec![
+                    sub_item(0, "SubIndex 000", "USINT", 8),
+                    sub_item(1, "Filter", "UINT", 16),
+                    sub_item(2, "Version", "UINT", 16),
+                ],
+                ..data_type("DT8000", 48)
+            },
+            DataTypeDef {
+                base_type: Some("USINT".to_string()),
+                enum_info: vec![enum_value(0, "Off"), enum_value(1, "On")],
+                ..data_type("DT0800EN03", 8)
+            },
+            DataTypeDef {
+                base_type: Some("UINT".to_string()),
+                array_info: Some(ArrayInfo {
+                    l_bound: 1,
+                    elements: 2,
+                }),
+                ..data_type("ARRAY [0..1] OF UINT", 32)
+            },
+        ])];
+        new.profiles = vec![profile(vec![
+            DataTypeDef {
+                base_type: Some("UINT".to_string()),
+                array_info: Some(ArrayInfo {
+                    l_bound: 1,
+                    elements: 3,
+                }),
+                ..data_type("ARRAY [0..1] OF UINT", 48)
+            },
+            DataTypeDef {
+                sub_items: vec![
+                    sub_item(0, "SubIndex 000", "USINT", 8),
+                    sub_item(1, "Filter", "UDINT", 32),
+                    sub_item(3, "Mode", "UINT", 16),
+                ],
+                ..data_type("DT8000", 64)
+            },
+            DataTypeDef {
+                base_type: Some("USINT".to_string()),
+                enum_info: vec![enum_value(0, "Off"), enum_value(1, "Enabled")],
+                ..data_type("DT0800EN03", 8)
+            },
+            data_type("DT8001", 16),
+        ])];
+
+        let d = diff_devices(&old, &new);
+        let lines: Vec<_> = d.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "~ profiles[0]/data_types[DT8000]/bit_size: 48 -> 64",
+                "~ profiles[0]/data_types[DT8000]/sub_items[Filter]/type: \"UINT\" -> \"UDINT\"",
+                "~ profiles[0]/data_types[DT8000]/sub_items[Filter]/bit_size: 16 -> 32",
+                "- profiles[0]/data_types[DT8000]/sub_items[Version]",
+                "+ profiles[0]/data_types[DT8000]/sub_items[Mode]",
+                "~ profiles[0]/data_types[DT0800EN03]/enum_info[1]/text: [(\"On\", None)] -> [(\"Enabled\", None)]",
+                "~ profiles[0]/data_types[ARRAY [0..1] OF UINT]/bit_size: 32 -> 48",
+                "~ profiles[0]/data_types[ARRAY [0..1] OF UINT]/array_info: Some(ArrayInfo { l_bound: 1, elements: 2 }) -> Some(ArrayInfo { l_bound: 1, elements: 3 })",
+                "+ profiles[0]/data_types[DT8001]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! E-Bus current budget of a terminal segment.
+
+use crate::structs::*;
+
+impl Device {
+    /// Current consumed from the E-Bus in mA, negative if supplied
+    /// (see [`Electrical::ebus_current`]).
+    pub fn ebus_current(&self) -> Option<i32> {
+        self.info
+            .as_ref()?
+            .electrical
+            .as_ref()
+            .map(|e| e.ebus_current)
+    }
+}
+
+/// Cumulative E-Bus current balance of a segment of devices.
+///
+/// The devices are given in E-Bus order, usually a coupler followed by
+/// its terminals. Each device adds its supplied current to the balance
+/// and subtracts its consumed current. Devices that do not declare their
+/// current are taken as neutral.
+#[derive(Debug, Clone)]
+pub struct EBusBudget<'a> {
+    pub entries: Vec<EBusBudgetEntry<'a>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EBusBudgetEntry<'a> {
+    pub device: &'a Device,
+    /// Declared current of the device in mA (see [`Device::ebus_current`]).
+    pub current: Option<i32>,
+    /// Current that is left after this device in mA.
+    pub balance: i32,
+}
+
+impl<'a> EBusBudget<'a> {
+    pub fn new(devices: impl IntoIterator<Item = &'a Device>) -> Self {
+        let mut balance = 0;
+        let entries = devices
+            .into_iter()
+            .map(|device| {
+                let current = device.ebus_current();
+                balance -= current.unwrap_or(0);
+                EBusBudgetEntry {
+                    device,
+                    current,
+                    balance,
+                }
+            })
+            .collect();
+        EBusBudget { entries }
+    }
+
+    /// Current that is left after the last device in mA.
+    pub fn balance(&self) -> i32 {
+        self.entries.last().map(|e| e.balance).unwrap_or(0)
+    }
+
+    /// Devices that are not sufficiently supplied.
+    pub fn overloaded(&self) -> impl Iterator<Item = &EBusBudgetEntry<'a>> {
+        self.entries.iter().filter(|e| e.balance < 0)
+    }
+
+    /// Position of the first device that is not sufficiently supplied.
+    ///
+    /// A power feed terminal has to be inserted before this device.
+    pub fn feed_needed_before(&self) -> Option<usize> {
+        self.entries.iter().position(|e| e.balance < 0)
+    }
+
+    /// Devices without declared E-Bus current.
+    pub fn undeclared(&self) -> impl Iterator<Item = &'a Device> + '_ {
+        self.entries
+            .iter()
+            .filter(|e| e.current.is_none())
+            .map(|e| e.device)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(name: &str, ebus_current: Option<i32>) -> Device {
+        Device {
+            name: LocalizedText::from(name),
+            desc: name.to_string(),
+            info: ebus_current.map(|ebus_current| DeviceInfo {
+                electrical: Some(Electrical {
+                    ebus_current,
+                    unknown: vec![],
+                }),
+                ..DeviceInfo::default()
+            }),
+            ..Device::default()
+        }
+    }
+
+    #[test]
+    fn sum_up_currents() {
+        let devices = vec![
+            device("EK1100", Some(-2000)),
+            device("EL1008", Some(90)),
+            device("EL2008", Some(110)),
+            device("Foo", None),
+        ];
+        let budget = EBusBudget::new(&devices);
+        let balances: Vec<_> = budget.entries.iter().map(|e| e.balance).collect();
+        assert_eq!(balances, vec![2000, 1910, 1800, 1800]);
+        assert_eq!(budget.balance(), 1800);
+        assert_eq!(budget.feed_needed_before(), None);
+        assert_eq!(budget.undeclared().count(), 1);
+    }
+
+    #[test]
+    fn locate_missing_power_feed() {
+        let devices = vec![
+            device("EK1100", Some(-2000)),
+            device("EL7041", Some(1200)),
+            device("EL7041", Some(1200)),
+            device("EL9410", Some(-2000)),
+            device("EL7041", Some(1200)),
+        ];
+        let budget = EBusBudget::new(&devices);
+        assert_eq!(budget.feed_needed_before(), Some(2));
+        assert_eq!(budget.overloaded().count(), 1);
+        assert_eq!(budget.balance(), 400);
+        assert_eq!(EBusBudget::new(&[]).balance(), 0);
+    }
+}
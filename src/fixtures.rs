@@ -0,0 +1,39 @@
+//! Builders for the unit tests.
+
+use crate::structs::*;
+
+pub(crate) fn entry(idx: u16, sub_idx: u8, bit_len: usize) -> PdoEntry {
+    PdoEntry {
+        entry_idx: PdoEntryIdx::new(idx, sub_idx),
+        depend_on_slot: false,
+        bit_len,
+        name: LocalizedText::new(),
+        data_type: None,
+        unknown: vec![],
+    }
+}
+
+/// A fixed PDO of the given sync manager.
+pub(crate) fn pdo(idx: u16, sm: u8, entries: Vec<PdoEntry>) -> Pdo {
+    Pdo {
+        sm: Some(SmIdx::from(sm)),
+        fixed: true,
+        mandatory: false,
+        idx: PdoIdx::from(idx),
+        depend_on_slot: false,
+        name: LocalizedText::new(),
+        entries,
+        attributes: vec![],
+        unknown: vec![],
+    }
+}
+
+/// An enabled sync manager.
+pub(crate) fn sm(start_address: u16, control_byte: Option<u8>) -> Sm {
+    Sm {
+        enable: true,
+        start_address,
+        control_byte,
+        ..Sm::default()
+    }
+}
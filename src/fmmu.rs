@@ -0,0 +1,233 @@
+//! FMMU configuration of a slave.
+
+use crate::{layout::*, structs::*};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Result},
+};
+
+/// Register settings of a Fieldbus Memory Management Unit (FMMU).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FmmuConfig {
+    /// Index of the FMMU, the registers start at `0x0600 + 16 * idx`.
+    pub idx: u8,
+    pub logical_start_address: u32,
+    /// Length in bytes.
+    pub length: u16,
+    pub logical_start_bit: u8,
+    pub logical_stop_bit: u8,
+    pub physical_start_address: u16,
+    pub physical_start_bit: u8,
+    /// `Output` is mapped with write access, `Input` with read access.
+    pub direction: Direction,
+}
+
+impl FmmuConfig {
+    /// The 16 register bytes of the FMMU (activated).
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut b = [0; 16];
+        b[0..4].copy_from_slice(&self.logical_start_address.to_le_bytes());
+        b[4..6].copy_from_slice(&self.length.to_le_bytes());
+        b[6] = self.logical_start_bit;
+        b[7] = self.logical_stop_bit;
+        b[8..10].copy_from_slice(&self.physical_start_address.to_le_bytes());
+        b[10] = self.physical_start_bit;
+        b[11] = match self.direction {
+            Direction::Input => 0x01,
+            Direction::Output => 0x02,
+        };
+        b[12] = 0x01;
+        b
+    }
+}
+
+impl Device {
+    /// FMMU configuration of the process data of the given PDOs.
+    ///
+    /// The output and input areas of the
+    /// [process image layout](Device::process_image_layout) are mapped to
+    /// the logical addresses `outputs` and `inputs`.
+    ///
+    /// Each sync manager with process data is mapped by one FMMU: the
+    /// FMMU that refers to it with [`FmmuUsage::sm`] or otherwise the next
+    /// free FMMU of the same direction. Devices without FMMU declarations
+    /// get one FMMU per sync manager. `MBoxState` FMMUs are not configured.
+    pub fn fmmu_config(
+        &self,
+        pdos: &[PdoIdx],
+        outputs: u32,
+        inputs: u32,
+    ) -> Result<Vec<FmmuConfig>> {
+        let layout = self.process_image_layout(pdos)?;
+        let mut used = vec![false; self.fmmu.len()];
+        let mut configs = vec![];
+
+        for (i, l) in layout.sms.iter().filter(|l| l.size > 0).enumerate() {
+            let idx = if self.fmmu.is_empty() {
+                i
+            } else {
+                let r#type = match l.direction {
+                    Direction::Output => FmmuType::Outputs,
+                    Direction::Input => FmmuType::Inputs,
+                };
+                let free = |f: usize| !used[f] && self.fmmu[f].r#type == r#type;
+                let idx = (0..self.fmmu.len())
+                    .find(|&f| free(f) && self.fmmu[f].sm == Some(l.sm))
+                    .or_else(|| {
+                        (0..self.fmmu.len()).find(|&f| free(f) && self.fmmu[f].sm.is_none())
+                    })
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("No FMMU available for SM {}", u8::from(l.sm)),
+                        )
+                    })?;
+                used[idx] = true;
+                idx
+            };
+            let base = match l.direction {
+                Direction::Output => outputs,
+                Direction::Input => inputs,
+            };
+            configs.push(FmmuConfig {
+                idx: to_u8(idx, "FMMU index")?,
+                logical_start_address: logical_address(base, l.offset)?,
+                length: u16::try_from(l.size).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Process data of SM {} is too large", u8::from(l.sm)),
+                    )
+                })?,
+                logical_start_bit: 0,
+                logical_stop_bit: 7,
+                physical_start_address: self.sm[usize::from(u8::from(l.sm))].start_address,
+                physical_start_bit: 0,
+                direction: l.direction,
+            });
+        }
+        configs.sort_by_key(|c| c.idx);
+        Ok(configs)
+    }
+}
+
+fn to_u8(v: usize, name: &str) -> Result<u8> {
+    u8::try_from(v).map_err(|_| Error::new(ErrorKind::InvalidData, format!("Invalid {}", name)))
+}
+
+fn logical_address(base: u32, offset: usize) -> Result<u32> {
+    u32::try_from(offset)
+        .ok()
+        .and_then(|o| base.checked_add(o))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Logical address out of range"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, sm};
+
+    fn pdo(idx: u16, sm: u8, bit_len: usize) -> Pdo {
+        Pdo {
+            mandatory: true,
+            name: LocalizedText::from("Channel"),
+            entries: vec![PdoEntry {
+                name: LocalizedText::from("Value"),
+                ..fixtures::entry(idx + 0x5000, 1, bit_len)
+            }],
+            ..fixtures::pdo(idx, sm, vec![])
+        }
+    }
+
+    fn fmmu(r#type: FmmuType, sm: Option<u8>) -> FmmuUsage {
+        FmmuUsage {
+            r#type,
+            sm: sm.map(SmIdx::from),
+            op_only: false,
+        }
+    }
+
+    fn device(fmmu: Vec<FmmuUsage>) -> Device {
+        Device {
+            name: LocalizedText::from("Foo"),
+            desc: "Foo".to_string(),
+            fmmu,
+            sm: vec![
+                sm(0x1000, Some(0x26)),
+                sm(0x1400, Some(0x22)),
+                sm(0x1800, Some(0x64)),
+                sm(0x1C00, Some(0x20)),
+            ],
+            rx_pdo: vec![pdo(0x1600, 2, 16)],
+            tx_pdo: vec![pdo(0x1A00, 3, 32), pdo(0x1A01, 3, 8)],
+            ..Device::default()
+        }
+    }
+
+    fn pdos() -> Vec<PdoIdx> {
+        vec![
+            PdoIdx::from(0x1600),
+            PdoIdx::from(0x1A00),
+            PdoIdx::from(0x1A01),
+        ]
+    }
+
+    #[test]
+    fn configure_fmmus() {
+        let d = device(vec![
+            fmmu(FmmuType::Outputs, None),
+            fmmu(FmmuType::Inputs, None),
+            fmmu(FmmuType::MBoxState, None),
+        ]);
+        let configs = d.fmmu_config(&pdos(), 0x1000, 0x2000).unwrap();
+        assert_eq!(
+            configs,
+            vec![
+                FmmuConfig {
+                    idx: 0,
+                    logical_start_address: 0x1000,
+                    length: 2,
+                    logical_start_bit: 0,
+                    logical_stop_bit: 7,
+                    physical_start_address: 0x1800,
+                    physical_start_bit: 0,
+                    direction: Direction::Output,
+                },
+                FmmuConfig {
+                    idx: 1,
+                    logical_start_address: 0x2000,
+                    length: 5,
+                    logical_start_bit: 0,
+                    logical_stop_bit: 7,
+                    physical_start_address: 0x1C00,
+                    physical_start_bit: 0,
+                    direction: Direction::Input,
+                },
+            ]
+        );
+        assert_eq!(
+            configs[1].to_bytes(),
+            [0x00, 0x20, 0, 0, 0x05, 0, 0, 7, 0x00, 0x1C, 0, 0x01, 0x01, 0, 0, 0]
+        );
+        assert_eq!(configs[0].to_bytes()[11], 0x02);
+    }
+
+    #[test]
+    fn prefer_fmmus_with_sm() {
+        let d = device(vec![
+            fmmu(FmmuType::Inputs, None),
+            fmmu(FmmuType::Inputs, Some(3)),
+            fmmu(FmmuType::Outputs, None),
+        ]);
+        let configs = d.fmmu_config(&pdos(), 0, 0x100).unwrap();
+        let idx: Vec<_> = configs.iter().map(|c| (c.idx, c.direction)).collect();
+        assert_eq!(idx, vec![(1, Direction::Input), (2, Direction::Output)]);
+
+        let defaults = device(vec![]).fmmu_config(&pdos(), 0, 0x100).unwrap();
+        assert_eq!(defaults.len(), 2);
+        assert_eq!(defaults[1].idx, 1);
+
+        let missing = device(vec![fmmu(FmmuType::Outputs, None)]);
+        let err = missing.fmmu_config(&pdos(), 0, 0x100).unwrap_err();
+        assert_eq!(err.to_string(), "No FMMU available for SM 3");
+    }
+}
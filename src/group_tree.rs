@@ -0,0 +1,203 @@
+//! Hierarchy of the device groups.
+
+use crate::structs::*;
+use std::{collections::HashSet, fmt};
+
+/// Groups nested by their parent group, with the devices of each group.
+///
+/// The [`Display`](fmt::Display) implementation renders the tree like the
+/// device browser of a configuration tool: one line per group or device,
+/// indented by its depth, with the names in the default language.
+#[derive(Debug, Clone)]
+pub struct GroupTree<'a> {
+    pub roots: Vec<GroupNode<'a>>,
+    /// Devices without group or with an unknown group type.
+    pub ungrouped: Vec<&'a Device>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupNode<'a> {
+    pub group: &'a Group,
+    pub children: Vec<GroupNode<'a>>,
+    pub devices: Vec<&'a Device>,
+}
+
+impl Description {
+    /// Nest the groups by [`Group::parent_group`] and attach the devices
+    /// by [`Device::group_type`].
+    ///
+    /// Sibling groups are ordered by [`Group::sort_order`] (groups without
+    /// sort order last), then by document order. Devices keep the document
+    /// order. Groups with an unknown parent and groups that are part of a
+    /// parent cycle become roots.
+    pub fn group_tree(&self) -> GroupTree<'_> {
+        let known: HashSet<_> = self.groups.iter().map(|g| g.r#type.as_str()).collect();
+        let mut visited = HashSet::new();
+
+        let top_level: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|g| match &g.parent_group {
+                Some(parent) => !known.contains(parent.as_str()),
+                None => true,
+            })
+            .collect();
+        let mut roots = self.nodes(top_level, &mut visited);
+        // Groups that are only reachable through a cycle
+        while let Some(g) = self
+            .groups
+            .iter()
+            .find(|g| !visited.contains(g.r#type.as_str()))
+        {
+            roots.extend(self.nodes(vec![g], &mut visited));
+        }
+        sort_groups(&mut roots);
+
+        let ungrouped = self
+            .devices
+            .iter()
+            .filter(|d| match &d.group_type {
+                Some(t) => !known.contains(t.as_str()),
+                None => true,
+            })
+            .collect();
+        GroupTree { roots, ungrouped }
+    }
+
+    fn nodes<'a>(
+        &'a self,
+        groups: Vec<&'a Group>,
+        visited: &mut HashSet<&'a str>,
+    ) -> Vec<GroupNode<'a>> {
+        let mut nodes = vec![];
+        for group in groups {
+            if !visited.insert(group.r#type.as_str()) {
+                continue;
+            }
+            let children = self
+                .groups
+                .iter()
+                .filter(|g| g.parent_group.as_deref() == Some(group.r#type.as_str()))
+                .collect();
+            let mut children = self.nodes(children, visited);
+            sort_groups(&mut children);
+            let devices = self
+                .devices
+                .iter()
+                .filter(|d| d.group_type.as_deref() == Some(group.r#type.as_str()))
+                .collect();
+            nodes.push(GroupNode {
+                group,
+                children,
+                devices,
+            });
+        }
+        nodes
+    }
+}
+
+fn sort_groups(nodes: &mut [GroupNode]) {
+    // stable, so the document order is kept for equal sort orders
+    nodes.sort_by_key(|n| (n.group.sort_order.is_none(), n.group.sort_order));
+}
+
+impl GroupTree<'_> {
+    /// All groups, depth first.
+    pub fn groups(&self) -> Vec<&GroupNode<'_>> {
+        fn walk<'a, 'b>(nodes: &'b [GroupNode<'a>], out: &mut Vec<&'b GroupNode<'a>>) {
+            for n in nodes {
+                out.push(n);
+                walk(&n.children, out);
+            }
+        }
+        let mut out = vec![];
+        walk(&self.roots, &mut out);
+        out
+    }
+}
+
+impl fmt::Display for GroupTree<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for n in &self.roots {
+            write_node(f, n, 0)?;
+        }
+        for d in &self.ungrouped {
+            write_device(f, d, 0)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_node(f: &mut fmt::Formatter, n: &GroupNode, depth: usize) -> fmt::Result {
+    let name = n.group.name.default_text().unwrap_or(&n.group.r#type);
+    writeln!(f, "{:indent$}{}", "", name, indent = depth * 2)?;
+    for c in &n.children {
+        write_node(f, c, depth + 1)?;
+    }
+    for d in &n.devices {
+        write_device(f, d, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn write_device(f: &mut fmt::Formatter, d: &Device, depth: usize) -> fmt::Result {
+    let name = d.name.default_text().unwrap_or(&d.desc);
+    writeln!(f, "{:indent$}{}", "", name, indent = depth * 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(t: &str, parent: Option<&str>, sort_order: Option<i32>) -> Group {
+        Group {
+            sort_order,
+            parent_group: parent.map(str::to_string),
+            r#type: t.to_string(),
+            name: LocalizedText::from(t),
+            ..Group::default()
+        }
+    }
+
+    fn device(name: &str, group_type: Option<&str>) -> Device {
+        Device {
+            name: LocalizedText::from(name),
+            desc: name.to_string(),
+            group_type: group_type.map(str::to_string),
+            ..Device::default()
+        }
+    }
+
+    #[test]
+    fn nest_and_order_groups() {
+        let description = Description {
+            groups: vec![
+                group("DigOut", Some("Terminals"), None),
+                group("Couplers", None, Some(1)),
+                group("DigIn", Some("Terminals"), Some(2)),
+                group("Terminals", None, Some(0)),
+                group("Orphan", Some("Missing"), None),
+                group("A", Some("B"), None),
+                group("B", Some("A"), None),
+            ],
+            devices: vec![
+                device("EL1008", Some("DigIn")),
+                device("EK1100", Some("Couplers")),
+                device("EL1004", Some("DigIn")),
+                device("Foo", None),
+                device("Bar", Some("Unknown")),
+            ],
+            modules: vec![],
+        };
+        let tree = description.group_tree();
+        let roots: Vec<_> = tree.roots.iter().map(|n| n.group.r#type.as_str()).collect();
+        assert_eq!(roots, vec!["Terminals", "Couplers", "Orphan", "A"]);
+        assert_eq!(tree.groups().len(), 7);
+        assert_eq!(tree.ungrouped.len(), 2);
+        assert_eq!(
+            tree.to_string(),
+            "Terminals\n  DigIn\n    EL1008\n    EL1004\n  DigOut\nCouplers\n  EK1100\n\
+             Orphan\nA\n  B\nFoo\nBar\n"
+        );
+    }
+}
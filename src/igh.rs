@@ -0,0 +1,463 @@
+//! Interoperability with the IgH EtherCAT master.
+
+use crate::{layout::*, structs::*, LocalizedText};
+use std::{
+    fmt::{self, Write},
+    io::{Error, ErrorKind, Result},
+};
+
+impl Device {
+    /// Generate the C arrays of the PDO configuration for the IgH master.
+    ///
+    /// The output has the same form as the one of `ethercat cstruct`:
+    /// an `ec_pdo_entry_info_t`, an `ec_pdo_info_t` and an `ec_sync_info_t`
+    /// array whose names start with `prefix` (e.g. `slave_0`).
+    /// The PDOs are assigned as in [`Device::process_image_layout`].
+    ///
+    /// The direction and the watchdog mode of a sync manager are taken
+    /// from its control byte. Without a control byte the direction of the
+    /// assigned PDOs is used.
+    pub fn igh_c_config(&self, prefix: &str, pdos: &[PdoIdx]) -> Result<String> {
+        if prefix.is_empty()
+            || prefix.starts_with(|c: char| c.is_ascii_digit())
+            || !prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("`{}` is not a valid C identifier", prefix),
+            ));
+        }
+        let layout = self.process_image_layout(pdos)?;
+        let mut code = String::new();
+        self.write_igh_c_config(&mut code, prefix, &layout)
+            .map_err(|e| Error::new(ErrorKind::Other, e))?;
+        Ok(code)
+    }
+
+    fn write_igh_c_config(
+        &self,
+        code: &mut String,
+        prefix: &str,
+        layout: &ProcessImageLayout,
+    ) -> fmt::Result {
+        let assigned: Vec<(&SmLayout, Vec<&Pdo>)> = layout
+            .sms
+            .iter()
+            .map(|sm| {
+                let pdos = sm
+                    .pdos
+                    .iter()
+                    .filter_map(|idx| {
+                        self.rx_pdo
+                            .iter()
+                            .chain(self.tx_pdo.iter())
+                            .find(|p| p.idx == *idx)
+                    })
+                    .collect();
+                (sm, pdos)
+            })
+            .collect();
+        let has_entries = assigned
+            .iter()
+            .flat_map(|(_, pdos)| pdos)
+            .any(|p| !p.entries.is_empty());
+
+        writeln!(code, "/* \"{}\"", comment(&self.desc))?;
+        if let Some(product_code) = self.product_code {
+            writeln!(code, " * Product code:    0x{:08x}", product_code)?;
+        }
+        if let Some(revision_no) = self.revision_no {
+            writeln!(code, " * Revision number: 0x{:08x}", revision_no)?;
+        }
+        writeln!(code, " */")?;
+
+        if has_entries {
+            writeln!(code)?;
+            writeln!(code, "ec_pdo_entry_info_t {}_pdo_entries[] = {{", prefix)?;
+            for pdo in assigned.iter().flat_map(|(_, pdos)| pdos) {
+                for e in &pdo.entries {
+                    write!(
+                        code,
+                        "    {{0x{:04x}, 0x{:02x}, {}}},",
+                        u16::from(e.entry_idx.idx),
+                        u8::from(e.entry_idx.sub_idx),
+                        e.bit_len
+                    )?;
+                    write_comment(code, &e.name)?;
+                }
+            }
+            writeln!(code, "}};")?;
+        }
+
+        if !layout.sms.is_empty() {
+            writeln!(code)?;
+            writeln!(code, "ec_pdo_info_t {}_pdos[] = {{", prefix)?;
+            let mut entry_pos = 0;
+            for pdo in assigned.iter().flat_map(|(_, pdos)| pdos) {
+                let entries = if pdo.entries.is_empty() {
+                    "NULL".to_string()
+                } else {
+                    format!("{}_pdo_entries + {}", prefix, entry_pos)
+                };
+                write!(
+                    code,
+                    "    {{0x{:04x}, {}, {}}},",
+                    u16::from(pdo.idx),
+                    pdo.entries.len(),
+                    entries
+                )?;
+                write_comment(code, &pdo.name)?;
+                entry_pos += pdo.entries.len();
+            }
+            writeln!(code, "}};")?;
+        }
+
+        writeln!(code)?;
+        writeln!(code, "ec_sync_info_t {}_syncs[] = {{", prefix)?;
+        let mut pdo_pos = 0;
+        for (i, sm) in self.sm.iter().enumerate() {
+            let assignment = assigned
+                .iter()
+                .find(|(l, _)| usize::from(u8::from(l.sm)) == i);
+            let direction = match sm
+                .direction()
+                .or_else(|| assignment.map(|(l, _)| l.direction))
+            {
+                Some(Direction::Output) => "EC_DIR_OUTPUT",
+                Some(Direction::Input) => "EC_DIR_INPUT",
+                None => "EC_DIR_INVALID",
+            };
+            let watchdog = match sm.watchdog() {
+                Some(true) => "EC_WD_ENABLE",
+                Some(false) => "EC_WD_DISABLE",
+                None => "EC_WD_DEFAULT",
+            };
+            let (n_pdos, pdos) = match assignment {
+                Some((_, pdos)) => {
+                    let p = (pdos.len(), format!("{}_pdos + {}", prefix, pdo_pos));
+                    pdo_pos += pdos.len();
+                    p
+                }
+                None => (0, "NULL".to_string()),
+            };
+            writeln!(
+                code,
+                "    {{{}, {}, {}, {}, {}}},",
+                i, direction, n_pdos, pdos, watchdog
+            )?;
+        }
+        writeln!(code, "    {{0xff}}")?;
+        writeln!(code, "}};")
+    }
+}
+
+impl Device {
+    /// Parse the output of `ethercat pdos` for a single slave.
+    ///
+    /// The output of `ethercat xml` is a regular ESI document and can be
+    /// read with [`EtherCatInfo::from_xml_str`](crate::EtherCatInfo::from_xml_str).
+    /// As the output contains neither the type nor the identity of the slave,
+    /// only the sync managers and PDOs are set.
+    pub fn from_igh_pdos_str(s: &str) -> Result<Self> {
+        let mut devices = Self::list_from_igh_pdos_str(s)?;
+        if devices.len() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected one slave, found {}", devices.len()),
+            ));
+        }
+        Ok(devices.remove(0))
+    }
+
+    /// Parse the output of `ethercat pdos` for several slaves.
+    ///
+    /// The slaves are separated by lines like `=== Master 0, Slave 1 ===`.
+    pub fn list_from_igh_pdos_str(s: &str) -> Result<Vec<Self>> {
+        let mut devices: Vec<Device> = vec![];
+        let mut current_pdo: Option<&str> = None;
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            let err = |msg: &str| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Line {}: {} (`{}`)", i + 1, msg, line),
+                )
+            };
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("===") {
+                devices.push(Device::default());
+                current_pdo = None;
+                continue;
+            }
+            if devices.is_empty() {
+                devices.push(Device::default());
+            }
+            let d = devices.last_mut().unwrap();
+
+            if let Some(rest) = line.strip_prefix("SM") {
+                let (idx, sm) = parse_igh_sm(rest).ok_or_else(|| err("Invalid sync manager"))?;
+                if idx != d.sm.len() {
+                    return Err(err("Unexpected sync manager index"));
+                }
+                d.sm.push(sm);
+                current_pdo = None;
+            } else if let Some(rest) = line.strip_prefix("PDO entry") {
+                let entry = parse_igh_entry(rest).ok_or_else(|| err("Invalid PDO entry"))?;
+                let pdos = match current_pdo {
+                    Some("RxPDO") => &mut d.rx_pdo,
+                    Some(_) => &mut d.tx_pdo,
+                    None => return Err(err("PDO entry without PDO")),
+                };
+                pdos.last_mut().unwrap().entries.push(entry);
+            } else if let Some(kind) = ["RxPDO", "TxPDO"].iter().find(|k| line.starts_with(*k)) {
+                let sm =
+                    d.sm.len()
+                        .checked_sub(1)
+                        .ok_or_else(|| err("PDO without SM"))?;
+                let pdo = parse_igh_pdo(&line[kind.len()..], SmIdx::from(sm as u8))
+                    .ok_or_else(|| err("Invalid PDO"))?;
+                if *kind == "RxPDO" {
+                    d.rx_pdo.push(pdo);
+                } else {
+                    d.tx_pdo.push(pdo);
+                }
+                current_pdo = Some(kind);
+            } else {
+                return Err(err("Unexpected line"));
+            }
+        }
+        Ok(devices)
+    }
+}
+
+/// `0: PhysAddr 0x1000, DefaultSize  128, ControlRegister 0x26, Enable 1`
+fn parse_igh_sm(s: &str) -> Option<(usize, Sm)> {
+    let (idx, rest) = s.split_once(':')?;
+    let mut sm = Sm {
+        enable: false,
+        start_address: 0,
+        control_byte: None,
+        default_size: None,
+        r#virtual: false,
+        r#type: None,
+        attributes: vec![],
+    };
+    for field in rest.split(',') {
+        let (name, value) = field.trim().split_once(' ')?;
+        let value = value.trim();
+        match name {
+            "PhysAddr" => sm.start_address = u16::from_str_radix(hex(value)?, 16).ok()?,
+            "DefaultSize" => sm.default_size = Some(value.parse().ok()?),
+            "ControlRegister" => sm.control_byte = Some(u8::from_str_radix(hex(value)?, 16).ok()?),
+            "Enable" => sm.enable = value.parse::<u8>().ok()? != 0,
+            _ => return None,
+        }
+    }
+    Some((idx.trim().parse().ok()?, sm))
+}
+
+/// ` 0x1600 "Channel 1"`
+fn parse_igh_pdo(s: &str, sm: SmIdx) -> Option<Pdo> {
+    let (idx, name) = s.trim().split_once(' ')?;
+    Some(Pdo {
+        sm: Some(sm),
+        fixed: false,
+        mandatory: false,
+        idx: PdoIdx::from(u16::from_str_radix(hex(idx)?, 16).ok()?),
+        depend_on_slot: false,
+        name: igh_name(name)?,
+        entries: vec![],
+        attributes: vec![],
+        unknown: vec![],
+    })
+}
+
+/// ` 0x7000:01,  1 bit, "Output"`
+fn parse_igh_entry(s: &str) -> Option<PdoEntry> {
+    let mut fields = s.splitn(3, ',');
+    let (idx, sub_idx) = fields.next()?.trim().split_once(':')?;
+    let bit_len = fields.next()?.trim().strip_suffix(" bit")?.trim();
+    Some(PdoEntry {
+        entry_idx: PdoEntryIdx::new(
+            u16::from_str_radix(hex(idx)?, 16).ok()?,
+            u8::from_str_radix(sub_idx, 16).ok()?,
+        ),
+        depend_on_slot: false,
+        bit_len: bit_len.parse().ok()?,
+        name: igh_name(fields.next()?)?,
+        data_type: None,
+        unknown: vec![],
+    })
+}
+
+fn hex(s: &str) -> Option<&str> {
+    s.strip_prefix("0x")
+}
+
+fn igh_name(s: &str) -> Option<LocalizedText> {
+    let name = s.trim().strip_prefix('"')?.strip_suffix('"')?;
+    if name.is_empty() {
+        Some(LocalizedText::new())
+    } else {
+        Some(LocalizedText::from(name))
+    }
+}
+
+fn write_comment(code: &mut String, names: &LocalizedText) -> fmt::Result {
+    let name = names.default_text().unwrap_or_default();
+    if name.is_empty() {
+        writeln!(code)
+    } else {
+        writeln!(code, " /* {} */", comment(name))
+    }
+}
+
+fn comment(s: &str) -> String {
+    s.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("*/", "* /")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, sm};
+
+    fn entry(idx: u16, sub_idx: u8, bit_len: usize, name: &str) -> PdoEntry {
+        PdoEntry {
+            name: LocalizedText::from(name),
+            ..fixtures::entry(idx, sub_idx, bit_len)
+        }
+    }
+
+    fn pdo(idx: u16, sm: u8, name: &str, entries: Vec<PdoEntry>) -> Pdo {
+        Pdo {
+            name: LocalizedText::from(name),
+            ..fixtures::pdo(idx, sm, entries)
+        }
+    }
+
+    fn device() -> Device {
+        Device {
+            desc: "EL2202".to_string(),
+            product_code: Some(0x089a3052),
+            revision_no: Some(0x0010_0000),
+            sm: vec![sm(0x1000, Some(0x64)), sm(0x1000, None)],
+            rx_pdo: vec![
+                pdo(0x1600, 0, "Channel 1", vec![entry(0x7000, 1, 1, "Output")]),
+                pdo(
+                    0x1601,
+                    0,
+                    "Channel 2",
+                    vec![entry(0x7010, 1, 1, "Output"), entry(0, 0, 7, "")],
+                ),
+            ],
+            tx_pdo: vec![pdo(0x1A00, 1, "Status", vec![])],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn generate_c_config() {
+        let pdos = [0x1600, 0x1601, 0x1A00].map(PdoIdx::from);
+        let code = device().igh_c_config("slave_1", &pdos).unwrap();
+        assert_eq!(
+            code,
+            r#"/* "EL2202"
+ * Product code:    0x089a3052
+ * Revision number: 0x00100000
+ */
+
+ec_pdo_entry_info_t slave_1_pdo_entries[] = {
+    {0x7000, 0x01, 1}, /* Output */
+    {0x7010, 0x01, 1}, /* Output */
+    {0x0000, 0x00, 7},
+};
+
+ec_pdo_info_t slave_1_pdos[] = {
+    {0x1600, 1, slave_1_pdo_entries + 0}, /* Channel 1 */
+    {0x1601, 2, slave_1_pdo_entries + 1}, /* Channel 2 */
+    {0x1a00, 0, NULL}, /* Status */
+};
+
+ec_sync_info_t slave_1_syncs[] = {
+    {0, EC_DIR_OUTPUT, 2, slave_1_pdos + 0, EC_WD_ENABLE},
+    {1, EC_DIR_INPUT, 1, slave_1_pdos + 2, EC_WD_DEFAULT},
+    {0xff}
+};
+"#
+        );
+    }
+
+    #[test]
+    fn sync_managers_without_pdos() {
+        let code = device().igh_c_config("slave_0", &[]).unwrap();
+        assert!(!code.contains("ec_pdo_entry_info_t"));
+        assert!(!code.contains("ec_pdo_info_t"));
+        assert!(code.contains("    {1, EC_DIR_INVALID, 0, NULL, EC_WD_DEFAULT},\n"));
+        assert!(device().igh_c_config("0slave", &[]).is_err());
+    }
+
+    const PDOS: &str = r#"SM0: PhysAddr 0x1000, DefaultSize  128, ControlRegister 0x26, Enable 1
+SM1: PhysAddr 0x1080, DefaultSize  128, ControlRegister 0x22, Enable 1
+SM2: PhysAddr 0x1100, DefaultSize    0, ControlRegister 0x64, Enable 1
+  RxPDO 0x1600 "Channel 1"
+    PDO entry 0x7000:01,  1 bit, "Output"
+    PDO entry 0x0000:00,  7 bit, ""
+SM3: PhysAddr 0x1180, DefaultSize    0, ControlRegister 0x20, Enable 1
+  TxPDO 0x1a00 "Channel 1"
+    PDO entry 0x6000:01, 16 bit, "Value, scaled"
+  TxPDO 0x1a01 "Status"
+"#;
+
+    #[test]
+    fn parse_pdos_output() {
+        let d = Device::from_igh_pdos_str(PDOS).unwrap();
+        assert_eq!(d.sm.len(), 4);
+        assert_eq!(d.sm[2].start_address, 0x1100);
+        assert_eq!(d.sm[2].control_byte, Some(0x64));
+        assert_eq!(d.sm[0].default_size, Some(128));
+        assert!(d.sm[3].enable);
+        assert_eq!(d.rx_pdo.len(), 1);
+        assert_eq!(d.rx_pdo[0].sm, Some(SmIdx::from(2)));
+        assert_eq!(d.rx_pdo[0].entries.len(), 2);
+        assert!(d.rx_pdo[0].entries[1].name.is_empty());
+        assert_eq!(d.tx_pdo.len(), 2);
+        assert_eq!(d.tx_pdo[0].idx, PdoIdx::from(0x1A00));
+        assert_eq!(d.tx_pdo[0].sm, Some(SmIdx::from(3)));
+        let e = &d.tx_pdo[0].entries[0];
+        assert_eq!(e.entry_idx, PdoEntryIdx::new(0x6000, 1));
+        assert_eq!(e.bit_len, 16);
+        assert_eq!(e.name, LocalizedText::from("Value, scaled"));
+        assert!(d.tx_pdo[1].entries.is_empty());
+    }
+
+    #[test]
+    fn parse_pdos_output_of_several_slaves() {
+        let s = format!(
+            "=== Master 0, Slave 0 ===\n{}\n=== Master 0, Slave 1 ===\n{}",
+            PDOS, PDOS
+        );
+        let devices = Device::list_from_igh_pdos_str(&s).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[1].tx_pdo.len(), 2);
+        assert!(Device::from_igh_pdos_str(&s).is_err());
+    }
+
+    #[test]
+    fn parse_invalid_pdos_output() {
+        let err = Device::from_igh_pdos_str("  RxPDO 0x1600 \"Foo\"").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 1: PDO without SM (`RxPDO 0x1600 \"Foo\"`)"
+        );
+        assert!(Device::from_igh_pdos_str("SM1: PhysAddr 0x1000").is_err());
+        assert!(Device::from_igh_pdos_str("Foo").is_err());
+    }
+}
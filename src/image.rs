@@ -0,0 +1,453 @@
+//! Decoding of the 16x14 icons of vendors, groups and devices.
+
+use crate::{loader::reference_path, structs::*};
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Decoded image with 8 bit RGBA pixels, row by row from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl HexBinary {
+    /// Decode the hex digits (whitespace is ignored).
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let digits: Vec<u8> = self
+            .0
+            .bytes()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        if digits.len() % 2 != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Odd number of hex digits",
+            ));
+        }
+        digits
+            .chunks(2)
+            .map(|pair| {
+                let s =
+                    std::str::from_utf8(pair).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                u8::from_str_radix(s, 16).map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid hex digits `{}`", s),
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+impl Image {
+    /// Decode the image.
+    ///
+    /// Image files are resolved relative to `base`, usually the
+    /// directory of the ESI file. Named images (`Image16x14`) refer to
+    /// images of the configuration tool and can't be decoded.
+    pub fn to_rgba(&self, base: impl AsRef<Path>) -> Result<RgbaImage> {
+        match self {
+            Image::Image16x14(name) => Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Named image `{}` can't be decoded", name),
+            )),
+            Image::ImageFile16x14(file) => {
+                let path = reference_path(base.as_ref(), file);
+                let data = fs::read(&path)
+                    .map_err(|e| Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+                RgbaImage::from_bmp(&data)
+            }
+            Image::ImageData16x14(hex) => RgbaImage::from_bmp(&hex.to_bytes()?),
+        }
+    }
+
+    /// Decode the image and encode it as PNG (see [`Image::to_rgba`]).
+    pub fn to_png(&self, base: impl AsRef<Path>) -> Result<Vec<u8>> {
+        self.to_rgba(base).map(|img| img.to_png())
+    }
+}
+
+impl RgbaImage {
+    /// Decode an uncompressed BMP image with 1, 4, 8, 16, 24 or 32 bits
+    /// per pixel, 16 and 32 bit images may use color masks (`BI_BITFIELDS`).
+    ///
+    /// The file header is optional. Magenta (`#FF00FF`), the background
+    /// color of ESI icons, becomes transparent.
+    pub fn from_bmp(data: &[u8]) -> Result<Self> {
+        let (dib, offset) = if data.starts_with(b"BM") {
+            (
+                data.get(14..).unwrap_or_default(),
+                u32_at(data, 10)? as usize,
+            )
+        } else {
+            (data, 0)
+        };
+        let header_len = u32_at(dib, 0)? as usize;
+        let (width, height, bit_count, compression, colors_used, entry_len) = if header_len == 12 {
+            (
+                i64::from(u16_at(dib, 4)?),
+                i64::from(u16_at(dib, 6)?),
+                u16_at(dib, 10)?,
+                0,
+                0,
+                3,
+            )
+        } else if header_len >= 40 {
+            (
+                i64::from(u32_at(dib, 4)? as i32),
+                i64::from(u32_at(dib, 8)? as i32),
+                u16_at(dib, 14)?,
+                u32_at(dib, 16)?,
+                u32_at(dib, 32)? as usize,
+                4,
+            )
+        } else {
+            return Err(invalid(format!(
+                "Unsupported BMP header size {}",
+                header_len
+            )));
+        };
+        let bitfields = compression == BI_BITFIELDS && matches!(bit_count, 16 | 32);
+        if compression != BI_RGB && !bitfields {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported BMP compression {}", compression),
+            ));
+        }
+        if !matches!(bit_count, 1 | 4 | 8 | 16 | 24 | 32) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported BMP bit count {}", bit_count),
+            ));
+        }
+        let top_down = height < 0;
+        let (width, height) = (width as usize, height.unsigned_abs() as usize);
+        if width == 0 || height == 0 || width > 0x4000 || height > 0x4000 {
+            return Err(invalid(format!("Invalid BMP size {}x{}", width, height)));
+        }
+
+        // The masks follow a `BITMAPINFOHEADER`, later headers contain them.
+        let masks = if bitfields {
+            [u32_at(dib, 40)?, u32_at(dib, 44)?, u32_at(dib, 48)?]
+        } else if bit_count == 16 {
+            [0x7C00, 0x03E0, 0x001F]
+        } else {
+            [0xFF_0000, 0xFF00, 0xFF]
+        };
+        let header_len = if bitfields && header_len == 40 {
+            header_len + 12
+        } else {
+            header_len
+        };
+
+        let palette_len = match bit_count {
+            1 | 4 | 8 if colors_used == 0 => 1 << bit_count,
+            1 | 4 | 8 => colors_used.min(1 << bit_count),
+            _ => 0,
+        };
+        let palette = dib
+            .get(header_len..header_len + palette_len * entry_len)
+            .ok_or_else(|| invalid("BMP palette is truncated"))?
+            .chunks(entry_len)
+            .map(|c| [c[2], c[1], c[0]])
+            .collect::<Vec<_>>();
+        let pixel_data = if offset > 0 {
+            data.get(offset..)
+        } else {
+            dib.get(header_len + palette_len * entry_len..)
+        }
+        .unwrap_or_default();
+
+        let bits = usize::from(bit_count);
+        let stride = (bits * width + 31) / 32 * 4;
+        if pixel_data.len() < stride * height {
+            return Err(invalid("BMP pixel data is truncated"));
+        }
+        let mut pixels = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            let row = if top_down { y } else { height - 1 - y };
+            let row = &pixel_data[row * stride..(row + 1) * stride];
+            for x in 0..width {
+                let rgb = match bit_count {
+                    16 => {
+                        let p = &row[x * 2..];
+                        let v = u32::from(u16::from_le_bytes([p[0], p[1]]));
+                        masks.map(|m| channel(v, m))
+                    }
+                    24 => {
+                        let p = &row[x * 3..];
+                        [p[2], p[1], p[0]]
+                    }
+                    32 => {
+                        let p = &row[x * 4..];
+                        let v = u32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+                        masks.map(|m| channel(v, m))
+                    }
+                    _ => {
+                        let bit = x * bits;
+                        let shift = 8 - bits - bit % 8;
+                        let idx = usize::from(row[bit / 8] >> shift) & ((1 << bits) - 1);
+                        *palette.get(idx).ok_or_else(|| {
+                            invalid(format!("BMP color index {} out of palette", idx))
+                        })?
+                    }
+                };
+                let alpha = if rgb == [0xFF, 0x00, 0xFF] { 0 } else { 0xFF };
+                pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], alpha]);
+            }
+        }
+        Ok(RgbaImage {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+
+    /// Encode the image as PNG (uncompressed).
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity((self.width as usize * 4 + 1) * self.height as usize);
+        for row in self.pixels.chunks(self.width as usize * 4) {
+            raw.push(0); // no filter
+            raw.extend_from_slice(row);
+        }
+        let mut ihdr = vec![];
+        ihdr.extend_from_slice(&self.width.to_be_bytes());
+        ihdr.extend_from_slice(&self.height.to_be_bytes());
+        // 8 bit RGBA, deflate, no interlace
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut png, b"IHDR", &ihdr);
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+/// The color channel of a pixel selected by `mask`, scaled to 8 bits.
+fn channel(pixel: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let max = u64::from(mask >> mask.trailing_zeros());
+    let value = u64::from((pixel & mask) >> mask.trailing_zeros());
+    (value * 0xFF / max) as u8
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// zlib stream with uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(u8::from(last));
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF_u32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for &x in data {
+        a = (a + u32::from(x)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn u16_at(data: &[u8], pos: usize) -> Result<u16> {
+    data.get(pos..pos + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or_else(|| invalid("BMP header is truncated"))
+}
+
+fn u32_at(data: &[u8], pos: usize) -> Result<u32> {
+    data.get(pos..pos + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| invalid("BMP header is truncated"))
+}
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x2 BMP with file header.
+    fn bmp(bit_count: u16, palette: &[[u8; 4]], rows: &[&[u8]]) -> Vec<u8> {
+        let pixels: Vec<u8> = rows.iter().flat_map(|r| r.iter().copied()).collect();
+        let offset = 14 + 40 + palette.len() * 4;
+        let mut data = b"BM".to_vec();
+        data.extend_from_slice(&((offset + pixels.len()) as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&(offset as u32).to_le_bytes());
+        data.extend_from_slice(&40_u32.to_le_bytes());
+        data.extend_from_slice(&2_i32.to_le_bytes());
+        data.extend_from_slice(&2_i32.to_le_bytes());
+        data.extend_from_slice(&1_u16.to_le_bytes());
+        data.extend_from_slice(&bit_count.to_le_bytes());
+        data.extend_from_slice(&[0; 16]);
+        data.extend_from_slice(&(palette.len() as u32).to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        for c in palette {
+            data.extend_from_slice(c);
+        }
+        data.extend_from_slice(&pixels);
+        data
+    }
+
+    /// 2x2 BMP with color masks after the info header.
+    fn bitfields_bmp(bit_count: u16, masks: [u32; 3], rows: &[&[u8]]) -> Vec<u8> {
+        let masks: Vec<_> = masks.iter().map(|m| m.to_le_bytes()).collect();
+        let mut data = bmp(bit_count, &masks, rows);
+        data[30..34].copy_from_slice(&BI_BITFIELDS.to_le_bytes());
+        data[46..50].copy_from_slice(&[0; 4]);
+        data
+    }
+
+    #[test]
+    fn hex_binary_to_bytes() {
+        let hex = HexBinary("424D 0aff\n".to_string());
+        assert_eq!(hex.to_bytes().unwrap(), vec![0x42, 0x4D, 0x0A, 0xFF]);
+        assert!(HexBinary("424".to_string()).to_bytes().is_err());
+        assert!(HexBinary("4G".to_string()).to_bytes().is_err());
+    }
+
+    #[test]
+    fn decode_palette_bmp() {
+        // blue, green, red, magenta (BGRx)
+        let palette = [
+            [255, 0, 0, 0],
+            [0, 255, 0, 0],
+            [0, 0, 255, 0],
+            [255, 0, 255, 0],
+        ];
+        // bottom row first
+        let data = bmp(4, &palette, &[&[0x23, 0, 0, 0], &[0x01, 0, 0, 0]]);
+        let img = RgbaImage::from_bmp(&data).unwrap();
+        assert_eq!((img.width, img.height), (2, 2));
+        assert_eq!(
+            img.pixels,
+            vec![
+                0, 0, 255, 255, 0, 255, 0, 255, // top: blue, green
+                255, 0, 0, 255, 255, 0, 255, 0, // bottom: red, transparent
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_24_bit_bmp() {
+        let data = bmp(
+            24,
+            &[],
+            &[&[1, 2, 3, 4, 5, 6, 0, 0], &[7, 8, 9, 10, 11, 12, 0, 0]],
+        );
+        let img = RgbaImage::from_bmp(&data).unwrap();
+        assert_eq!(&img.pixels[..8], &[9, 8, 7, 255, 12, 11, 10, 255]);
+        assert_eq!(&img.pixels[8..], &[3, 2, 1, 255, 6, 5, 4, 255]);
+        // without file header
+        let img2 = RgbaImage::from_bmp(&data[14..]).unwrap();
+        assert_eq!(img, img2);
+        assert!(RgbaImage::from_bmp(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn decode_16_bit_bmp() {
+        // 5-5-5, bottom row first: white, red, green, blue
+        let data = bmp(
+            16,
+            &[],
+            &[&[0xFF, 0x7F, 0x00, 0x7C], &[0xE0, 0x03, 0x1F, 0x00]],
+        );
+        let img = RgbaImage::from_bmp(&data).unwrap();
+        assert_eq!(
+            img.pixels,
+            vec![
+                0, 255, 0, 255, 0, 0, 255, 255, // top: green, blue
+                255, 255, 255, 255, 255, 0, 0, 255, // bottom: white, red
+            ]
+        );
+
+        // 5-6-5, bottom row first: red, green, magenta (transparent), black
+        let data = bitfields_bmp(
+            16,
+            [0xF800, 0x07E0, 0x001F],
+            &[&[0x00, 0xF8, 0xE0, 0x07], &[0x1F, 0xF8, 0x00, 0x00]],
+        );
+        let img = RgbaImage::from_bmp(&data).unwrap();
+        assert_eq!(&img.pixels[..8], &[255, 0, 255, 0, 0, 0, 0, 255]);
+        assert_eq!(&img.pixels[8..], &[255, 0, 0, 255, 0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn decode_32_bit_bitfields_bmp() {
+        // RGBA byte order instead of the default BGRx
+        let data = bitfields_bmp(
+            32,
+            [0xFF, 0xFF00, 0xFF_0000],
+            &[&[1, 2, 3, 0, 4, 5, 6, 0], &[7, 8, 9, 0, 10, 11, 12, 0]],
+        );
+        let img = RgbaImage::from_bmp(&data).unwrap();
+        assert_eq!(&img.pixels[..8], &[7, 8, 9, 255, 10, 11, 12, 255]);
+        assert_eq!(&img.pixels[8..], &[1, 2, 3, 255, 4, 5, 6, 255]);
+        assert_eq!(RgbaImage::from_bmp(&data[14..]).unwrap(), img);
+    }
+
+    #[test]
+    fn encode_png() {
+        let img = RgbaImage {
+            width: 1,
+            height: 1,
+            pixels: vec![1, 2, 3, 4],
+        };
+        let png = img.to_png();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // CRC of the empty IEND chunk
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]));
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        let zlib = zlib_stored(&[0, 1, 2, 3, 4]);
+        assert_eq!(&zlib[2..7], &[1, 5, 0, 0xFA, 0xFF]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Commands of the state transitions.
+
+use crate::structs::*;
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
+
+const CODES: &[(Transition, &str)] = &[
+    (Transition::InitToPreop, "IP"),
+    (Transition::PreopToSafeop, "PS"),
+    (Transition::PreopToInit, "PI"),
+    (Transition::SafeopToOp, "SO"),
+    (Transition::SafeopToPreop, "SP"),
+    (Transition::SafeopToInit, "SI"),
+    (Transition::OpToSafeop, "OS"),
+    (Transition::OpToPreop, "OP"),
+    (Transition::OpToInit, "OI"),
+    (Transition::InitToBoot, "IB"),
+    (Transition::BootToInit, "BI"),
+    (Transition::InitToInit, "II"),
+    (Transition::PreopToPreop, "PP"),
+    (Transition::SafeopToSafeop, "SS"),
+];
+
+impl Transition {
+    /// Abbreviation of the transition (e.g. `PS` for PREOP to SAFEOP).
+    pub fn code(self) -> &'static str {
+        CODES
+            .iter()
+            .find(|(t, _)| *t == self)
+            .map(|(_, c)| *c)
+            .expect("code of every transition")
+    }
+}
+
+impl fmt::Display for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Transition {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        CODES
+            .iter()
+            .find(|(_, c)| c.eq_ignore_ascii_case(s))
+            .map(|(t, _)| *t)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unknown transition `{}`", s),
+                )
+            })
+    }
+}
+
+impl Mailbox {
+    /// Commands to execute during the given transition, in document order.
+    pub fn init_cmds_for(&self, transition: Transition) -> impl Iterator<Item = &InitCmd> {
+        self.init_cmds
+            .iter()
+            .filter(move |c| c.transitions.contains(&transition))
+    }
+}
+
+impl Device {
+    /// Commands to execute during the given transition
+    /// (see [`Mailbox::init_cmds_for`]).
+    pub fn init_cmds(&self, transition: Transition) -> Vec<&InitCmd> {
+        self.mailbox
+            .iter()
+            .flat_map(|m| m.init_cmds_for(transition))
+            .collect()
+    }
+}
+
+impl Module {
+    /// Commands to execute during the given transition
+    /// (see [`Mailbox::init_cmds_for`]).
+    pub fn init_cmds(&self, transition: Transition) -> Vec<&InitCmd> {
+        self.mailbox
+            .iter()
+            .flat_map(|m| m.init_cmds_for(transition))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmd(transitions: Vec<Transition>, sub_idx: u8) -> InitCmd {
+        InitCmd {
+            transitions,
+            kind: InitCmdKind::Sdo {
+                idx: Idx::from(0x8000),
+                sub_idx: SubIdx::from(sub_idx),
+                complete_access: false,
+                depend_on_slot: false,
+            },
+            data: vec![sub_idx],
+            timeout: None,
+            comment: None,
+            attributes: vec![],
+            unknown: vec![],
+        }
+    }
+
+    #[test]
+    fn parse_and_format_transitions() {
+        assert_eq!(
+            "PS".parse::<Transition>().unwrap(),
+            Transition::PreopToSafeop
+        );
+        assert_eq!(" op".parse::<Transition>().unwrap(), Transition::OpToPreop);
+        assert!("XY".parse::<Transition>().is_err());
+        for (t, _) in CODES {
+            assert_eq!(t.to_string().parse::<Transition>().unwrap(), *t);
+        }
+    }
+
+    #[test]
+    fn select_commands_of_transition() {
+        use Transition::*;
+        let mailbox = Mailbox {
+            attributes: vec![],
+            init_cmds: vec![
+                cmd(vec![PreopToSafeop], 1),
+                cmd(vec![InitToPreop, PreopToSafeop], 2),
+                cmd(vec![SafeopToOp], 3),
+            ],
+            unknown: vec![],
+        };
+        let data: Vec<_> = mailbox
+            .init_cmds_for(PreopToSafeop)
+            .map(|c| c.data[0])
+            .collect();
+        assert_eq!(data, vec![1, 2]);
+        assert_eq!(mailbox.init_cmds_for(OpToInit).count(), 0);
+    }
+}
@@ -0,0 +1,295 @@
+//! Process image layout of selected PDOs.
+
+use crate::structs::*;
+use ethercat_types::Offset;
+use std::{
+    collections::BTreeMap,
+    io::{Error, ErrorKind, Result},
+};
+
+/// Direction of process data, seen from the master.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Data written by the master (`RxPdo`).
+    Output,
+    /// Data read by the master (`TxPdo`).
+    Input,
+}
+
+/// Layout of the process data of a slave.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessImageLayout {
+    /// Sync managers that carry process data, ordered by index.
+    pub sms: Vec<SmLayout>,
+    /// Size of the output area in bytes.
+    pub output_size: usize,
+    /// Size of the input area in bytes.
+    pub input_size: usize,
+}
+
+/// Layout of the process data of a single sync manager.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmLayout {
+    pub sm: SmIdx,
+    pub direction: Direction,
+    /// Assigned PDOs in assignment order.
+    pub pdos: Vec<PdoIdx>,
+    /// Offset of the SM data within the input or output area.
+    pub offset: usize,
+    /// Size of the SM data in bytes.
+    pub size: usize,
+    /// All mapped entries, except gaps.
+    pub entries: Vec<EntryLayout>,
+}
+
+/// Location of a mapped PDO entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryLayout {
+    pub pdo_idx: PdoIdx,
+    pub entry_idx: PdoEntryIdx,
+    pub name: LocalizedText,
+    pub data_type: Option<String>,
+    pub bit_len: usize,
+    pub direction: Direction,
+    /// Offset within the sync manager data.
+    pub sm_offset: Offset,
+    /// Offset within the input or output area of the slave.
+    pub offset: Offset,
+}
+
+impl Sm {
+    /// Direction of the sync manager according to its control byte.
+    pub fn direction(&self) -> Option<Direction> {
+        match self.control_byte? & 0x0C {
+            0x00 => Some(Direction::Input),
+            0x04 => Some(Direction::Output),
+            _ => None,
+        }
+    }
+
+    /// Whether the watchdog is enabled by the control byte.
+    pub fn watchdog(&self) -> Option<bool> {
+        self.control_byte.map(|c| c & 0x40 != 0)
+    }
+}
+
+impl ProcessImageLayout {
+    /// All mapped entries of the given direction.
+    pub fn entries(&self, direction: Direction) -> impl Iterator<Item = &EntryLayout> {
+        self.sms
+            .iter()
+            .filter(move |sm| sm.direction == direction)
+            .flat_map(|sm| sm.entries.iter())
+    }
+}
+
+impl Device {
+    /// Calculate the process image layout for the given PDOs.
+    ///
+    /// The PDOs are assigned to their sync managers in the given order.
+    /// Entries are packed bit by bit and each sync manager starts
+    /// at a byte boundary. The output and input areas contain the data
+    /// of the respective sync managers in ascending order.
+    pub fn process_image_layout(&self, pdos: &[PdoIdx]) -> Result<ProcessImageLayout> {
+        let mut assigned: BTreeMap<u8, (Direction, Vec<&Pdo>)> = BTreeMap::new();
+
+        for (i, idx) in pdos.iter().enumerate() {
+            if pdos[..i].contains(idx) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("PDO 0x{:04X} is selected more than once", u16::from(*idx)),
+                ));
+            }
+            let (pdo, direction) = self
+                .rx_pdo
+                .iter()
+                .find(|p| p.idx == *idx)
+                .map(|p| (p, Direction::Output))
+                .or_else(|| {
+                    self.tx_pdo
+                        .iter()
+                        .find(|p| p.idx == *idx)
+                        .map(|p| (p, Direction::Input))
+                })
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("PDO 0x{:04X} not found", u16::from(*idx)),
+                    )
+                })?;
+            let sm = pdo.sm.map(u8::from).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "PDO 0x{:04X} is not assigned to a sync manager",
+                        u16::from(*idx)
+                    ),
+                )
+            })?;
+            if usize::from(sm) >= self.sm.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("PDO 0x{:04X} refers to unknown SM {}", u16::from(*idx), sm),
+                ));
+            }
+            let (sm_direction, sm_pdos) = assigned.entry(sm).or_insert((direction, vec![]));
+            if *sm_direction != direction {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("SM {} contains inputs and outputs", sm),
+                ));
+            }
+            sm_pdos.push(pdo);
+        }
+
+        let mut output_size = 0;
+        let mut input_size = 0;
+        let mut sms = vec![];
+
+        for (sm, (direction, sm_pdos)) in assigned {
+            let area_size = match direction {
+                Direction::Output => &mut output_size,
+                Direction::Input => &mut input_size,
+            };
+            let mut bit_pos = 0;
+            let mut entries = vec![];
+            for pdo in &sm_pdos {
+                for e in &pdo.entries {
+                    if u16::from(e.entry_idx.idx) != 0 {
+                        let area_bit_pos = *area_size * 8 + bit_pos;
+                        entries.push(EntryLayout {
+                            pdo_idx: pdo.idx,
+                            entry_idx: e.entry_idx,
+                            name: e.name.clone(),
+                            data_type: e.data_type.clone(),
+                            bit_len: e.bit_len,
+                            direction,
+                            sm_offset: offset(bit_pos),
+                            offset: offset(area_bit_pos),
+                        });
+                    }
+                    bit_pos += e.bit_len;
+                }
+            }
+            let size = (bit_pos + 7) / 8;
+            sms.push(SmLayout {
+                sm: SmIdx::from(sm),
+                direction,
+                pdos: sm_pdos.iter().map(|p| p.idx).collect(),
+                offset: *area_size,
+                size,
+                entries,
+            });
+            *area_size += size;
+        }
+
+        Ok(ProcessImageLayout {
+            sms,
+            output_size,
+            input_size,
+        })
+    }
+}
+
+fn offset(bit_pos: usize) -> Offset {
+    Offset {
+        byte: bit_pos / 8,
+        bit: (bit_pos % 8) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{entry, pdo, sm};
+
+    fn device() -> Device {
+        Device {
+            desc: "Foo".to_string(),
+            sm: vec![sm(0x1000, None); 4],
+            rx_pdo: vec![
+                pdo(
+                    0x1600,
+                    2,
+                    vec![entry(0x7000, 1, 1), entry(0x7000, 2, 1), entry(0, 0, 6)],
+                ),
+                pdo(0x1601, 2, vec![entry(0x7010, 1, 16)]),
+            ],
+            tx_pdo: vec![
+                pdo(
+                    0x1A00,
+                    3,
+                    vec![entry(0x6000, 1, 3), entry(0x6000, 2, 16), entry(0, 0, 5)],
+                ),
+                pdo(0x1A01, 3, vec![entry(0x6010, 1, 32)]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn layout_of_selected_pdos() {
+        let d = device();
+        let pdos = [0x1A01, 0x1600, 0x1A00, 0x1601].map(PdoIdx::from);
+        let layout = d.process_image_layout(&pdos).unwrap();
+
+        assert_eq!(layout.output_size, 3);
+        assert_eq!(layout.input_size, 7);
+        assert_eq!(layout.sms.len(), 2);
+
+        let outputs = &layout.sms[0];
+        assert_eq!(outputs.sm, SmIdx::from(2));
+        assert_eq!(outputs.direction, Direction::Output);
+        assert_eq!(
+            outputs.pdos,
+            vec![PdoIdx::from(0x1600), PdoIdx::from(0x1601)]
+        );
+        assert_eq!(outputs.entries.len(), 3);
+        assert_eq!(outputs.entries[1].offset, Offset { byte: 0, bit: 1 });
+        assert_eq!(outputs.entries[2].offset, Offset { byte: 1, bit: 0 });
+
+        let inputs = &layout.sms[1];
+        assert_eq!(
+            inputs.pdos,
+            vec![PdoIdx::from(0x1A01), PdoIdx::from(0x1A00)]
+        );
+        assert_eq!(inputs.offset, 0);
+        assert_eq!(inputs.size, 7);
+        // 0x6000:02 follows a 3 bit entry and crosses the byte boundaries.
+        assert_eq!(inputs.entries[2].entry_idx, PdoEntryIdx::new(0x6000, 2));
+        assert_eq!(inputs.entries[2].sm_offset, Offset { byte: 4, bit: 3 });
+    }
+
+    #[test]
+    fn offsets_within_area() {
+        let mut d = device();
+        d.tx_pdo[1].sm = Some(SmIdx::from(1));
+        d.sm[1] = sm(0x1000, None);
+        let pdos = [0x1A00, 0x1A01].map(PdoIdx::from);
+        let layout = d.process_image_layout(&pdos).unwrap();
+        assert_eq!(layout.input_size, 7);
+        assert_eq!(layout.sms[0].sm, SmIdx::from(1));
+        assert_eq!(layout.sms[1].sm, SmIdx::from(3));
+        assert_eq!(layout.sms[1].offset, 4);
+        let e = &layout.sms[1].entries[0];
+        assert_eq!(e.sm_offset, Offset { byte: 0, bit: 0 });
+        assert_eq!(e.offset, Offset { byte: 4, bit: 0 });
+        assert_eq!(layout.entries(Direction::Input).count(), 3);
+        assert_eq!(layout.entries(Direction::Output).count(), 0);
+    }
+
+    #[test]
+    fn invalid_selection() {
+        let mut d = device();
+        assert!(d.process_image_layout(&[PdoIdx::from(0x1234)]).is_err());
+        assert!(d
+            .process_image_layout(&[PdoIdx::from(0x1600), PdoIdx::from(0x1600)])
+            .is_err());
+        d.tx_pdo[0].sm = Some(SmIdx::from(2));
+        assert!(d
+            .process_image_layout(&[PdoIdx::from(0x1600), PdoIdx::from(0x1A00)])
+            .is_err());
+        d.tx_pdo[0].sm = None;
+        assert!(d.process_image_layout(&[PdoIdx::from(0x1A00)]).is_err());
+    }
+}
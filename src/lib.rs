@@ -40,18 +40,239 @@
 
 use std::{
     convert::TryInto,
+    fmt,
     io::{Error, ErrorKind, Result},
 };
 
+mod codec;
+mod codegen;
+mod config;
+mod data_type;
+mod diagnosis;
+mod diff;
+mod ebus;
+#[cfg(test)]
+mod fixtures;
+mod fmmu;
+mod group_tree;
+mod igh;
+mod image;
+mod init_cmd;
+mod layout;
+mod loader;
+mod localized_text;
+mod merge;
 mod parser;
+mod schema;
+mod startup;
 mod structs;
+mod topology;
+mod validate;
+mod writer;
+mod xml;
 
+pub use codec::*;
+pub use codegen::*;
+pub use config::*;
+pub use diagnosis::*;
+pub use diff::*;
+pub use ebus::*;
+pub use fmmu::*;
+pub use group_tree::*;
+pub use image::*;
+pub use layout::*;
+pub use localized_text::*;
+pub use merge::*;
+pub use schema::*;
+pub use startup::*;
 pub use structs::*;
+pub use topology::*;
+pub use validate::*;
+pub use xml::{XmlElement, XmlNode};
+
+/// Options for [`EtherCatInfo::from_xml_str_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Reject documents that do not conform to the structure of
+    /// `EtherCATInfo.xsd`.
+    ///
+    /// In lenient mode the violations are reported as warnings.
+    pub strict: bool,
+    /// Skip or default invalid values instead of failing.
+    pub lenient: bool,
+}
+
+/// Something that was tolerated while parsing in lenient mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// Location of the tolerated element (e.g. `/EtherCATInfo/Vendor`).
+    pub path: String,
+    pub message: String,
+}
+
+impl Warning {
+    pub(crate) fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Warning {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
 
 impl EtherCatInfo {
     pub fn from_xml_str(xml: &str) -> Result<Self> {
-        let raw_info: parser::EtherCATInfo = serde_xml_rs::from_reader(xml.as_bytes())
-            .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
-        raw_info.try_into()
+        Self::from_xml_str_with_options(xml, &ParseOptions::default()).map(|(info, _)| info)
+    }
+
+    /// Like [`EtherCatInfo::from_xml_str`] but rejects documents that
+    /// do not conform to the structure of `EtherCATInfo.xsd`.
+    ///
+    /// The returned error contains a [`SchemaError`] listing all violations.
+    pub fn from_xml_str_strict(xml: &str) -> Result<Self> {
+        let opts = ParseOptions {
+            strict: true,
+            ..Default::default()
+        };
+        Self::from_xml_str_with_options(xml, &opts).map(|(info, _)| info)
+    }
+
+    /// Parse with the given options.
+    ///
+    /// Warnings are only collected in lenient mode.
+    pub fn from_xml_str_with_options(
+        xml: &str,
+        opts: &ParseOptions,
+    ) -> Result<(Self, Vec<Warning>)> {
+        let tree = xml::parse(xml)?;
+        parse(&tree, opts)
+    }
+}
+
+impl EtherCatModule {
+    /// Parse a module description file (`EtherCATModule` root element).
+    pub fn from_xml_str(xml: &str) -> Result<Self> {
+        Self::from_xml_str_with_options(xml, &ParseOptions::default()).map(|(m, _)| m)
+    }
+
+    /// Parse with the given options.
+    ///
+    /// Warnings are only collected in lenient mode.
+    pub fn from_xml_str_with_options(
+        xml: &str,
+        opts: &ParseOptions,
+    ) -> Result<(Self, Vec<Warning>)> {
+        let tree = xml::parse(xml)?;
+        expect_root(&tree, "EtherCATModule")?;
+        let (info, warnings) = parse(&tree, opts)?;
+        Ok((EtherCatModule::from(info), warnings))
+    }
+}
+
+impl From<EtherCatInfo> for EtherCatModule {
+    fn from(info: EtherCatInfo) -> Self {
+        EtherCatModule {
+            version: info.version,
+            vendor: info.vendor,
+            modules: info.description.modules,
+            attributes: info.attributes,
+            namespaces: info.namespaces,
+            unknown: info.unknown,
+        }
+    }
+}
+
+impl Dictionary {
+    /// Parse a dictionary file (`EtherCATDict` root element).
+    ///
+    /// Device profiles refer to such files with `DictionaryFile`
+    /// (see [`EtherCatInfo::attach_dictionary`]).
+    pub fn from_xml_str(xml: &str) -> Result<Self> {
+        let tree = xml::parse(xml)?;
+        expect_root(&tree, "EtherCATDict")?;
+        dictionary(&tree)
+    }
+}
+
+/// An ESI document of any supported kind.
+#[derive(Debug, Clone)]
+pub enum EsiDocument {
+    Info(EtherCatInfo),
+    Module(EtherCatModule),
+    Dict(Dictionary),
+}
+
+impl EsiDocument {
+    /// Parse a document, depending on its root element.
+    pub fn from_xml_str(xml: &str) -> Result<Self> {
+        Self::from_xml_str_with_options(xml, &ParseOptions::default()).map(|(doc, _)| doc)
+    }
+
+    /// Parse with the given options.
+    ///
+    /// Warnings are only collected in lenient mode.
+    /// Dictionary files are not checked against the schema.
+    pub fn from_xml_str_with_options(
+        xml: &str,
+        opts: &ParseOptions,
+    ) -> Result<(Self, Vec<Warning>)> {
+        let tree = xml::parse(xml)?;
+        match tree.name.as_str() {
+            "EtherCATInfo" => {
+                parse(&tree, opts).map(|(info, warnings)| (EsiDocument::Info(info), warnings))
+            }
+            "EtherCATModule" => parse(&tree, opts)
+                .map(|(info, warnings)| (EsiDocument::Module(info.into()), warnings)),
+            "EtherCATDict" => Ok((EsiDocument::Dict(dictionary(&tree)?), vec![])),
+            _ => Err(Error::new(
+                ErrorKind::Unsupported,
+                format!("Unsupported root element `{}`", tree.name),
+            )),
+        }
+    }
+}
+
+fn expect_root(tree: &XmlElement, name: &str) -> Result<()> {
+    if tree.name != name {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Expected root element `{}`, found `{}`", name, tree.name),
+        ));
+    }
+    Ok(())
+}
+
+/// Convert the content of an `EtherCATDict` document.
+fn dictionary(tree: &XmlElement) -> Result<Dictionary> {
+    let dict = tree.children().find(|c| c.name == "Dictionary");
+    parser::dictionary_from(dict.unwrap_or(tree))
+}
+
+/// Convert a document with the structure of `EtherCATInfo`.
+fn parse(tree: &XmlElement, opts: &ParseOptions) -> Result<(EtherCatInfo, Vec<Warning>)> {
+    let mut warnings = vec![];
+    if opts.strict {
+        let violations = check_tree(tree);
+        if opts.lenient {
+            warnings.extend(
+                violations
+                    .into_iter()
+                    .map(|v| Warning::new(v.path, v.message)),
+            );
+        } else if !violations.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, SchemaError(violations)));
+        }
     }
+    let raw_info = parser::EtherCATInfo::from(tree);
+    let info = if opts.lenient {
+        raw_info.try_into_lenient(&tree.name, &mut warnings)?
+    } else {
+        raw_info.try_into()?
+    };
+    Ok((info, warnings))
 }
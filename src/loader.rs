@@ -0,0 +1,303 @@
+//! Loading of ESI files and the documents they refer to.
+
+use crate::{structs::*, EsiDocument};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+impl EtherCatInfo {
+    /// Read an ESI file and load the documents it refers to.
+    ///
+    /// The modules of the document referenced by `InfoReference`
+    /// (an `EtherCATInfo` or `EtherCATModule` document) are
+    /// appended to the modules of this document, and the dictionaries
+    /// referenced by `DictionaryFile` are attached to the device and
+    /// module profiles. References are resolved relative to the referencing
+    /// file. The file names are used as source of the items
+    /// (see [`EtherCatInfo::set_source`]).
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Loader::default().load(path.as_ref())
+    }
+
+    /// Load the documents this document refers to.
+    ///
+    /// `base` is the directory that relative references are resolved to,
+    /// usually the directory of the file this document was read from.
+    /// See [`EtherCatInfo::from_file`].
+    pub fn resolve_references(&mut self, base: impl AsRef<Path>) -> Result<()> {
+        Loader::default().resolve(self, base.as_ref())
+    }
+
+    /// Attach a dictionary to the device and module profiles that refer
+    /// to it with `DictionaryFile`.
+    ///
+    /// `file` is compared with the references as given in the document
+    /// (Windows path separators are accepted).
+    /// Profiles that already have a dictionary are left unchanged.
+    /// Returns the number of profiles the dictionary was attached to.
+    pub fn attach_dictionary(&mut self, file: &str, dictionary: &Dictionary) -> usize {
+        let d = &mut self.description;
+        let profiles = d
+            .devices
+            .iter_mut()
+            .flat_map(|d| d.profiles.iter_mut())
+            .chain(d.modules.iter_mut().flat_map(|m| m.profiles.iter_mut()));
+        attach(profiles, file, dictionary)
+    }
+}
+
+fn attach<'a>(
+    profiles: impl Iterator<Item = &'a mut Profile>,
+    file: &str,
+    dictionary: &Dictionary,
+) -> usize {
+    let file = normalize(file);
+    let mut count = 0;
+    for p in profiles {
+        let refers = p.dictionary_file.as_deref().map(normalize) == Some(file.clone());
+        if refers && p.dictionary.is_none() {
+            p.dictionary = Some(dictionary.clone());
+            count += 1;
+        }
+    }
+    count
+}
+
+/// References to dictionary files without an attached dictionary.
+fn dictionary_files<'a>(profiles: impl Iterator<Item = &'a Profile>) -> Vec<String> {
+    let mut files: Vec<_> = profiles
+        .filter(|p| p.dictionary.is_none())
+        .filter_map(|p| p.dictionary_file.clone())
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+#[derive(Default)]
+struct Loader {
+    /// Files that are currently being loaded, to detect cycles.
+    stack: Vec<PathBuf>,
+    dictionaries: HashMap<PathBuf, Dictionary>,
+}
+
+impl Loader {
+    fn load(&mut self, path: &Path) -> Result<EtherCatInfo> {
+        match self.load_document(path)? {
+            EsiDocument::Info(info) => Ok(info),
+            EsiDocument::Module(_) | EsiDocument::Dict(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "{}: Module description and dictionary files can only be referenced",
+                    path.display()
+                ),
+            )),
+        }
+    }
+
+    fn load_document(&mut self, path: &Path) -> Result<EsiDocument> {
+        let key = canonicalize(path)?;
+        if let Some(pos) = self.stack.iter().position(|p| *p == key) {
+            let cycle: Vec<_> = self.stack[pos..]
+                .iter()
+                .chain(Some(&key))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Reference cycle: {}", cycle.join(" -> ")),
+            ));
+        }
+        let source = path.display().to_string();
+        let base = path.parent().unwrap_or(Path::new(""));
+        let doc = EsiDocument::from_xml_str(&read(path)?).map_err(|e| with_path(path, e))?;
+        match doc {
+            EsiDocument::Info(mut info) => {
+                info.set_source(&source);
+                self.stack.push(key);
+                let result = self.resolve(&mut info, base);
+                self.stack.pop();
+                result?;
+                Ok(EsiDocument::Info(info))
+            }
+            EsiDocument::Module(mut m) => {
+                for module in &mut m.modules {
+                    module.source = Some(source.clone());
+                }
+                let profiles = m.modules.iter().flat_map(|m| m.profiles.iter());
+                for file in dictionary_files(profiles) {
+                    let dictionary = self.load_dictionary(&reference_path(base, &file))?;
+                    let profiles = m.modules.iter_mut().flat_map(|m| m.profiles.iter_mut());
+                    attach(profiles, &file, &dictionary);
+                }
+                Ok(EsiDocument::Module(m))
+            }
+            EsiDocument::Dict(_) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: Unexpected dictionary file", path.display()),
+            )),
+        }
+    }
+
+    fn resolve(&mut self, info: &mut EtherCatInfo, base: &Path) -> Result<()> {
+        if let Some(reference) = &info.info_reference {
+            let modules = match self.load_document(&reference_path(base, reference))? {
+                EsiDocument::Info(referenced) => referenced.description.modules,
+                EsiDocument::Module(referenced) => referenced.modules,
+                EsiDocument::Dict(_) => unreachable!("rejected by load_document"),
+            };
+            info.description.modules.extend(modules);
+        }
+        let d = &info.description;
+        let profiles = d
+            .devices
+            .iter()
+            .flat_map(|d| d.profiles.iter())
+            .chain(d.modules.iter().flat_map(|m| m.profiles.iter()));
+        for file in dictionary_files(profiles) {
+            let dictionary = self.load_dictionary(&reference_path(base, &file))?;
+            info.attach_dictionary(&file, &dictionary);
+        }
+        Ok(())
+    }
+
+    fn load_dictionary(&mut self, path: &Path) -> Result<Dictionary> {
+        let key = canonicalize(path)?;
+        if let Some(dictionary) = self.dictionaries.get(&key) {
+            return Ok(dictionary.clone());
+        }
+        let dictionary = Dictionary::from_xml_str(&read(path)?).map_err(|e| with_path(path, e))?;
+        self.dictionaries.insert(key, dictionary.clone());
+        Ok(dictionary)
+    }
+}
+
+/// ESI files often use Windows path separators.
+pub(crate) fn reference_path(base: &Path, reference: &str) -> PathBuf {
+    base.join(normalize(reference))
+}
+
+fn normalize(reference: &str) -> String {
+    reference.trim().replace('\\', "/")
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf> {
+    fs::canonicalize(path).map_err(|e| with_path(path, e))
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| with_path(path, e))
+}
+
+fn with_path(path: &Path, err: Error) -> Error {
+    Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    const VENDOR: &str = "<Vendor><Id>2</Id></Vendor>";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ethercat-esi-{}-{}", name, process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    fn info(reference: &str, devices: &str, modules: &str) -> String {
+        format!(
+            "<EtherCATInfo>{}{}<Descriptions><Devices>{}</Devices>{}</Descriptions></EtherCATInfo>",
+            reference, VENDOR, devices, modules
+        )
+    }
+
+    #[test]
+    fn follow_references() {
+        let dir = temp_dir("references");
+        let main = info(
+            r"<InfoReference>sub\Modules.xml</InfoReference>",
+            r"<Device>
+                <Type>Foo</Type>
+                <Name>Foo</Name>
+                <Profile><DictionaryFile>sub\Dict.xml</DictionaryFile></Profile>
+              </Device>",
+            "",
+        );
+        let modules = format!(
+            "<EtherCATModule>{}<Modules><Module><Type>M1</Type><Name>M1</Name>{}</Module></Modules></EtherCATModule>",
+            VENDOR, "<Profile><DictionaryFile>Dict.xml</DictionaryFile></Profile>"
+        );
+        fs::write(dir.join("Main.xml"), main).unwrap();
+        fs::write(dir.join("sub/Modules.xml"), modules).unwrap();
+
+        let err = EtherCatInfo::from_file(dir.join("Main.xml")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert!(err.to_string().contains("Dict.xml"));
+
+        fs::write(
+            dir.join("sub/Dict.xml"),
+            "<EtherCATDict><Dictionary><Objects><Object>
+                <Index>#x1000</Index><Name>Device type</Name><Type>UDINT</Type><BitSize>32</BitSize>
+             </Object></Objects></Dictionary></EtherCATDict>",
+        )
+        .unwrap();
+        let info = EtherCatInfo::from_file(dir.join("Main.xml")).unwrap();
+        assert_eq!(info.description.modules.len(), 1);
+        let module = &info.description.modules[0];
+        assert_eq!(
+            module.source,
+            Some(dir.join("sub/Modules.xml").display().to_string())
+        );
+        let dictionary = module.profiles[0].dictionary.as_ref().unwrap();
+        assert_eq!(dictionary.objects[0].idx, Idx::from(0x1000));
+        let device = &info.description.devices[0];
+        assert_eq!(device.profiles[0].dictionary.as_ref(), Some(dictionary));
+
+        let err = EtherCatInfo::from_file(dir.join("sub/Modules.xml")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn attach_dictionary() {
+        let xml = info(
+            "",
+            r"<Device>
+                <Type>Foo</Type>
+                <Name>Foo</Name>
+                <Profile><DictionaryFile>dicts\Foo.xml</DictionaryFile></Profile>
+              </Device>
+              <Device><Type>Bar</Type><Name>Bar</Name><Profile/></Device>",
+            "",
+        );
+        let mut info = EtherCatInfo::from_xml_str(&xml).unwrap();
+        let dictionary = Dictionary::default();
+        assert_eq!(info.attach_dictionary("Foo.xml", &dictionary), 0);
+        assert_eq!(info.attach_dictionary("dicts/Foo.xml", &dictionary), 1);
+        assert_eq!(info.attach_dictionary("dicts/Foo.xml", &dictionary), 0);
+        assert!(info.description.devices[0].profiles[0].dictionary.is_some());
+        assert!(info.description.devices[1].profiles[0].dictionary.is_none());
+    }
+
+    #[test]
+    fn detect_reference_cycles() {
+        let dir = temp_dir("cycle");
+        let a = info("<InfoReference>B.xml</InfoReference>", "", "");
+        let b = info("<InfoReference>A.xml</InfoReference>", "", "");
+        fs::write(dir.join("A.xml"), a).unwrap();
+        fs::write(dir.join("B.xml"), b).unwrap();
+
+        let err = EtherCatInfo::from_file(dir.join("A.xml")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("Reference cycle: "));
+        assert!(err.to_string().ends_with("A.xml"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
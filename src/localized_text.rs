@@ -0,0 +1,272 @@
+//! Texts in several languages.
+
+use std::{fmt, iter::FromIterator};
+
+/// Language of the texts without a language ID.
+pub const DEFAULT_LC_ID: u16 = 1033;
+
+/// A human readable text in several languages.
+///
+/// The languages are identified by Windows locale IDs (LCID),
+/// e.g. 1031 for German (`de-DE`) and 1033 for English (`en-US`).
+/// Texts without a language ID are taken as English.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct LocalizedText {
+    texts: Vec<(String, Option<u16>)>,
+}
+
+impl LocalizedText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, text: impl Into<String>, lc_id: Option<u16>) {
+        self.texts.push((text.into(), lc_id));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// The texts with their language IDs, in document order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, Option<u16>)> {
+        self.texts.iter().map(|(t, id)| (t.as_str(), *id))
+    }
+
+    /// The text in exactly the given language.
+    pub fn get(&self, lc_id: u16) -> Option<&str> {
+        self.iter()
+            .find(|(_, id)| id.unwrap_or(DEFAULT_LC_ID) == lc_id)
+            .map(|(t, _)| t)
+    }
+
+    /// The text in the language of a BCP-47 tag (e.g. `de-DE` or `de`).
+    ///
+    /// Texts of another region of the same language are accepted
+    /// if there is no exact match.
+    pub fn get_by_tag(&self, tag: &str) -> Option<&str> {
+        if let Some(text) = lc_id_from_tag(tag).and_then(|id| self.get(id)) {
+            return Some(text);
+        }
+        let language = primary_subtag(tag);
+        self.iter()
+            .find(|(_, id)| {
+                tag_from_lc_id(id.unwrap_or(DEFAULT_LC_ID))
+                    .is_some_and(|t| primary_subtag(t).eq_ignore_ascii_case(language))
+            })
+            .map(|(t, _)| t)
+    }
+
+    /// The English text or, if there is none, the first one.
+    pub fn default_text(&self) -> Option<&str> {
+        self.get(DEFAULT_LC_ID)
+            .or_else(|| self.texts.first().map(|(t, _)| t.as_str()))
+    }
+
+    /// The text in the first available language of the fallback chain.
+    ///
+    /// For every language of the chain an exact match is preferred over
+    /// the same language of another region (same primary language ID).
+    /// If none matches, the [default text](LocalizedText::default_text)
+    /// is returned.
+    pub fn resolve(&self, fallback: &[u16]) -> Option<&str> {
+        fallback
+            .iter()
+            .find_map(|&lc_id| {
+                self.get(lc_id).or_else(|| {
+                    self.iter()
+                        .find(|(_, id)| {
+                            primary_language(id.unwrap_or(DEFAULT_LC_ID)) == primary_language(lc_id)
+                        })
+                        .map(|(t, _)| t)
+                })
+            })
+            .or_else(|| self.default_text())
+    }
+
+    /// Like [`LocalizedText::resolve`] with a chain of BCP-47 tags.
+    ///
+    /// Unknown tags are skipped.
+    pub fn resolve_tags(&self, fallback: &[&str]) -> Option<&str> {
+        let fallback: Vec<_> = fallback.iter().filter_map(|t| lc_id_from_tag(t)).collect();
+        self.resolve(&fallback)
+    }
+}
+
+/// Formatted like the list of texts with their language IDs.
+impl fmt::Debug for LocalizedText {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.texts.iter()).finish()
+    }
+}
+
+/// A text without language ID.
+impl From<&str> for LocalizedText {
+    fn from(text: &str) -> Self {
+        LocalizedText {
+            texts: vec![(text.to_string(), None)],
+        }
+    }
+}
+
+impl From<Vec<(String, Option<u16>)>> for LocalizedText {
+    fn from(texts: Vec<(String, Option<u16>)>) -> Self {
+        LocalizedText { texts }
+    }
+}
+
+impl FromIterator<(String, Option<u16>)> for LocalizedText {
+    fn from_iter<I: IntoIterator<Item = (String, Option<u16>)>>(iter: I) -> Self {
+        LocalizedText {
+            texts: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Windows locale IDs and BCP-47 tags of common languages.
+const LANGUAGES: &[(u16, &str)] = &[
+    (1025, "ar-SA"),
+    (1026, "bg-BG"),
+    (1028, "zh-TW"),
+    (1029, "cs-CZ"),
+    (1030, "da-DK"),
+    (1031, "de-DE"),
+    (1032, "el-GR"),
+    (1033, "en-US"),
+    (1034, "es-ES"),
+    (1035, "fi-FI"),
+    (1036, "fr-FR"),
+    (1037, "he-IL"),
+    (1038, "hu-HU"),
+    (1040, "it-IT"),
+    (1041, "ja-JP"),
+    (1042, "ko-KR"),
+    (1043, "nl-NL"),
+    (1044, "nb-NO"),
+    (1045, "pl-PL"),
+    (1046, "pt-BR"),
+    (1048, "ro-RO"),
+    (1049, "ru-RU"),
+    (1050, "hr-HR"),
+    (1051, "sk-SK"),
+    (1053, "sv-SE"),
+    (1054, "th-TH"),
+    (1055, "tr-TR"),
+    (1057, "id-ID"),
+    (1058, "uk-UA"),
+    (1060, "sl-SI"),
+    (1061, "et-EE"),
+    (1062, "lv-LV"),
+    (1063, "lt-LT"),
+    (1066, "vi-VN"),
+    (1081, "hi-IN"),
+    (2052, "zh-CN"),
+    (2055, "de-CH"),
+    (2057, "en-GB"),
+    (2058, "es-MX"),
+    (2060, "fr-BE"),
+    (2064, "it-CH"),
+    (2067, "nl-BE"),
+    (2070, "pt-PT"),
+    (3079, "de-AT"),
+    (3081, "en-AU"),
+    (3084, "fr-CA"),
+    (4105, "en-CA"),
+    (4108, "fr-CH"),
+];
+
+/// The Windows locale ID of a BCP-47 tag (e.g. 1031 for `de-DE`).
+///
+/// A tag without region or with an unknown region refers to the
+/// main region of the language (e.g. `de` to `de-DE`).
+pub fn lc_id_from_tag(tag: &str) -> Option<u16> {
+    let tag = tag.trim().replace('_', "-");
+    LANGUAGES
+        .iter()
+        .find(|(_, t)| t.eq_ignore_ascii_case(&tag))
+        .or_else(|| {
+            LANGUAGES
+                .iter()
+                .find(|(_, t)| primary_subtag(t).eq_ignore_ascii_case(primary_subtag(&tag)))
+        })
+        .map(|(id, _)| *id)
+}
+
+/// The BCP-47 tag of a Windows locale ID (e.g. `de-DE` for 1031).
+pub fn tag_from_lc_id(lc_id: u16) -> Option<&'static str> {
+    LANGUAGES
+        .iter()
+        .find(|(id, _)| *id == lc_id)
+        .map(|(_, t)| *t)
+}
+
+fn primary_subtag(tag: &str) -> &str {
+    tag.split(['-', '_']).next().unwrap_or_default()
+}
+
+/// The primary language ID is stored in the lower 10 bits of an LCID.
+fn primary_language(lc_id: u16) -> u16 {
+    lc_id & 0x3FF
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text() -> LocalizedText {
+        vec![
+            ("Eingänge".to_string(), Some(1031)),
+            ("Inputs".to_string(), None),
+            ("Entrées".to_string(), Some(3084)),
+        ]
+        .into()
+    }
+
+    #[test]
+    fn convert_lc_ids_and_tags() {
+        assert_eq!(lc_id_from_tag("de-DE"), Some(1031));
+        assert_eq!(lc_id_from_tag("en_us"), Some(1033));
+        assert_eq!(lc_id_from_tag("de"), Some(1031));
+        assert_eq!(lc_id_from_tag("de-LU"), Some(1031));
+        assert_eq!(lc_id_from_tag("xx-XX"), None);
+        assert_eq!(tag_from_lc_id(1031), Some("de-DE"));
+        assert_eq!(tag_from_lc_id(1033), Some("en-US"));
+        assert_eq!(tag_from_lc_id(1), None);
+    }
+
+    #[test]
+    fn look_up_texts() {
+        let t = text();
+        assert_eq!(t.get(1031), Some("Eingänge"));
+        assert_eq!(t.get(1033), Some("Inputs"));
+        assert_eq!(t.get(3079), None);
+        assert_eq!(t.get_by_tag("de-DE"), Some("Eingänge"));
+        assert_eq!(t.get_by_tag("de-AT"), Some("Eingänge"));
+        assert_eq!(t.get_by_tag("fr"), Some("Entrées"));
+        assert_eq!(t.get_by_tag("it"), None);
+        assert_eq!(t.default_text(), Some("Inputs"));
+        assert_eq!(LocalizedText::new().default_text(), None);
+        let german: LocalizedText = vec![("Eingänge".to_string(), Some(1031))].into();
+        assert_eq!(german.default_text(), Some("Eingänge"));
+    }
+
+    #[test]
+    fn resolve_with_fallback_chain() {
+        let t = text();
+        assert_eq!(t.resolve(&[1040, 3079, 1033]), Some("Eingänge"));
+        assert_eq!(t.resolve(&[4108]), Some("Entrées"));
+        assert_eq!(t.resolve(&[1040]), Some("Inputs"));
+        assert_eq!(t.resolve(&[]), Some("Inputs"));
+        assert_eq!(t.resolve_tags(&["xx", "fr-FR", "de"]), Some("Entrées"));
+    }
+
+    #[test]
+    fn debug_format() {
+        let t = LocalizedText::from("Foo");
+        assert_eq!(format!("{:?}", t), r#"[("Foo", None)]"#);
+    }
+}
@@ -0,0 +1,159 @@
+//! Merging of ESI documents.
+
+use crate::{
+    diff::{device_key, device_label, diff_devices, diff_modules},
+    structs::*,
+    Diff,
+};
+use std::{
+    fmt, fs,
+    io::{Error, ErrorKind, Result},
+    mem,
+    path::Path,
+};
+
+/// Two different definitions of the same device or module.
+///
+/// The first definition is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// The device (e.g. `EK1100 [0x044c2c52 rev 0x00110000]`) or module.
+    pub item: String,
+    /// Source of the kept definition.
+    pub kept: Option<String>,
+    /// Source of the dropped definition.
+    pub dropped: Option<String>,
+    /// Differences of the dropped definition to the kept one.
+    pub diff: Diff,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is defined differently in {} and {} ({} differences)",
+            self.item,
+            self.kept.as_deref().unwrap_or("(unknown)"),
+            self.dropped.as_deref().unwrap_or("(unknown)"),
+            self.diff.changes.len()
+        )
+    }
+}
+
+impl EtherCatInfo {
+    /// Set the source of all groups, devices and modules.
+    pub fn set_source(&mut self, source: &str) {
+        let d = &mut self.description;
+        for g in &mut d.groups {
+            g.source = Some(source.to_string());
+        }
+        for dev in &mut d.devices {
+            dev.source = Some(source.to_string());
+        }
+        for m in &mut d.modules {
+            m.source = Some(source.to_string());
+        }
+    }
+
+    /// Merge the descriptions of several documents of the same vendor.
+    ///
+    /// Groups and modules with the same type and devices with the same
+    /// identity (see [`diff`](crate::diff())) are only taken once.
+    /// Differing definitions of the same device or module are reported as
+    /// [`MergeConflict`]s. Vendor, version and info reference are taken
+    /// from the first document.
+    pub fn merge(infos: Vec<EtherCatInfo>) -> Result<(EtherCatInfo, Vec<MergeConflict>)> {
+        let mut infos = infos.into_iter();
+        let mut merged = infos
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Nothing to merge"))?;
+        let mut descriptions = vec![mem::take(&mut merged.description)];
+        for info in infos {
+            if info.vendor.id != merged.vendor.id {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Can't merge descriptions of vendor 0x{:08x} and 0x{:08x}",
+                        merged.vendor.id, info.vendor.id
+                    ),
+                ));
+            }
+            merged.unknown.extend(info.unknown);
+            descriptions.push(info.description);
+        }
+
+        let mut conflicts = vec![];
+        let mut groups: Vec<Group> = vec![];
+        let mut devices: Vec<Device> = vec![];
+        let mut modules: Vec<Module> = vec![];
+
+        for description in descriptions {
+            for g in description.groups {
+                if !groups.iter().any(|other| other.r#type == g.r#type) {
+                    groups.push(g);
+                }
+            }
+            for d in description.devices {
+                match devices
+                    .iter()
+                    .find(|other| device_key(other) == device_key(&d))
+                {
+                    Some(kept) => {
+                        let diff = diff_devices(kept, &d);
+                        if !diff.is_empty() {
+                            conflicts.push(MergeConflict {
+                                item: device_label(&d),
+                                kept: kept.source.clone(),
+                                dropped: d.source.clone(),
+                                diff,
+                            });
+                        }
+                    }
+                    None => devices.push(d),
+                }
+            }
+            for m in description.modules {
+                match modules.iter().find(|other| other.r#type == m.r#type) {
+                    Some(kept) => {
+                        let diff = diff_modules(kept, &m);
+                        if !diff.is_empty() {
+                            conflicts.push(MergeConflict {
+                                item: format!("module {}", m.r#type),
+                                kept: kept.source.clone(),
+                                dropped: m.source.clone(),
+                                diff,
+                            });
+                        }
+                    }
+                    None => modules.push(m),
+                }
+            }
+        }
+        merged.description = Description {
+            groups,
+            devices,
+            modules,
+        };
+        Ok((merged, conflicts))
+    }
+
+    /// Read and merge ESI files.
+    ///
+    /// The file names are used as source of the items
+    /// (see [`EtherCatInfo::merge`]).
+    pub fn merge_files<P: AsRef<Path>>(
+        paths: impl IntoIterator<Item = P>,
+    ) -> Result<(EtherCatInfo, Vec<MergeConflict>)> {
+        let infos = paths
+            .into_iter()
+            .map(|p| {
+                let path = p.as_ref();
+                let xml = fs::read_to_string(path)?;
+                let mut info = EtherCatInfo::from_xml_str(&xml)?;
+                info.set_source(&path.display().to_string());
+                Ok(info)
+            })
+            .collect::<Result<_>>()?;
+        Self::merge(infos)
+    }
+}
@@ -1,4 +1,18 @@
-use super::*;
+// The conversions predate these lints.
+#![allow(
+    clippy::iter_overeager_cloned,
+    clippy::match_like_matches_macro,
+    clippy::unwrap_or_default
+)]
+
+use super::{
+    dictionary::profiles_from,
+    fmmu::fmmus_from,
+    info::{device_info_from, physics_from},
+    lenient::Tolerance,
+    mailbox::mailbox_from,
+    *,
+};
 use crate::structs as S;
 use ethercat_types as ec;
 use std::{convert::TryFrom, num::ParseIntError, str::FromStr};
@@ -11,10 +25,7 @@ impl TryFrom<EtherCATInfo> for S::EtherCatInfo {
             None => S::Description::default(),
         };
 
-        if let Some(Modules {
-            items: Some(modules),
-        }) = x.Modules
-        {
+        if let Some(Modules { items: modules }) = x.Modules {
             modules
                 .into_iter()
                 .map(S::Module::try_from)
@@ -28,8 +39,14 @@ impl TryFrom<EtherCATInfo> for S::EtherCatInfo {
         Ok(S::EtherCatInfo {
             version: x.Version,
             info_reference: x.InfoReference,
-            vendor: x.Vendor.try_into()?,
+            vendor: x
+                .Vendor
+                .ok_or_else(|| missing("element", "Vendor", "EtherCATInfo"))?
+                .try_into()?,
             description,
+            attributes: x.attributes,
+            namespaces: x.namespaces,
+            unknown: x.unknown,
         })
     }
 }
@@ -39,13 +56,18 @@ impl TryFrom<Vendor> for S::Vendor {
     fn try_from(v: Vendor) -> Result<Self> {
         let image = v.image()?;
         Ok(S::Vendor {
-            file_version: v.FileVersion,
-            id: u32_from_hex_dec_value(&v.Id)?,
-            name: names_from(v.Name.unwrap_or_default())?,
+            file_version: v
+                .FileVersion
+                .as_deref()
+                .map(u32_from_hex_dec_value)
+                .transpose()?,
+            id: u32_from_hex_dec_value(&v.Id.ok_or_else(|| missing("element", "Id", "Vendor"))?)?,
+            name: names_from(v.Name)?,
             comment: v.Comment,
             url: v.URL,
             desc_url: v.DescriptionURL,
             image,
+            unknown: v.unknown,
         })
     }
 }
@@ -93,34 +115,42 @@ impl Group {
     }
 }
 
+impl Device {
+    fn image(&self) -> Result<Option<S::Image>> {
+        let mut images = self.items.iter().filter_map(|p| match p {
+            DeviceProperty::Image16x14(img) => Some(S::Image::Image16x14(img.clone())),
+            DeviceProperty::ImageFile16x14(img) => Some(S::Image::ImageFile16x14(img.clone())),
+            DeviceProperty::ImageData16x14(img) => {
+                Some(S::Image::ImageData16x14(S::HexBinary(img.clone())))
+            }
+            _ => None,
+        });
+        let image = images.next();
+        if images.next().is_some() {
+            return Err(Error::new(ErrorKind::Other, "Multiple images found"));
+        }
+        Ok(image)
+    }
+}
+
 impl TryFrom<Descriptions> for S::Description {
     type Error = Error;
     fn try_from(d: Descriptions) -> Result<Self> {
         let groups: Vec<_> = d
             .Groups
-            .map(|groups| {
-                groups
-                    .items
-                    .map(|items| items.into_iter().map(S::Group::try_from).collect())
-                    .unwrap_or_else(|| Ok(vec![]))
-            })
+            .map(|groups| groups.items.into_iter().map(S::Group::try_from).collect())
             .unwrap_or_else(|| Ok(vec![]))?;
 
         let devices: Vec<_> = d
             .Devices
             .items
-            .unwrap_or_else(Vec::new)
             .into_iter()
             .map(S::Device::try_from)
             .collect::<Result<_>>()?;
 
         let modules: Vec<_> = d
             .Modules
-            .map(|dev| {
-                dev.items
-                    .map(|items| items.into_iter().map(S::Module::try_from).collect())
-                    .unwrap_or_else(|| Ok(vec![]))
-            })
+            .map(|dev| dev.items.into_iter().map(S::Module::try_from).collect())
             .unwrap_or_else(|| Ok(vec![]))?;
 
         Ok(S::Description {
@@ -174,12 +204,21 @@ impl TryFrom<Group> for S::Group {
             .ok_or_else(|| Error::new(ErrorKind::Other, "Mandatory group type not found"))?;
 
         Ok(S::Group {
-            sort_order: g.SortOrder,
+            sort_order: g
+                .SortOrder
+                .map(|s| {
+                    s.trim()
+                        .parse()
+                        .map_err(|e| Error::new(ErrorKind::Other, e))
+                })
+                .transpose()?,
             parent_group: g.ParentGroup,
             name,
             comment,
             r#type,
             image,
+            unknown: g.unknown,
+            source: None,
         })
     }
 }
@@ -187,6 +226,13 @@ impl TryFrom<Group> for S::Group {
 impl TryFrom<Device> for S::Device {
     type Error = Error;
     fn try_from(d: Device) -> Result<Self> {
+        Device::convert(d, &mut Tolerance::strict())
+    }
+}
+
+impl Device {
+    pub(super) fn convert(d: Self, t: &mut Tolerance) -> Result<S::Device> {
+        let image = d.image()?;
         let props = d.items.iter();
         let name = props
             .clone()
@@ -224,6 +270,17 @@ impl TryFrom<Device> for S::Device {
             .transpose()?;
         let desc = d_type.Description.to_owned();
 
+        let group_type = props
+            .clone()
+            .filter_map(|p| {
+                if let DeviceProperty::GroupType(t) = p {
+                    Some(t.trim().to_string())
+                } else {
+                    None
+                }
+            })
+            .next();
+
         let sm = props
             .clone()
             .filter_map(|p| {
@@ -272,15 +329,34 @@ impl TryFrom<Device> for S::Device {
             .map(S::Pdo::try_from)
             .collect::<Result<_>>()?;
 
+        let fmmu = fmmus_from(&d.fmmu, t)?;
         Ok(S::Device {
-            physics: d.Physics,
+            physics: d.Physics.as_deref().map(physics_from).unwrap_or_default(),
             name,
             desc,
             product_code,
             revision_no,
+            group_type,
+            info: d
+                .info
+                .as_ref()
+                .map(|e| device_info_from(e, &mut t.nested("Info")))
+                .transpose()?,
+            fmmu,
             sm,
             rx_pdo,
             tx_pdo,
+            mailbox: d
+                .mailbox
+                .as_ref()
+                .map(|e| mailbox_from(e, &mut t.nested("Mailbox")))
+                .transpose()?,
+            image,
+            profiles: profiles_from(&d.profiles, t)?,
+            attributes: d.attributes,
+            type_attributes: d_type.attributes.clone(),
+            unknown: d.unknown,
+            source: None,
         })
     }
 }
@@ -289,7 +365,11 @@ impl TryFrom<Sm> for S::Sm {
     type Error = Error;
     fn try_from(sm: Sm) -> Result<Self> {
         Ok(S::Sm {
-            start_address: u16_from_hex_dec_value(&sm.StartAddress)?,
+            start_address: u16_from_hex_dec_value(
+                sm.StartAddress
+                    .as_deref()
+                    .ok_or_else(|| missing("attribute", "StartAddress", "Sm"))?,
+            )?,
             control_byte: sm
                 .ControlByte
                 .as_deref()
@@ -301,8 +381,15 @@ impl TryFrom<Sm> for S::Sm {
             } else {
                 None
             },
-            enable: sm.Enable == Some(1),
+            enable: sm
+                .Enable
+                .as_deref()
+                .map(u8_from_hex_dec_value)
+                .transpose()?
+                == Some(1),
             r#virtual: sm.Virtual.as_deref().map(bool_from_str).transpose()? == Some(true),
+            r#type: sm.text,
+            attributes: sm.attributes,
         })
     }
 }
@@ -322,14 +409,26 @@ impl TryFrom<Pdo> for S::Pdo {
                 false
             },
             name: names_from(pdo.Name)?,
-            sm: pdo.Sm.map(ec::SmIdx::from),
-            idx: ec::PdoIdx::from(u16_from_hex_dec_value(&pdo.Index.value)?),
+            sm: pdo
+                .Sm
+                .as_deref()
+                .map(u8_from_hex_dec_value)
+                .transpose()?
+                .map(ec::SmIdx::from),
+            idx: ec::PdoIdx::from(u16_from_hex_dec_value(
+                pdo.Index
+                    .as_deref()
+                    .ok_or_else(|| missing("element", "Index", "Pdo"))?,
+            )?),
+            depend_on_slot: pdo.DependOnSlot.as_deref().map(bool_from_str).transpose()?
+                == Some(true),
             entries: pdo
                 .Entry
-                .unwrap_or_default()
                 .into_iter()
                 .map(S::PdoEntry::try_from)
                 .collect::<Result<_>>()?,
+            attributes: pdo.attributes,
+            unknown: pdo.unknown,
         })
     }
 }
@@ -339,15 +438,25 @@ impl TryFrom<Entry> for S::PdoEntry {
     fn try_from(e: Entry) -> Result<Self> {
         Ok(S::PdoEntry {
             entry_idx: S::PdoEntryIdx {
-                idx: ec::Idx::from(u16_from_hex_dec_value(&e.Index.value)?),
+                idx: ec::Idx::from(u16_from_hex_dec_value(
+                    e.Index
+                        .as_deref()
+                        .ok_or_else(|| missing("element", "Index", "Entry"))?,
+                )?),
                 sub_idx: match e.SubIndex {
                     Some(idx_string) => ec::SubIdx::from(u8_from_hex_dec_value(&idx_string)?),
                     None => ec::SubIdx::from(0),
                 },
             },
-            bit_len: e.BitLen,
-            name: names_from(e.Name.unwrap_or_default())?,
+            depend_on_slot: e.DependOnSlot.as_deref().map(bool_from_str).transpose()? == Some(true),
+            bit_len: u32_from_hex_dec_value(
+                e.BitLen
+                    .as_deref()
+                    .ok_or_else(|| missing("element", "BitLen", "Entry"))?,
+            )? as usize,
+            name: names_from(e.Name)?,
             data_type: e.DataType,
+            unknown: e.unknown,
         })
     }
 }
@@ -355,49 +464,64 @@ impl TryFrom<Entry> for S::PdoEntry {
 impl TryFrom<Module> for S::Module {
     type Error = Error;
     fn try_from(m: Module) -> Result<Self> {
+        Module::convert(m, &mut Tolerance::strict())
+    }
+}
+
+impl Module {
+    pub(super) fn convert(m: Self, t: &mut Tolerance) -> Result<S::Module> {
         let rx_pdo = m
             .RxPdo
-            .unwrap_or_default()
             .into_iter()
             .map(S::Pdo::try_from)
             .collect::<Result<_>>()?;
 
         let tx_pdo = m
             .TxPdo
-            .unwrap_or_default()
             .into_iter()
             .map(S::Pdo::try_from)
             .collect::<Result<_>>()?;
 
         Ok(S::Module {
             name: names_from(m.Name)?,
-            r#type: m.Type,
+            r#type: m
+                .Type
+                .ok_or_else(|| Error::new(ErrorKind::Other, "Mandatory module type not found"))?,
             rx_pdo,
             tx_pdo,
-            mailbox: None,
-            profile: None,
+            mailbox: m
+                .mailbox
+                .as_ref()
+                .map(|e| mailbox_from(e, &mut t.nested("Mailbox")))
+                .transpose()?,
+            profiles: profiles_from(&m.profiles, t)?,
+            attributes: m.attributes,
+            type_attributes: m.type_attributes,
+            unknown: m.unknown,
+            source: None,
         })
     }
 }
 
-fn names_from(names: Vec<Name>) -> Result<S::Names> {
+pub(super) fn names_from(names: Vec<Name>) -> Result<S::LocalizedText> {
     names
         .into_iter()
-        .filter_map(|n| {
-            let lc_id = if let Some(val) = n.LcId {
-                match u16_from_hex_dec_value(&val) {
-                    Err(e) => return Some(Err(e)),
-                    Ok(val) => Some(val),
-                }
-            } else {
-                None
-            };
-            n.value.map(|name| Ok((name, lc_id)))
+        .map(|n| {
+            let lc_id = n.LcId.as_deref().map(u16_from_hex_dec_value).transpose()?;
+            // Empty names are kept, so they can be written back.
+            Ok((n.value.unwrap_or_default(), lc_id))
         })
         .collect()
 }
 
-fn bool_from_str(v: &str) -> Result<bool> {
+fn missing(kind: &str, name: &str, parent: &str) -> Error {
+    Error::new(
+        ErrorKind::Other,
+        format!("Mandatory {} `{}` of `{}` not found", kind, name, parent),
+    )
+}
+
+pub(super) fn bool_from_str(v: &str) -> Result<bool> {
     match &*v.to_lowercase() {
         "1" | "true" => Ok(true),
         "0" | "false" => Ok(false),
@@ -408,15 +532,19 @@ fn bool_from_str(v: &str) -> Result<bool> {
     }
 }
 
-fn u32_from_hex_dec_value(v: &str) -> Result<u32> {
+pub(super) fn u32_from_hex_dec_value(v: &str) -> Result<u32> {
     from_hex_dec_value(v, |x| u32::from_str_radix(x, 16))
 }
 
-fn u16_from_hex_dec_value(v: &str) -> Result<u16> {
+pub(super) fn i32_from_hex_dec_value(v: &str) -> Result<i32> {
+    from_hex_dec_value(v, |x| i32::from_str_radix(x, 16))
+}
+
+pub(super) fn u16_from_hex_dec_value(v: &str) -> Result<u16> {
     from_hex_dec_value(v, |x| u16::from_str_radix(x, 16))
 }
 
-fn u8_from_hex_dec_value(v: &str) -> Result<u8> {
+pub(super) fn u8_from_hex_dec_value(v: &str) -> Result<u8> {
     from_hex_dec_value(v, |x| u8::from_str_radix(x, 16))
 }
 
@@ -454,6 +582,14 @@ mod tests {
         assert_eq!(u32_from_hex_dec_value("XF75").unwrap(), 0xf75);
     }
 
+    #[test]
+    fn parse_i32() {
+        assert_eq!(i32_from_hex_dec_value("-500").unwrap(), -500);
+        assert_eq!(i32_from_hex_dec_value("#x1F4").unwrap(), 500);
+        assert_eq!(i32_from_hex_dec_value("0x10").unwrap(), 16);
+        assert!(i32_from_hex_dec_value("#xZZ").is_err());
+    }
+
     #[test]
     fn parse_u16() {
         assert_eq!(u16_from_hex_dec_value("0").unwrap(), 0);
@@ -481,6 +617,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn parse_bool_from_str() {
         assert_eq!(bool_from_str("1").unwrap(), true);
         assert_eq!(bool_from_str("true").unwrap(), true);
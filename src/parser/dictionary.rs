@@ -0,0 +1,389 @@
+//! Conversion of device profiles and object dictionaries.
+
+use super::{conversions::*, lenient::Tolerance, tree::*, *};
+use crate::structs as S;
+use ethercat_types as ec;
+
+/// Child elements of `Profile` that are interpreted.
+const PROFILE_ELEMENTS: &[&str] = &[
+    "ProfileNo",
+    "AddInfo",
+    "Dictionary",
+    "DictionaryFile",
+    "DiagMessages",
+];
+
+/// Child elements of `Dictionary` that are interpreted.
+const DICTIONARY_ELEMENTS: &[&str] = &["DataTypes", "Objects"];
+
+/// Child elements of `Flags` that are interpreted.
+const FLAGS_ELEMENTS: &[&str] = &["Access", "Category", "PdoMapping", "SdoAccess"];
+
+pub(super) fn profiles_from(elements: &[XmlElement], t: &mut Tolerance) -> Result<Vec<S::Profile>> {
+    elements
+        .iter()
+        .enumerate()
+        .map(|(i, e)| profile_from(e, &mut t.nested(&format!("Profile[{}]", i + 1))))
+        .collect()
+}
+
+/// Convert a `Profile` element.
+///
+/// Invalid values, data types, objects and messages are tolerated
+/// on their own, see [`items_from`].
+fn profile_from(e: &XmlElement, t: &mut Tolerance) -> Result<S::Profile> {
+    let mut unknown = unknown_children(e, PROFILE_ELEMENTS);
+    let mut number = |name: &str| match child(e, name) {
+        Some(c) => t.convert(
+            c,
+            name,
+            |c| u32_from_hex_dec_value(c.text().unwrap_or_default().trim()),
+            &mut unknown,
+        ),
+        None => Ok(None),
+    };
+    let profile_no = number("ProfileNo")?;
+    let add_info = number("AddInfo")?;
+    let dictionary = match child(e, "Dictionary") {
+        Some(d) => Some(dictionary_with(d, &mut t.nested("Dictionary"))?),
+        None => None,
+    };
+    let diag_messages = items_from(
+        e,
+        "DiagMessages",
+        "DiagMessage",
+        diag_message_from,
+        t,
+        &mut unknown,
+    )?;
+    Ok(S::Profile {
+        profile_no,
+        add_info,
+        dictionary,
+        dictionary_file: text(e, "DictionaryFile").map(str::to_string),
+        diag_messages,
+        unknown,
+    })
+}
+
+fn diag_message_from(e: &XmlElement) -> Result<S::DiagMessage> {
+    Ok(S::DiagMessage {
+        text_id: u16_from_hex_dec_value(required(e, "TextId")?)?,
+        text: names(e, "MessageText")?,
+    })
+}
+
+/// Convert a `Dictionary` element.
+pub(crate) fn dictionary_from(e: &XmlElement) -> Result<S::Dictionary> {
+    dictionary_with(e, &mut Tolerance::strict())
+}
+
+fn dictionary_with(e: &XmlElement, t: &mut Tolerance) -> Result<S::Dictionary> {
+    let mut unknown = unknown_children(e, DICTIONARY_ELEMENTS);
+    let data_types = items_from(e, "DataTypes", "DataType", data_type_from, t, &mut unknown)?;
+    let objects = items_from(e, "Objects", "Object", object_from, t, &mut unknown)?;
+    Ok(S::Dictionary {
+        data_types,
+        objects,
+        unknown,
+    })
+}
+
+/// Convert the items of the list elements with the given name
+/// (e.g. `Object` of `Objects`).
+///
+/// The items that are tolerated are kept in a list element of `unknown`.
+fn items_from<T>(
+    e: &XmlElement,
+    list: &str,
+    item: &str,
+    convert: fn(&XmlElement) -> Result<T>,
+    t: &mut Tolerance,
+    unknown: &mut Vec<XmlElement>,
+) -> Result<Vec<T>> {
+    let mut items = vec![];
+    let mut invalid = vec![];
+    for (i, c) in children(e, list)
+        .flat_map(|l| children(l, item))
+        .enumerate()
+    {
+        let path = format!("{}/{}[{}]", list, item, i + 1);
+        items.extend(t.convert(c, &path, convert, &mut invalid)?);
+    }
+    if !invalid.is_empty() {
+        let mut rest = XmlElement::new(list);
+        rest.nodes = invalid.into_iter().map(XmlNode::Element).collect();
+        unknown.push(rest);
+    }
+    Ok(items)
+}
+
+fn data_type_from(e: &XmlElement) -> Result<S::DataTypeDef> {
+    let name = required(e, "Name")?;
+    let array_info = child(e, "ArrayInfo").map(array_info_from).transpose()?;
+    let sub_items = children(e, "SubItem")
+        .map(sub_item_def_from)
+        .collect::<Result<_>>()?;
+    let enum_info = children(e, "EnumInfo")
+        .map(enum_value_from)
+        .collect::<Result<_>>()?;
+    Ok(S::DataTypeDef {
+        name: name.to_string(),
+        base_type: text(e, "BaseType").map(str::to_string),
+        bit_size: usize_from(required(e, "BitSize")?)?,
+        array_info,
+        sub_items,
+        enum_info,
+    })
+}
+
+fn array_info_from(e: &XmlElement) -> Result<S::ArrayInfo> {
+    Ok(S::ArrayInfo {
+        l_bound: u32_from_hex_dec_value(required(e, "LBound")?)?,
+        elements: u32_from_hex_dec_value(required(e, "Elements")?)?,
+    })
+}
+
+fn enum_value_from(e: &XmlElement) -> Result<S::EnumValue> {
+    Ok(S::EnumValue {
+        text: names(e, "Text")?,
+        value: required(e, "Enum")?
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::Other, e))?,
+    })
+}
+
+fn sub_item_def_from(e: &XmlElement) -> Result<S::SubItemDef> {
+    Ok(S::SubItemDef {
+        sub_idx: text(e, "SubIdx")
+            .map(|v| u8_from_hex_dec_value(v).map(ec::SubIdx::from))
+            .transpose()?,
+        name: required(e, "Name")?.to_string(),
+        r#type: required(e, "Type")?.to_string(),
+        bit_size: usize_from(required(e, "BitSize")?)?,
+        bit_offs: usize_from(required(e, "BitOffs")?)?,
+        flags: child(e, "Flags").map(flags_from),
+    })
+}
+
+fn object_from(e: &XmlElement) -> Result<S::Object> {
+    Ok(S::Object {
+        idx: ec::Idx::from(u16_from_hex_dec_value(required(e, "Index")?)?),
+        depend_on_slot: match child(e, "Index").and_then(|i| attribute(i, "DependOnSlot")) {
+            Some(v) => bool_from_str(v)?,
+            None => false,
+        },
+        name: names(e, "Name")?,
+        r#type: required(e, "Type")?.to_string(),
+        bit_size: usize_from(required(e, "BitSize")?)?,
+        info: child(e, "Info").map(info_from),
+        flags: child(e, "Flags").map(flags_from),
+    })
+}
+
+fn info_from(e: &XmlElement) -> S::ObjectInfo {
+    let hex = |name| text(e, name).map(|v| S::HexBinary(v.to_string()));
+    let string = |name| text(e, name).map(str::to_string);
+    S::ObjectInfo {
+        default_data: hex("DefaultData"),
+        min_data: hex("MinData"),
+        max_data: hex("MaxData"),
+        default_value: string("DefaultValue"),
+        min_value: string("MinValue"),
+        max_value: string("MaxValue"),
+        sub_items: children(e, "SubItem")
+            .map(|e| S::SubItemInfo {
+                name: text(e, "Name").unwrap_or_default().to_string(),
+                info: child(e, "Info").map(info_from).unwrap_or_default(),
+            })
+            .collect(),
+    }
+}
+
+fn flags_from(e: &XmlElement) -> S::ObjectFlags {
+    let string = |name| text(e, name).map(str::to_string);
+    S::ObjectFlags {
+        access: string("Access"),
+        category: string("Category"),
+        pdo_mapping: string("PdoMapping"),
+        sdo_access: string("SdoAccess"),
+        unknown: unknown_children(e, FLAGS_ELEMENTS),
+    }
+}
+
+fn names(e: &XmlElement, name: &str) -> Result<S::LocalizedText> {
+    names_from(children(e, name).map(Name::from).collect())
+}
+
+fn usize_from(v: &str) -> Result<usize> {
+    u32_from_hex_dec_value(v).map(|v| v as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn convert_profile() {
+        let s = r##"
+        <Profile>
+            <ProfileNo>5001</ProfileNo>
+            <ChannelInfo><ProfileNo>401</ProfileNo></ChannelInfo>
+            <Dictionary>
+                <DataTypes>
+                    <DataType>
+                        <Name>DT1018</Name>
+                        <BitSize>48</BitSize>
+                        <SubItem>
+                            <SubIdx>0</SubIdx>
+                            <Name>SubIndex 000</Name>
+                            <Type>USINT</Type>
+                            <BitSize>8</BitSize>
+                            <BitOffs>0</BitOffs>
+                            <Flags><Access>ro</Access></Flags>
+                        </SubItem>
+                        <SubItem>
+                            <SubIdx>1</SubIdx>
+                            <Name>Vendor ID</Name>
+                            <Type>UDINT</Type>
+                            <BitSize>32</BitSize>
+                            <BitOffs>16</BitOffs>
+                        </SubItem>
+                    </DataType>
+                    <DataType>
+                        <Name>DT0801EN02</Name>
+                        <BaseType>USINT</BaseType>
+                        <BitSize>2</BitSize>
+                        <EnumInfo><Text>1ms</Text><Enum>0</Enum></EnumInfo>
+                        <EnumInfo><Text>3ms</Text><Enum>1</Enum></EnumInfo>
+                    </DataType>
+                </DataTypes>
+                <Objects>
+                    <Object>
+                        <Index>#x1018</Index>
+                        <Name>Identity</Name>
+                        <Name LcId="1031">Identität</Name>
+                        <Type>DT1018</Type>
+                        <BitSize>48</BitSize>
+                        <Info>
+                            <SubItem>
+                                <Name>SubIndex 000</Name>
+                                <Info><DefaultData>01</DefaultData></Info>
+                            </SubItem>
+                            <SubItem><Name>Vendor ID</Name></SubItem>
+                        </Info>
+                        <Flags><Access>ro</Access><Category>m</Category></Flags>
+                    </Object>
+                </Objects>
+            </Dictionary>
+            <DiagMessages>
+                <DiagMessage>
+                    <TextId>#x0011</TextId>
+                    <MessageText>Sync Manager %d invalid address (%d)</MessageText>
+                </DiagMessage>
+            </DiagMessages>
+        </Profile>"##;
+        let profile = profile_from(&xml::parse(s).unwrap(), &mut Tolerance::strict()).unwrap();
+        assert_eq!(profile.profile_no, Some(5001));
+        assert_eq!(profile.dictionary_file, None);
+        assert_eq!(profile.diag_messages.len(), 1);
+        assert_eq!(profile.diag_messages[0].text_id, 0x11);
+        assert_eq!(
+            profile.diag_messages[0].text.default_text(),
+            Some("Sync Manager %d invalid address (%d)")
+        );
+        assert_eq!(profile.unknown.len(), 1);
+        assert_eq!(profile.unknown[0].name, "ChannelInfo");
+
+        let dict = profile.dictionary.unwrap();
+        assert_eq!(dict.data_types.len(), 2);
+        let record = &dict.data_types[0];
+        assert_eq!(record.bit_size, 48);
+        assert_eq!(record.sub_items[1].sub_idx, Some(ec::SubIdx::from(1)));
+        assert_eq!(record.sub_items[1].bit_offs, 16);
+        assert_eq!(
+            record.sub_items[0]
+                .flags
+                .as_ref()
+                .unwrap()
+                .access
+                .as_deref(),
+            Some("ro")
+        );
+        let enumeration = &dict.data_types[1];
+        assert_eq!(enumeration.base_type.as_deref(), Some("USINT"));
+        assert_eq!(enumeration.enum_info[1].value, 1);
+        assert_eq!(enumeration.enum_info[1].text.default_text(), Some("3ms"));
+
+        let object = &dict.objects[0];
+        assert_eq!(object.idx, ec::Idx::from(0x1018));
+        assert_eq!(object.name.get(1031), Some("Identität"));
+        let info = object.info.as_ref().unwrap();
+        assert_eq!(info.sub_items.len(), 2);
+        assert_eq!(
+            info.sub_items[0].info.default_data,
+            Some(S::HexBinary("01".to_string()))
+        );
+        assert_eq!(info.sub_items[1].info, S::ObjectInfo::default());
+        assert_eq!(
+            object.flags.as_ref().unwrap().category.as_deref(),
+            Some("m")
+        );
+    }
+
+    #[test]
+    fn keep_invalid_objects_in_lenient_mode() {
+        let s = r##"
+        <Profile>
+            <ProfileNo>foo</ProfileNo>
+            <Dictionary>
+                <DataTypes>
+                    <DataType><Name>DT1</Name></DataType>
+                    <DataType><Name>UINT</Name><BitSize>16</BitSize></DataType>
+                </DataTypes>
+                <Objects>
+                    <Object><Index>#x1000</Index><Name>A</Name><Type>UDINT</Type><BitSize>32</BitSize></Object>
+                    <Object><Name>No index</Name></Object>
+                    <Object><Index>#x1008</Index><Name>B</Name><Type>STRING(4)</Type><BitSize>32</BitSize></Object>
+                </Objects>
+            </Dictionary>
+        </Profile>"##;
+        let mut warnings = vec![];
+        let mut t = Tolerance::lenient("/Device", &mut warnings);
+        let profiles = profiles_from(&[xml::parse(s).unwrap()], &mut t).unwrap();
+        let profile = &profiles[0];
+        assert_eq!(profile.profile_no, None);
+        assert_eq!(profile.unknown[0].to_string(), "<ProfileNo>foo</ProfileNo>");
+
+        let dict = profile.dictionary.as_ref().unwrap();
+        assert_eq!(dict.data_types.len(), 1);
+        let objects: Vec<_> = dict.objects.iter().map(|o| u16::from(o.idx)).collect();
+        assert_eq!(objects, vec![0x1000, 0x1008]);
+        let unknown: Vec<_> = dict.unknown.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            unknown,
+            vec![
+                "<DataTypes><DataType><Name>DT1</Name></DataType></DataTypes>",
+                "<Objects><Object><Name>No index</Name></Object></Objects>"
+            ]
+        );
+
+        let paths: Vec<_> = warnings.iter().map(|w| w.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/Device/Profile[1]/ProfileNo",
+                "/Device/Profile[1]/Dictionary/DataTypes/DataType[1]",
+                "/Device/Profile[1]/Dictionary/Objects/Object[2]",
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_objects_without_index() {
+        let s = "<Dictionary><Objects><Object><Name>Foo</Name></Object></Objects></Dictionary>";
+        let err = dictionary_from(&xml::parse(s).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("`Index`"));
+    }
+}
@@ -0,0 +1,102 @@
+//! Conversion of the `Fmmu` elements of devices.
+
+use super::{conversions::*, lenient::Tolerance, tree::*, *};
+use crate::structs as S;
+use ethercat_types as ec;
+
+/// Convert the `Fmmu` elements of a device.
+///
+/// Invalid ones that are tolerated are replaced by unused FMMUs,
+/// so that the indices of the following ones do not change.
+pub(super) fn fmmus_from(elements: &[XmlElement], t: &mut Tolerance) -> Result<Vec<S::FmmuUsage>> {
+    let mut fmmus = vec![];
+    for (i, e) in elements.iter().enumerate() {
+        let path = format!("Fmmu[{}]", i + 1);
+        let unused = S::FmmuUsage {
+            r#type: S::FmmuType::Unused,
+            sm: None,
+            op_only: false,
+        };
+        fmmus.push(t.convert_or(e, &path, fmmu_from, unused)?);
+    }
+    Ok(fmmus)
+}
+
+fn fmmu_from(e: &XmlElement) -> Result<S::FmmuUsage> {
+    let r#type = match e.text().map(str::trim).unwrap_or_default() {
+        "Outputs" => S::FmmuType::Outputs,
+        "Inputs" => S::FmmuType::Inputs,
+        "MBoxState" => S::FmmuType::MBoxState,
+        "" => S::FmmuType::Unused,
+        other => S::FmmuType::Other(other.to_string()),
+    };
+    Ok(S::FmmuUsage {
+        r#type,
+        sm: attribute(e, "Sm")
+            .map(|v| u8_from_hex_dec_value(v).map(ec::SmIdx::from))
+            .transpose()?,
+        op_only: attribute(e, "OpOnly")
+            .map(bool_from_str)
+            .transpose()?
+            .unwrap_or(false),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn convert_fmmus() {
+        let elements: Vec<_> = [
+            "<Fmmu>Outputs</Fmmu>",
+            r#"<Fmmu Sm="3" OpOnly="1"> Inputs </Fmmu>"#,
+            "<Fmmu>MBoxState</Fmmu>",
+            "<Fmmu/>",
+            "<Fmmu>DynamicInputs</Fmmu>",
+        ]
+        .iter()
+        .map(|s| xml::parse(s).unwrap())
+        .collect();
+        let fmmus = fmmus_from(&elements, &mut Tolerance::strict()).unwrap();
+        assert_eq!(fmmus[0].r#type, S::FmmuType::Outputs);
+        assert_eq!(fmmus[0].sm, None);
+        assert!(!fmmus[0].op_only);
+        assert_eq!(fmmus[1].r#type, S::FmmuType::Inputs);
+        assert_eq!(fmmus[1].sm, Some(ec::SmIdx::from(3)));
+        assert!(fmmus[1].op_only);
+        assert_eq!(fmmus[2].r#type, S::FmmuType::MBoxState);
+        assert_eq!(fmmus[3].r#type, S::FmmuType::Unused);
+        assert_eq!(
+            fmmus[4].r#type,
+            S::FmmuType::Other("DynamicInputs".to_string())
+        );
+
+        let invalid = xml::parse(r#"<Fmmu Sm="x">Inputs</Fmmu>"#).unwrap();
+        assert!(fmmu_from(&invalid).is_err());
+    }
+
+    #[test]
+    fn keep_invalid_fmmus_in_lenient_mode() {
+        let elements: Vec<_> = [r#"<Fmmu Sm="x">Inputs</Fmmu>"#, "<Fmmu>Outputs</Fmmu>"]
+            .iter()
+            .map(|s| xml::parse(s).unwrap())
+            .collect();
+        let mut warnings = vec![];
+        let fmmus =
+            fmmus_from(&elements, &mut Tolerance::lenient("/Device", &mut warnings)).unwrap();
+        assert_eq!(fmmus.len(), 2);
+        assert_eq!(fmmus[0].r#type, S::FmmuType::Unused);
+        assert_eq!(fmmus[0].sm, None);
+        assert_eq!(fmmus[1].r#type, S::FmmuType::Outputs);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, "/Device/Fmmu[1]");
+        assert!(warnings[0]
+            .message
+            .starts_with("Replaced by a placeholder: "));
+
+        let strict = fmmus_from(&elements, &mut Tolerance::strict());
+        assert!(strict.is_err());
+    }
+}
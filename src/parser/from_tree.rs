@@ -0,0 +1,389 @@
+//! Construction of the raw data from the element tree.
+//!
+//! Nothing is converted here, so any document can be read.
+//! Values are checked by the conversions (see [`super::lenient`]).
+
+use super::{tree::*, *};
+
+/// Child elements of `EtherCATInfo` that are interpreted by the conversions.
+const INFO_ELEMENTS: &[&str] = &["InfoReference", "Vendor", "Descriptions", "Modules"];
+
+/// Child elements of `Vendor` that are interpreted by the conversions.
+const VENDOR_ELEMENTS: &[&str] = &[
+    "Id",
+    "Name",
+    "Comment",
+    "URL",
+    "DescriptionURL",
+    "Image16x14",
+    "ImageFile16x14",
+    "ImageData16x14",
+];
+
+/// Child elements of `Group` that are interpreted by the conversions.
+const GROUP_ELEMENTS: &[&str] = &[
+    "Type",
+    "Name",
+    "Comment",
+    "Image16x14",
+    "ImageFile16x14",
+    "ImageData16x14",
+];
+
+/// Child elements of `Device` that are interpreted by the conversions.
+const DEVICE_ELEMENTS: &[&str] = &[
+    "Type",
+    "Name",
+    "GroupType",
+    "Info",
+    "Fmmu",
+    "Sm",
+    "RxPdo",
+    "TxPdo",
+    "Mailbox",
+    "Profile",
+    "Image16x14",
+    "ImageFile16x14",
+    "ImageData16x14",
+];
+
+/// Child elements of `Module` that are interpreted by the conversions.
+const MODULE_ELEMENTS: &[&str] = &["Type", "Name", "RxPdo", "TxPdo", "Mailbox", "Profile"];
+
+/// Attributes of `Sm` that are interpreted by the conversions.
+const SM_ATTRIBUTES: &[&str] = &[
+    "Enable",
+    "StartAddress",
+    "ControlByte",
+    "DefaultSize",
+    "Virtual",
+];
+
+/// Attributes of `RxPdo` and `TxPdo` that are interpreted by the conversions.
+const PDO_ATTRIBUTES: &[&str] = &["Sm", "Fixed", "Mandatory"];
+
+/// Child elements of `RxPdo` and `TxPdo` that are interpreted by the conversions.
+const PDO_ELEMENTS: &[&str] = &["Index", "Name", "Entry"];
+
+/// Child elements of `Entry` that are interpreted by the conversions.
+const ENTRY_ELEMENTS: &[&str] = &["Index", "SubIndex", "BitLen", "Name", "DataType"];
+
+/// The text of the first child element with the given name, as it is.
+fn string(e: &XmlElement, name: &str) -> Option<String> {
+    child(e, name).map(|c| c.text().unwrap_or_default().to_string())
+}
+
+/// The trimmed text of the first child element with the given name.
+fn value(e: &XmlElement, name: &str) -> Option<String> {
+    text(e, name).map(str::to_string)
+}
+
+/// The child elements that are not interpreted, including further
+/// occurrences of the known elements that are interpreted once (all but `Name`).
+fn uninterpreted_children(e: &XmlElement, known: &[&str]) -> Vec<XmlElement> {
+    let mut seen = vec![];
+    e.children()
+        .filter(|c| {
+            let name = c.name.as_str();
+            if !known.contains(&name) {
+                return true;
+            }
+            if name == "Name" {
+                return false;
+            }
+            let repeated = seen.contains(&name);
+            seen.push(name);
+            repeated
+        })
+        .cloned()
+        .collect()
+}
+
+fn attr(e: &XmlElement, name: &str) -> Option<String> {
+    attribute(e, name).map(str::to_string)
+}
+
+fn names(e: &XmlElement) -> Vec<Name> {
+    children(e, "Name").map(Name::from).collect()
+}
+
+fn pdos(e: &XmlElement, name: &str) -> Vec<Pdo> {
+    children(e, name).map(Pdo::from).collect()
+}
+
+impl From<&XmlElement> for EtherCATInfo {
+    /// `e` is the root element of an `EtherCATInfo` or `EtherCATModule` document.
+    fn from(e: &XmlElement) -> Self {
+        EtherCATInfo {
+            Version: attr(e, "Version"),
+            InfoReference: string(e, "InfoReference"),
+            Vendor: child(e, "Vendor").map(Vendor::from),
+            Descriptions: child(e, "Descriptions").map(Descriptions::from),
+            Modules: child(e, "Modules").map(Modules::from),
+            attributes: unknown_attributes(e, &["Version"]),
+            namespaces: e.namespaces.clone(),
+            unknown: unknown_children(e, INFO_ELEMENTS),
+        }
+    }
+}
+
+impl From<&XmlElement> for Vendor {
+    fn from(e: &XmlElement) -> Self {
+        Vendor {
+            FileVersion: attr(e, "FileVersion"),
+            Id: value(e, "Id"),
+            Name: names(e),
+            Comment: string(e, "Comment"),
+            URL: string(e, "URL"),
+            DescriptionURL: string(e, "DescriptionURL"),
+            Image16x14: string(e, "Image16x14"),
+            ImageFile16x14: string(e, "ImageFile16x14"),
+            ImageData16x14: string(e, "ImageData16x14"),
+            unknown: uninterpreted_children(e, VENDOR_ELEMENTS),
+        }
+    }
+}
+
+impl From<&XmlElement> for Descriptions {
+    fn from(e: &XmlElement) -> Self {
+        Descriptions {
+            Groups: child(e, "Groups").map(|e| Groups {
+                items: children(e, "Group").map(Group::from).collect(),
+            }),
+            Devices: Devices {
+                items: children(e, "Devices")
+                    .flat_map(|e| children(e, "Device"))
+                    .map(Device::from)
+                    .collect(),
+            },
+            Modules: child(e, "Modules").map(Modules::from),
+        }
+    }
+}
+
+impl From<&XmlElement> for Modules {
+    fn from(e: &XmlElement) -> Self {
+        Modules {
+            items: children(e, "Module").map(Module::from).collect(),
+        }
+    }
+}
+
+impl From<&XmlElement> for Group {
+    fn from(e: &XmlElement) -> Self {
+        let mut items = vec![];
+        if let Some(t) = string(e, "Type") {
+            items.push(GroupProperty::Type(t));
+        }
+        let names = names(e);
+        if !names.is_empty() {
+            items.push(GroupProperty::Name(names));
+        }
+        if let Some(c) = string(e, "Comment") {
+            items.push(GroupProperty::Comment(c));
+        }
+        for c in e.children() {
+            let img = c.text().unwrap_or_default().to_string();
+            match c.name.as_str() {
+                "Image16x14" => items.push(GroupProperty::Image16x14(img)),
+                "ImageFile16x14" => items.push(GroupProperty::ImageFile16x14(img)),
+                "ImageData16x14" => items.push(GroupProperty::ImageData16x14(img)),
+                _ => {}
+            }
+        }
+        Group {
+            SortOrder: attr(e, "SortOrder"),
+            ParentGroup: attr(e, "ParentGroup"),
+            items,
+            unknown: uninterpreted_children(e, GROUP_ELEMENTS),
+        }
+    }
+}
+
+impl From<&XmlElement> for Device {
+    fn from(e: &XmlElement) -> Self {
+        let mut items = vec![];
+        if let Some(t) = child(e, "Type") {
+            items.push(DeviceProperty::Type(DeviceType {
+                ProductCode: attr(t, "ProductCode"),
+                RevisionNo: attr(t, "RevisionNo"),
+                Description: t.text().unwrap_or_default().to_string(),
+                attributes: unknown_attributes(t, &["ProductCode", "RevisionNo"]),
+            }));
+        }
+        let names = names(e);
+        if !names.is_empty() {
+            items.push(DeviceProperty::Name(names));
+        }
+        if let Some(t) = string(e, "GroupType") {
+            items.push(DeviceProperty::GroupType(t));
+        }
+        let sm: Vec<_> = children(e, "Sm").map(Sm::from).collect();
+        if !sm.is_empty() {
+            items.push(DeviceProperty::Sm(sm));
+        }
+        let rx_pdo = pdos(e, "RxPdo");
+        if !rx_pdo.is_empty() {
+            items.push(DeviceProperty::RxPdo(rx_pdo));
+        }
+        let tx_pdo = pdos(e, "TxPdo");
+        if !tx_pdo.is_empty() {
+            items.push(DeviceProperty::TxPdo(tx_pdo));
+        }
+        for c in e.children() {
+            let img = c.text().unwrap_or_default().to_string();
+            match c.name.as_str() {
+                "Image16x14" => items.push(DeviceProperty::Image16x14(img)),
+                "ImageFile16x14" => items.push(DeviceProperty::ImageFile16x14(img)),
+                "ImageData16x14" => items.push(DeviceProperty::ImageData16x14(img)),
+                _ => {}
+            }
+        }
+        Device {
+            Physics: attr(e, "Physics"),
+            items,
+            info: child(e, "Info").cloned(),
+            fmmu: children(e, "Fmmu").cloned().collect(),
+            mailbox: child(e, "Mailbox").cloned(),
+            profiles: children(e, "Profile").cloned().collect(),
+            attributes: unknown_attributes(e, &["Physics"]),
+            unknown: unknown_children(e, DEVICE_ELEMENTS),
+        }
+    }
+}
+
+impl From<&XmlElement> for Name {
+    fn from(e: &XmlElement) -> Self {
+        Name {
+            LcId: attr(e, "LcId"),
+            value: e.text().map(str::to_string),
+        }
+    }
+}
+
+impl From<&XmlElement> for Sm {
+    fn from(e: &XmlElement) -> Self {
+        Sm {
+            Enable: attr(e, "Enable"),
+            StartAddress: attr(e, "StartAddress"),
+            ControlByte: attr(e, "ControlByte"),
+            DefaultSize: attr(e, "DefaultSize"),
+            Virtual: attr(e, "Virtual"),
+            text: e.text().map(str::to_string),
+            attributes: unknown_attributes(e, SM_ATTRIBUTES),
+        }
+    }
+}
+
+impl From<&XmlElement> for Pdo {
+    fn from(e: &XmlElement) -> Self {
+        Pdo {
+            Sm: attr(e, "Sm"),
+            Fixed: attr(e, "Fixed"),
+            Mandatory: attr(e, "Mandatory"),
+            Index: value(e, "Index"),
+            DependOnSlot: child(e, "Index").and_then(|i| attr(i, "DependOnSlot")),
+            Name: names(e),
+            Entry: children(e, "Entry").map(Entry::from).collect(),
+            attributes: unknown_attributes(e, PDO_ATTRIBUTES),
+            unknown: unknown_children(e, PDO_ELEMENTS),
+        }
+    }
+}
+
+impl From<&XmlElement> for Entry {
+    fn from(e: &XmlElement) -> Self {
+        Entry {
+            Index: value(e, "Index"),
+            DependOnSlot: child(e, "Index").and_then(|i| attr(i, "DependOnSlot")),
+            SubIndex: value(e, "SubIndex"),
+            BitLen: value(e, "BitLen"),
+            Name: names(e),
+            DataType: value(e, "DataType"),
+            unknown: unknown_children(e, ENTRY_ELEMENTS),
+        }
+    }
+}
+
+impl From<&XmlElement> for Module {
+    fn from(e: &XmlElement) -> Self {
+        Module {
+            Type: string(e, "Type"),
+            Name: names(e),
+            TxPdo: pdos(e, "TxPdo"),
+            RxPdo: pdos(e, "RxPdo"),
+            mailbox: child(e, "Mailbox").cloned(),
+            profiles: children(e, "Profile").cloned().collect(),
+            attributes: e.attributes.clone(),
+            type_attributes: child(e, "Type")
+                .map(|t| t.attributes.clone())
+                .unwrap_or_default(),
+            unknown: unknown_children(e, MODULE_ELEMENTS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn keep_unknown_elements() {
+        let s = r##"
+        <EtherCATInfo>
+            <Vendor>
+                <Id>2</Id>
+                <VendorSpecific><Foo/></VendorSpecific>
+            </Vendor>
+            <Descriptions>
+                <Groups>
+                    <Group>
+                        <Type>Coupler</Type>
+                        <Name>Coupler</Name>
+                        <VendorSpecific><TwinCAT/></VendorSpecific>
+                    </Group>
+                </Groups>
+                <Devices>
+                    <Device>
+                        <Type>Foo</Type>
+                        <Name>Bar</Name>
+                        <Comment>Baz</Comment>
+                        <GroupType>Coupler</GroupType>
+                    </Device>
+                    <Device>
+                        <Type>Foo</Type>
+                        <Name>Bar</Name>
+                        <VendorSpecific>
+                            <TwinCAT><AlternativeSmMapping Default="1"/></TwinCAT>
+                        </VendorSpecific>
+                    </Device>
+                </Devices>
+            </Descriptions>
+            <VendorSpecific/>
+        </EtherCATInfo>"##;
+        let info = EtherCATInfo::from(&xml::parse(s).unwrap());
+
+        assert_eq!(info.unknown, vec![XmlElement::new("VendorSpecific")]);
+        assert_eq!(
+            info.Vendor.unwrap().unknown[0].to_string(),
+            "<VendorSpecific><Foo/></VendorSpecific>"
+        );
+
+        let descriptions = info.Descriptions.unwrap();
+        let group = &descriptions.Groups.unwrap().items[0];
+        assert_eq!(group.unknown.len(), 1);
+        assert_eq!(
+            group.unknown[0].to_string(),
+            "<VendorSpecific><TwinCAT/></VendorSpecific>"
+        );
+
+        let devices = descriptions.Devices.items;
+        assert_eq!(devices[0].unknown.len(), 1);
+        assert_eq!(devices[0].unknown[0].name, "Comment");
+        assert_eq!(
+            devices[1].unknown[0].to_string(),
+            r#"<VendorSpecific><TwinCAT><AlternativeSmMapping Default="1"/></TwinCAT></VendorSpecific>"#
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Conversion of the `Info` element of devices.
+
+use super::{conversions::*, lenient::Tolerance, tree::*, *};
+use crate::structs as S;
+use std::time::Duration;
+
+/// Child elements of `Info` that are interpreted.
+const INFO_ELEMENTS: &[&str] = &[
+    "StateMachine",
+    "Mailbox",
+    "IdentificationAdo",
+    "Electrical",
+    "Port",
+];
+
+/// Child elements of `Electrical` that are interpreted.
+const ELECTRICAL_ELEMENTS: &[&str] = &["EBusCurrent"];
+
+/// Child elements of `Port` that are interpreted.
+const PORT_ELEMENTS: &[&str] = &["Type", "Connection", "Label"];
+
+pub(super) fn device_info_from(e: &XmlElement, t: &mut Tolerance) -> Result<S::DeviceInfo> {
+    let mut unknown = unknown_children(e, INFO_ELEMENTS);
+    let mut state_machine_timeouts = None;
+    if let Some(sm) = child(e, "StateMachine") {
+        let converted = t.convert(
+            sm,
+            "StateMachine",
+            |e| {
+                child(e, "Timeout")
+                    .map(state_machine_timeouts_from)
+                    .transpose()
+            },
+            &mut unknown,
+        )?;
+        if converted.is_some() {
+            unknown.extend(without_timeout(sm));
+        }
+        state_machine_timeouts = converted.flatten();
+    }
+    let mut mailbox_timeouts = None;
+    if let Some(mailbox) = child(e, "Mailbox") {
+        let converted = t.convert(
+            mailbox,
+            "Mailbox",
+            |e| child(e, "Timeout").map(mailbox_timeouts_from).transpose(),
+            &mut unknown,
+        )?;
+        if converted.is_some() {
+            unknown.extend(without_timeout(mailbox));
+        }
+        mailbox_timeouts = converted.flatten();
+    }
+    let mut identification_ado = None;
+    if let Some(ado) = child(e, "IdentificationAdo") {
+        identification_ado = t.convert(
+            ado,
+            "IdentificationAdo",
+            |e| u16_from_hex_dec_value(e.text().unwrap_or_default().trim()),
+            &mut unknown,
+        )?;
+    }
+    let mut electrical = None;
+    if let Some(el) = child(e, "Electrical") {
+        electrical = t.convert(el, "Electrical", electrical_from, &mut unknown)?;
+    }
+    let mut ports = vec![];
+    for (i, port) in children(e, "Port").enumerate() {
+        let path = format!("Port[{}]", i + 1);
+        ports.extend(t.convert(port, &path, port_from, &mut unknown)?);
+    }
+    Ok(S::DeviceInfo {
+        state_machine_timeouts,
+        mailbox_timeouts,
+        identification_ado,
+        electrical,
+        ports,
+        unknown,
+    })
+}
+
+/// The rest of a `StateMachine` or `Mailbox` element without its
+/// interpreted `Timeout` (e.g. `Behavior`), if anything is left.
+fn without_timeout(e: &XmlElement) -> Option<XmlElement> {
+    let mut rest = e.clone();
+    let timeout = rest
+        .nodes
+        .iter()
+        .position(|n| matches!(n, XmlNode::Element(c) if c.name == "Timeout"));
+    if let Some(i) = timeout {
+        rest.nodes.remove(i);
+        if rest.nodes.is_empty() && rest.attributes.is_empty() {
+            return None;
+        }
+    }
+    Some(rest)
+}
+
+fn state_machine_timeouts_from(e: &XmlElement) -> Result<S::StateMachineTimeouts> {
+    Ok(S::StateMachineTimeouts {
+        preop: millis(e, "PreopTimeout")?,
+        safeop_op: millis(e, "SafeopOpTimeout")?,
+        back_to_init: millis(e, "BackToInitTimeout")?,
+        back_to_safeop: millis(e, "BackToSafeopTimeout")?,
+    })
+}
+
+fn mailbox_timeouts_from(e: &XmlElement) -> Result<S::MailboxTimeouts> {
+    Ok(S::MailboxTimeouts {
+        request: millis(e, "RequestTimeout")?,
+        response: millis(e, "ResponseTimeout")?,
+    })
+}
+
+fn electrical_from(e: &XmlElement) -> Result<S::Electrical> {
+    Ok(S::Electrical {
+        ebus_current: i32_from_hex_dec_value(required(e, "EBusCurrent")?)?,
+        unknown: unknown_children(e, ELECTRICAL_ELEMENTS),
+    })
+}
+
+fn port_from(e: &XmlElement) -> Result<S::Port> {
+    let r#type = match required(e, "Type")? {
+        "MII" => S::PortType::Mii,
+        "EBUS" => S::PortType::Ebus,
+        "NONE" => S::PortType::Unused,
+        other => S::PortType::Other(other.to_string()),
+    };
+    Ok(S::Port {
+        r#type,
+        connection: text(e, "Connection").map(str::to_string),
+        label: child(e, "Label").map(|l| l.text().unwrap_or_default().trim().to_string()),
+        unknown: unknown_children(e, PORT_ELEMENTS),
+    })
+}
+
+/// The port types of the `Physics` attribute, one character per port.
+pub(super) fn physics_from(physics: &str) -> Vec<S::PortType> {
+    physics
+        .chars()
+        .map(|c| match c {
+            'Y' => S::PortType::Mii,
+            'K' => S::PortType::Ebus,
+            ' ' => S::PortType::Unused,
+            other => S::PortType::Other(other.to_string()),
+        })
+        .collect()
+}
+
+/// A mandatory timeout given in milliseconds.
+fn millis(e: &XmlElement, name: &str) -> Result<Duration> {
+    let ms = u32_from_hex_dec_value(required(e, name)?)?;
+    Ok(Duration::from_millis(u64::from(ms)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn convert_device_info() {
+        let s = r##"
+        <Info>
+            <StateMachine>
+                <Timeout>
+                    <PreopTimeout>2000</PreopTimeout>
+                    <SafeopOpTimeout>9000</SafeopOpTimeout>
+                    <BackToInitTimeout>5000</BackToInitTimeout>
+                    <BackToSafeopTimeout>200</BackToSafeopTimeout>
+                </Timeout>
+                <Behavior StartToSafeopNoSync="true"/>
+            </StateMachine>
+            <Mailbox>
+                <Timeout>
+                    <RequestTimeout>100</RequestTimeout>
+                    <ResponseTimeout>#x7D0</ResponseTimeout>
+                </Timeout>
+            </Mailbox>
+            <IdentificationAdo>#x1000</IdentificationAdo>
+            <Electrical><EBusCurrent>#x1F4</EBusCurrent></Electrical>
+            <Port><Type>MII</Type><Label>X1 IN</Label></Port>
+            <Port><Type>NONE</Type><Label/></Port>
+            <EtherCATController><DpramSize>8192</DpramSize></EtherCATController>
+        </Info>"##;
+        let info = device_info_from(&xml::parse(s).unwrap(), &mut Tolerance::strict()).unwrap();
+        let sm = info.state_machine_timeouts.unwrap();
+        assert_eq!(sm.preop, Duration::from_secs(2));
+        assert_eq!(sm.safeop_op, Duration::from_secs(9));
+        assert_eq!(sm.back_to_init, Duration::from_secs(5));
+        assert_eq!(sm.back_to_safeop, Duration::from_millis(200));
+        let mailbox = info.mailbox_timeouts.unwrap();
+        assert_eq!(mailbox.request, Duration::from_millis(100));
+        assert_eq!(mailbox.response, Duration::from_millis(2000));
+        assert_eq!(info.identification_ado, Some(0x1000));
+        assert_eq!(info.electrical.unwrap().ebus_current, 500);
+        assert_eq!(info.ports.len(), 2);
+        assert_eq!(info.ports[0].r#type, S::PortType::Mii);
+        assert_eq!(info.ports[0].label.as_deref(), Some("X1 IN"));
+        assert_eq!(info.ports[1].r#type, S::PortType::Unused);
+        assert_eq!(info.ports[1].label.as_deref(), Some(""));
+        assert_eq!(info.unknown.len(), 2);
+        assert_eq!(info.unknown[0].name, "EtherCATController");
+        assert_eq!(
+            info.unknown[1].to_string(),
+            r#"<StateMachine><Behavior StartToSafeopNoSync="true"/></StateMachine>"#
+        );
+    }
+
+    #[test]
+    fn keep_state_machine_without_timeout() {
+        let s = r#"<Info><StateMachine><Behavior StartToSafeopNoSync="true"/></StateMachine><Mailbox/></Info>"#;
+        let info = device_info_from(&xml::parse(s).unwrap(), &mut Tolerance::strict()).unwrap();
+        assert!(info.state_machine_timeouts.is_none());
+        assert!(info.mailbox_timeouts.is_none());
+        let unknown: Vec<_> = info.unknown.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            unknown,
+            vec![
+                r#"<StateMachine><Behavior StartToSafeopNoSync="true"/></StateMachine>"#,
+                "<Mailbox/>"
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_incomplete_timeouts() {
+        let s = "<Info><Mailbox><Timeout><RequestTimeout>100</RequestTimeout></Timeout></Mailbox></Info>";
+        let err = device_info_from(&xml::parse(s).unwrap(), &mut Tolerance::strict()).unwrap_err();
+        assert!(err.to_string().contains("`ResponseTimeout`"));
+    }
+
+    #[test]
+    fn keep_invalid_elements_in_lenient_mode() {
+        let s = r##"
+        <Info>
+            <StateMachine>
+                <Timeout><PreopTimeout>2000</PreopTimeout></Timeout>
+            </StateMachine>
+            <Mailbox>
+                <Timeout>
+                    <RequestTimeout>100</RequestTimeout>
+                    <ResponseTimeout>2000</ResponseTimeout>
+                </Timeout>
+            </Mailbox>
+            <IdentificationAdo>foo</IdentificationAdo>
+            <Electrical><EBusCurrent>a lot</EBusCurrent></Electrical>
+            <Port><Label>X1</Label></Port>
+            <Port><Type>MII</Type><Label>X2</Label></Port>
+        </Info>"##;
+        let mut warnings = vec![];
+        let mut t = Tolerance::lenient("/Device", &mut warnings);
+        let info = device_info_from(&xml::parse(s).unwrap(), &mut t.nested("Info")).unwrap();
+        assert!(info.state_machine_timeouts.is_none());
+        assert!(info.mailbox_timeouts.is_some());
+        assert!(info.identification_ado.is_none());
+        let unknown: Vec<_> = info.unknown.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(
+            unknown,
+            vec!["StateMachine", "IdentificationAdo", "Electrical", "Port"]
+        );
+        assert_eq!(info.ports.len(), 1);
+        assert_eq!(info.ports[0].label.as_deref(), Some("X2"));
+
+        assert_eq!(warnings.len(), 4);
+        assert_eq!(warnings[0].path, "/Device/Info/StateMachine");
+        assert!(warnings[0]
+            .message
+            .starts_with("Kept as unknown element: Mandatory element `SafeopOpTimeout`"));
+        assert_eq!(warnings[1].path, "/Device/Info/IdentificationAdo");
+        assert_eq!(warnings[2].path, "/Device/Info/Electrical");
+        assert_eq!(warnings[3].path, "/Device/Info/Port[1]");
+    }
+
+    #[test]
+    fn convert_physics() {
+        use S::PortType::*;
+        assert_eq!(physics_from("KK Y"), vec![Ebus, Ebus, Unused, Mii]);
+        assert_eq!(physics_from("YF"), vec![Mii, Other("F".to_string())]);
+        assert!(physics_from("").is_empty());
+    }
+
+    #[test]
+    fn empty_info() {
+        let info =
+            device_info_from(&xml::parse("<Info/>").unwrap(), &mut Tolerance::strict()).unwrap();
+        assert!(info.state_machine_timeouts.is_none());
+        assert!(info.mailbox_timeouts.is_none());
+        assert!(info.identification_ado.is_none());
+    }
+}
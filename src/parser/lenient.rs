@@ -0,0 +1,686 @@
+use super::{conversions::*, *};
+use crate::{structs as S, Warning};
+use std::{
+    convert::{TryFrom, TryInto},
+    mem,
+};
+
+impl EtherCATInfo {
+    /// Convert the raw data, skipping or defaulting invalid values.
+    ///
+    /// Everything that was tolerated is reported as a [`Warning`].
+    /// `root` is the name of the root element, used for the paths of the warnings.
+    pub fn try_into_lenient(
+        mut self,
+        root: &str,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<S::EtherCatInfo> {
+        let root = format!("/{}", root);
+        if let Some(v) = &mut self.Vendor {
+            v.sanitize(&format!("{}/Vendor", root), warnings);
+        }
+
+        let mut groups: Vec<S::Group> = vec![];
+        let mut devices = vec![];
+        let mut modules = vec![];
+
+        if let Some(d) = &mut self.Descriptions {
+            let path = format!("{}/Descriptions", root);
+            if let Some(Groups { items }) = &mut d.Groups {
+                groups = convert_each(
+                    mem::take(items),
+                    &format!("{}/Groups/Group", path),
+                    Group::sanitize,
+                    |g, _| g.try_into(),
+                    warnings,
+                );
+            }
+            devices = convert_each(
+                mem::take(&mut d.Devices.items),
+                &format!("{}/Devices/Device", path),
+                Device::sanitize,
+                Device::convert,
+                warnings,
+            );
+            if let Some(Modules { items }) = &mut d.Modules {
+                modules = convert_each(
+                    mem::take(items),
+                    &format!("{}/Modules/Module", path),
+                    Module::sanitize,
+                    Module::convert,
+                    warnings,
+                );
+            }
+        }
+        if let Some(Modules { items }) = &mut self.Modules {
+            modules.extend(convert_each(
+                mem::take(items),
+                &format!("{}/Modules/Module", root),
+                Module::sanitize,
+                Module::convert,
+                warnings,
+            ));
+        }
+
+        let mut info = S::EtherCatInfo::try_from(self)?;
+        info.description.groups = groups;
+        info.description.devices = devices;
+        info.description.modules = modules;
+        Ok(info)
+    }
+}
+
+/// Convert each item on its own and skip the ones that are still invalid
+/// after sanitizing them.
+fn convert_each<T, U>(
+    items: Vec<T>,
+    path: &str,
+    sanitize: fn(&mut T, &str, &mut Vec<Warning>),
+    convert: fn(T, &mut Tolerance) -> Result<U>,
+    warnings: &mut Vec<Warning>,
+) -> Vec<U> {
+    items
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, mut item)| {
+            let path = format!("{}[{}]", path, i + 1);
+            sanitize(&mut item, &path, warnings);
+            let mut tolerance = Tolerance::lenient(&path, warnings);
+            match convert(item, &mut tolerance) {
+                Ok(item) => Some(item),
+                Err(err) => {
+                    warnings.push(Warning::new(path, format!("Skipped: {}", err)));
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Handling of interpreted elements that can't be converted.
+///
+/// In lenient mode such an element is reported as a [`Warning`]
+/// and kept as unknown element, otherwise the conversion fails.
+pub(super) struct Tolerance<'a> {
+    path: String,
+    warnings: Option<&'a mut Vec<Warning>>,
+}
+
+impl<'a> Tolerance<'a> {
+    pub(super) fn strict() -> Self {
+        Tolerance {
+            path: String::new(),
+            warnings: None,
+        }
+    }
+
+    pub(super) fn lenient(path: &str, warnings: &'a mut Vec<Warning>) -> Self {
+        Tolerance {
+            path: path.to_string(),
+            warnings: Some(warnings),
+        }
+    }
+
+    /// The tolerance for the content of the child element at `path`
+    /// (e.g. `Info`).
+    pub(super) fn nested(&mut self, path: &str) -> Tolerance<'_> {
+        Tolerance {
+            path: format!("{}/{}", self.path, path),
+            warnings: self.warnings.as_deref_mut(),
+        }
+    }
+
+    /// Convert the element at `path`, in lenient mode a failure keeps
+    /// the element in `unknown` and returns `None`.
+    pub(super) fn convert<T>(
+        &mut self,
+        e: &XmlElement,
+        path: &str,
+        convert: impl FnOnce(&XmlElement) -> Result<T>,
+        unknown: &mut Vec<XmlElement>,
+    ) -> Result<Option<T>> {
+        match (convert(e), &mut self.warnings) {
+            (Ok(value), _) => Ok(Some(value)),
+            (Err(err), Some(warnings)) => {
+                warnings.push(Warning::new(
+                    format!("{}/{}", self.path, path),
+                    format!("Kept as unknown element: {}", err),
+                ));
+                unknown.push(e.clone());
+                Ok(None)
+            }
+            (Err(err), None) => Err(err),
+        }
+    }
+
+    /// Convert the element at `path`, in lenient mode a failure is
+    /// replaced by `placeholder` (e.g. to keep the indices of the
+    /// following elements).
+    pub(super) fn convert_or<T>(
+        &mut self,
+        e: &XmlElement,
+        path: &str,
+        convert: impl FnOnce(&XmlElement) -> Result<T>,
+        placeholder: T,
+    ) -> Result<T> {
+        match (convert(e), &mut self.warnings) {
+            (Ok(value), _) => Ok(value),
+            (Err(err), Some(warnings)) => {
+                warnings.push(Warning::new(
+                    format!("{}/{}", self.path, path),
+                    format!("Replaced by a placeholder: {}", err),
+                ));
+                Ok(placeholder)
+            }
+            (Err(err), None) => Err(err),
+        }
+    }
+}
+
+impl Vendor {
+    fn sanitize(&mut self, path: &str, warnings: &mut Vec<Warning>) {
+        if let Some(id) = &self.Id {
+            if let Err(err) = u32_from_hex_dec_value(id) {
+                warnings.push(Warning::new(
+                    path,
+                    format!("Invalid vendor ID `{}` ({}) defaulted to 0", id, err),
+                ));
+                self.Id = Some("0".to_string());
+            }
+        }
+        sanitize_value(
+            &mut self.FileVersion,
+            u32_from_hex_dec_value,
+            "file version",
+            path,
+            warnings,
+        );
+        sanitize_names(&mut self.Name, path, warnings);
+        let mut found = false;
+        for img in [
+            &mut self.Image16x14,
+            &mut self.ImageFile16x14,
+            &mut self.ImageData16x14,
+        ] {
+            if img.is_some() {
+                if found {
+                    warnings.push(Warning::new(path, "Ignored additional image"));
+                    *img = None;
+                }
+                found = true;
+            }
+        }
+    }
+}
+
+impl Group {
+    fn sanitize(&mut self, path: &str, warnings: &mut Vec<Warning>) {
+        sanitize_value(
+            &mut self.SortOrder,
+            |v| {
+                v.trim()
+                    .parse::<i32>()
+                    .map_err(|e| Error::new(ErrorKind::Other, e))
+            },
+            "sort order",
+            path,
+            warnings,
+        );
+        let mut found = false;
+        self.items.retain(|p| match p {
+            GroupProperty::Image16x14(_)
+            | GroupProperty::ImageFile16x14(_)
+            | GroupProperty::ImageData16x14(_) => {
+                if found {
+                    warnings.push(Warning::new(path, "Ignored additional image"));
+                    return false;
+                }
+                found = true;
+                true
+            }
+            _ => true,
+        });
+        for p in &mut self.items {
+            if let GroupProperty::Name(names) = p {
+                sanitize_names(names, path, warnings);
+            }
+        }
+    }
+}
+
+impl Device {
+    fn sanitize(&mut self, path: &str, warnings: &mut Vec<Warning>) {
+        let mut found = false;
+        self.items.retain(|p| match p {
+            DeviceProperty::Image16x14(_)
+            | DeviceProperty::ImageFile16x14(_)
+            | DeviceProperty::ImageData16x14(_) => {
+                if found {
+                    warnings.push(Warning::new(path, "Ignored additional image"));
+                    return false;
+                }
+                found = true;
+                true
+            }
+            _ => true,
+        });
+        for p in &mut self.items {
+            match p {
+                DeviceProperty::Type(t) => {
+                    sanitize_value(
+                        &mut t.ProductCode,
+                        u32_from_hex_dec_value,
+                        "product code",
+                        path,
+                        warnings,
+                    );
+                    sanitize_value(
+                        &mut t.RevisionNo,
+                        u32_from_hex_dec_value,
+                        "revision number",
+                        path,
+                        warnings,
+                    );
+                }
+                DeviceProperty::Name(names) => sanitize_names(names, path, warnings),
+                DeviceProperty::Sm(sms) => {
+                    for (i, sm) in sms.iter_mut().enumerate() {
+                        let path = format!("{}/Sm[{}]", path, i + 1);
+                        sm.sanitize(&path, warnings);
+                    }
+                }
+                DeviceProperty::RxPdo(pdos) => sanitize_pdos(pdos, "RxPdo", path, warnings),
+                DeviceProperty::TxPdo(pdos) => sanitize_pdos(pdos, "TxPdo", path, warnings),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Sm {
+    /// An invalid start address disables the sync manager,
+    /// it is kept so that the indices of the following ones do not change.
+    fn sanitize(&mut self, path: &str, warnings: &mut Vec<Warning>) {
+        let start_address = self
+            .StartAddress
+            .as_deref()
+            .map(u16_from_hex_dec_value)
+            .transpose();
+        match (&self.StartAddress, start_address) {
+            (Some(_), Ok(_)) => {}
+            (None, _) => {
+                warnings.push(Warning::new(
+                    path,
+                    "Missing start address, sync manager disabled",
+                ));
+                self.disable();
+            }
+            (Some(v), Err(err)) => {
+                warnings.push(Warning::new(
+                    path,
+                    format!(
+                        "Invalid start address `{}` ({}), sync manager disabled",
+                        v, err
+                    ),
+                ));
+                self.disable();
+            }
+        }
+        sanitize_value(
+            &mut self.Enable,
+            u8_from_hex_dec_value,
+            "enable flag",
+            path,
+            warnings,
+        );
+        sanitize_value(
+            &mut self.ControlByte,
+            u8_from_hex_dec_value,
+            "control byte",
+            path,
+            warnings,
+        );
+        sanitize_value(
+            &mut self.DefaultSize,
+            u32_from_hex_dec_value,
+            "default size",
+            path,
+            warnings,
+        );
+        sanitize_value(
+            &mut self.Virtual,
+            bool_from_str,
+            "virtual flag",
+            path,
+            warnings,
+        );
+    }
+
+    fn disable(&mut self) {
+        self.StartAddress = Some("0".to_string());
+        self.Enable = None;
+    }
+}
+
+impl Pdo {
+    /// An invalid entry is replaced by a gap of the same length, so that
+    /// the offsets of the following entries do not change. If its length
+    /// is invalid too, the whole PDO is skipped (see [`sanitize_pdos`]).
+    fn sanitize(&mut self, path: &str, warnings: &mut Vec<Warning>) {
+        sanitize_value(&mut self.Fixed, bool_from_str, "fixed flag", path, warnings);
+        sanitize_value(
+            &mut self.Mandatory,
+            bool_from_str,
+            "mandatory flag",
+            path,
+            warnings,
+        );
+        sanitize_value(
+            &mut self.Sm,
+            u8_from_hex_dec_value,
+            "sync manager",
+            path,
+            warnings,
+        );
+        sanitize_names(&mut self.Name, path, warnings);
+        for (i, e) in self.Entry.iter_mut().enumerate() {
+            let path = format!("{}/Entry[{}]", path, i + 1);
+            sanitize_names(&mut e.Name, &path, warnings);
+            let err = match S::PdoEntry::try_from(e.clone()) {
+                Ok(_) => continue,
+                Err(err) => err,
+            };
+            let bit_len = e.BitLen.as_deref().map(u32_from_hex_dec_value);
+            if let Some(Ok(_)) = bit_len {
+                warnings.push(Warning::new(path, format!("Replaced by a gap: {}", err)));
+                *e = Entry {
+                    Index: Some("0".to_string()),
+                    DependOnSlot: None,
+                    SubIndex: None,
+                    BitLen: e.BitLen.take(),
+                    Name: vec![],
+                    DataType: None,
+                    unknown: mem::take(&mut e.unknown),
+                };
+            }
+        }
+    }
+}
+
+impl Module {
+    fn sanitize(&mut self, path: &str, warnings: &mut Vec<Warning>) {
+        sanitize_names(&mut self.Name, path, warnings);
+        sanitize_pdos(&mut self.RxPdo, "RxPdo", path, warnings);
+        sanitize_pdos(&mut self.TxPdo, "TxPdo", path, warnings);
+    }
+}
+
+fn sanitize_pdos(pdos: &mut Vec<Pdo>, name: &str, path: &str, warnings: &mut Vec<Warning>) {
+    let mut i = 0;
+    pdos.retain_mut(|pdo| {
+        i += 1;
+        let path = format!("{}/{}[{}]", path, name, i);
+        pdo.sanitize(&path, warnings);
+        keep_valid::<_, S::Pdo>(pdo, path, warnings)
+    });
+}
+
+/// Whether a sanitized item can be converted, a warning is added if not.
+fn keep_valid<T: Clone, U>(item: &T, path: String, warnings: &mut Vec<Warning>) -> bool
+where
+    U: TryFrom<T, Error = Error>,
+{
+    match U::try_from(item.clone()) {
+        Ok(_) => true,
+        Err(err) => {
+            warnings.push(Warning::new(path, format!("Skipped: {}", err)));
+            false
+        }
+    }
+}
+
+fn sanitize_names(names: &mut [Name], path: &str, warnings: &mut Vec<Warning>) {
+    for n in names {
+        if let Some(lc_id) = &n.LcId {
+            if let Err(err) = u16_from_hex_dec_value(lc_id) {
+                warnings.push(Warning::new(
+                    path,
+                    format!(
+                        "Ignored invalid LcId `{}` of name `{}` ({})",
+                        lc_id,
+                        n.value.as_deref().unwrap_or_default(),
+                        err
+                    ),
+                ));
+                n.LcId = None;
+            }
+        }
+    }
+}
+
+/// Remove an optional value that can't be parsed, so that its default is used.
+fn sanitize_value<T>(
+    value: &mut Option<String>,
+    parse: fn(&str) -> Result<T>,
+    what: &str,
+    path: &str,
+    warnings: &mut Vec<Warning>,
+) {
+    if let Some(v) = value {
+        if let Err(err) = parse(v) {
+            warnings.push(Warning::new(
+                path,
+                format!("Ignored invalid {} `{}` ({})", what, v, err),
+            ));
+            *value = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn lenient_conversion() {
+        let s = r##"
+        <EtherCATInfo>
+            <Vendor>
+                <Id>#x00000002</Id>
+                <Name LcId="foo">Vendor</Name>
+            </Vendor>
+            <Descriptions>
+                <Devices>
+                    <Device>
+                        <Name>No type</Name>
+                    </Device>
+                    <Device>
+                        <Type ProductCode="#x45" RevisionNo="#x001">Foo</Type>
+                        <Name>Bar</Name>
+                        <RxPdo Fixed="yes" Sm="2">
+                            <Index>#x1600</Index>
+                            <Name>Out</Name>
+                        </RxPdo>
+                    </Device>
+                </Devices>
+            </Descriptions>
+        </EtherCATInfo>"##;
+        let raw = EtherCATInfo::from(&xml::parse(s).unwrap());
+        let mut warnings = vec![];
+        let info = raw.try_into_lenient("EtherCATInfo", &mut warnings).unwrap();
+
+        assert_eq!(info.vendor.name, S::LocalizedText::from("Vendor"));
+        assert_eq!(info.description.devices.len(), 1);
+        assert_eq!(info.description.devices[0].desc, "Foo");
+        assert!(!info.description.devices[0].rx_pdo[0].fixed);
+
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[0].path, "/EtherCATInfo/Vendor");
+        assert!(warnings[0]
+            .message
+            .starts_with("Ignored invalid LcId `foo`"));
+        assert_eq!(
+            warnings[1],
+            Warning::new(
+                "/EtherCATInfo/Descriptions/Devices/Device[1]",
+                "Skipped: Mandatory device type not found"
+            )
+        );
+        assert_eq!(
+            warnings[2].path,
+            "/EtherCATInfo/Descriptions/Devices/Device[2]/RxPdo[1]"
+        );
+        assert!(warnings[2]
+            .message
+            .starts_with("Ignored invalid fixed flag `yes`"));
+    }
+
+    fn convert(s: &str) -> (S::EtherCatInfo, Vec<Warning>) {
+        let raw = EtherCATInfo::from(&xml::parse(s).unwrap());
+        let mut warnings = vec![];
+        let info = raw.try_into_lenient("EtherCATInfo", &mut warnings).unwrap();
+        (info, warnings)
+    }
+
+    #[test]
+    fn replace_invalid_entries_by_gaps() {
+        let s = r##"
+        <EtherCATInfo>
+            <Vendor><Id>2</Id></Vendor>
+            <Descriptions>
+                <Devices>
+                    <Device>
+                        <Type>Foo</Type>
+                        <Name>Bar</Name>
+                        <TxPdo Sm="3">
+                            <Index>#x1a00</Index>
+                            <Name>In</Name>
+                            <Entry><Index>#x6000</Index><SubIndex>1</SubIndex><BitLen>1</BitLen></Entry>
+                            <Entry><Index>#xZZ</Index><SubIndex>2</SubIndex><BitLen>8</BitLen></Entry>
+                            <Entry><Index>#x6000</Index><SubIndex>-1</SubIndex><BitLen>1</BitLen></Entry>
+                            <Entry><Index>#x6000</Index><SubIndex>5</SubIndex><BitLen>1</BitLen></Entry>
+                        </TxPdo>
+                        <TxPdo Sm="3">
+                            <Index>#x1a01</Index>
+                            <Name>Invalid length</Name>
+                            <Entry><Index>#x6010</Index><SubIndex>1</SubIndex><BitLen>x</BitLen></Entry>
+                        </TxPdo>
+                        <TxPdo Sm="3">
+                            <Index>#x1a02</Index>
+                            <Name>Missing length</Name>
+                            <Entry><Index>#x6020</Index><SubIndex>1</SubIndex></Entry>
+                        </TxPdo>
+                        <TxPdo Sm="3">
+                            <Index>foo</Index>
+                            <Name>Invalid</Name>
+                        </TxPdo>
+                    </Device>
+                </Devices>
+            </Descriptions>
+        </EtherCATInfo>"##;
+        let (info, warnings) = convert(s);
+        let device = &info.description.devices[0];
+        assert_eq!(device.tx_pdo.len(), 1);
+        let entries: Vec<_> = device.tx_pdo[0]
+            .entries
+            .iter()
+            .map(|e| (e.entry_idx, e.bit_len))
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (S::PdoEntryIdx::new(0x6000, 1), 1),
+                (S::PdoEntryIdx::new(0, 0), 8),
+                (S::PdoEntryIdx::new(0, 0), 1),
+                (S::PdoEntryIdx::new(0x6000, 5), 1),
+            ]
+        );
+
+        let paths: Vec<_> = warnings.iter().map(|w| w.path.as_str()).collect();
+        let pdo = "/EtherCATInfo/Descriptions/Devices/Device[1]/TxPdo";
+        assert_eq!(
+            paths,
+            vec![
+                format!("{}[1]/Entry[2]", pdo),
+                format!("{}[1]/Entry[3]", pdo),
+                format!("{}[2]", pdo),
+                format!("{}[3]", pdo),
+                format!("{}[4]", pdo),
+            ]
+        );
+        assert!(warnings[..2]
+            .iter()
+            .all(|w| w.message.starts_with("Replaced by a gap: ")));
+        assert!(warnings[2..]
+            .iter()
+            .all(|w| w.message.starts_with("Skipped: ")));
+        assert!(warnings[3].message.contains("`BitLen`"));
+    }
+
+    #[test]
+    fn default_invalid_sm_values() {
+        let s = r##"
+        <EtherCATInfo>
+            <Vendor><Id>2</Id></Vendor>
+            <Descriptions>
+                <Devices>
+                    <Device>
+                        <Type>Foo</Type>
+                        <Name>Bar</Name>
+                        <Sm StartAddress="#x1000" Enable="yes"/>
+                        <Sm StartAddress="foo" Enable="1"/>
+                        <Sm Enable="1"/>
+                        <Sm StartAddress="#x1100" Enable="1"/>
+                        <RxPdo Sm="x">
+                            <Index>#x1600</Index>
+                            <Name>Out</Name>
+                        </RxPdo>
+                    </Device>
+                </Devices>
+            </Descriptions>
+        </EtherCATInfo>"##;
+        let (info, warnings) = convert(s);
+        let device = &info.description.devices[0];
+        assert_eq!(device.sm.len(), 4);
+        assert!(!device.sm[0].enable);
+        assert_eq!(device.sm[0].start_address, 0x1000);
+        assert!(!device.sm[1].enable);
+        assert!(!device.sm[2].enable);
+        assert!(device.sm[3].enable);
+        assert_eq!(device.sm[3].start_address, 0x1100);
+        assert_eq!(device.rx_pdo[0].sm, None);
+
+        let messages: Vec<_> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].starts_with("Ignored invalid enable flag `yes`"));
+        assert!(messages[1].starts_with("Invalid start address `foo`"));
+        assert_eq!(messages[2], "Missing start address, sync manager disabled");
+        assert!(messages[3].starts_with("Ignored invalid sync manager `x`"));
+    }
+
+    #[test]
+    fn skip_modules_without_type() {
+        let s = r##"
+        <EtherCATInfo>
+            <Vendor><Id>2</Id></Vendor>
+            <Descriptions>
+                <Devices/>
+                <Modules>
+                    <Module><Name>No type</Name></Module>
+                    <Module><Type>M1</Type><Name>Module</Name></Module>
+                </Modules>
+            </Descriptions>
+        </EtherCATInfo>"##;
+        let (info, warnings) = convert(s);
+        assert_eq!(info.description.modules.len(), 1);
+        assert_eq!(info.description.modules[0].r#type, "M1");
+        assert_eq!(
+            warnings,
+            vec![Warning::new(
+                "/EtherCATInfo/Descriptions/Modules/Module[1]",
+                "Skipped: Mandatory module type not found"
+            )]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Conversion of the `Mailbox` elements of devices and modules.
+
+use super::{conversions::*, lenient::Tolerance, tree::*, *};
+use crate::structs as S;
+use ethercat_types as ec;
+use std::time::Duration;
+
+/// Child elements of every `InitCmd`, the others are protocol specific.
+const INIT_CMD_ELEMENTS: &[&str] = &["Transition", "Data", "Timeout", "Comment"];
+
+/// Convert a `Mailbox` element.
+///
+/// `InitCmd` elements that are tolerated are kept in their protocol element.
+pub(super) fn mailbox_from(e: &XmlElement, t: &mut Tolerance) -> Result<S::Mailbox> {
+    let mut init_cmds = vec![];
+    let mut unknown = vec![];
+    for protocol in e.children() {
+        let mut rest = protocol.clone();
+        rest.nodes.clear();
+        let mut converted = vec![];
+        for n in &protocol.nodes {
+            match n {
+                XmlNode::Element(c) if c.name == "InitCmd" => {
+                    let count = converted.len();
+                    let path = format!("{}/InitCmd[{}]", protocol.name, count + 1);
+                    let convert = |c: &XmlElement| init_cmd_from(&protocol.name, c);
+                    match t.convert(c, &path, convert, &mut vec![])? {
+                        Some(cmd) => converted.push(Some(cmd)),
+                        None => {
+                            converted.push(None);
+                            rest.nodes.push(n.clone());
+                        }
+                    }
+                }
+                _ => rest.nodes.push(n.clone()),
+            }
+        }
+        init_cmds.extend(converted.into_iter().flatten());
+        unknown.push(rest);
+    }
+    Ok(S::Mailbox {
+        attributes: e.attributes.clone(),
+        init_cmds,
+        unknown,
+    })
+}
+
+fn init_cmd_from(protocol: &str, e: &XmlElement) -> Result<S::InitCmd> {
+    let transitions = children(e, "Transition")
+        .filter_map(XmlElement::text)
+        .map(str::parse)
+        .collect::<Result<_>>()?;
+    // Attributes and child elements of the protocol specific commands.
+    let (attributes, elements): (&[&str], &[&str]) = match protocol {
+        "CoE" => (&["CompleteAccess"], &["Index", "SubIndex"]),
+        "SoE" => (&["Chn"], &["IDN"]),
+        _ => (&[], &[]),
+    };
+    let kind = match protocol {
+        "CoE" => init_cmd_sdo(e)?,
+        "SoE" => S::InitCmdKind::Idn {
+            idn: u16_from_hex_dec_value(required(e, "IDN")?)?,
+            drive_no: attribute(e, "Chn")
+                .map(u8_from_hex_dec_value)
+                .transpose()?
+                .unwrap_or(0),
+        },
+        _ => S::InitCmdKind::Other {
+            protocol: protocol.to_string(),
+            fields: e
+                .children()
+                .filter(|c| !INIT_CMD_ELEMENTS.contains(&c.name.as_str()))
+                .map(|c| {
+                    (
+                        c.name.clone(),
+                        c.text().unwrap_or_default().trim().to_string(),
+                    )
+                })
+                .collect(),
+        },
+    };
+    let data = text(e, "Data")
+        .map(|d| S::HexBinary(d.to_string()).to_bytes())
+        .transpose()?
+        .unwrap_or_default();
+    let timeout = text(e, "Timeout")
+        .map(u32_from_hex_dec_value)
+        .transpose()?
+        .map(|ms| Duration::from_millis(u64::from(ms)));
+    Ok(S::InitCmd {
+        transitions,
+        data,
+        timeout,
+        comment: text(e, "Comment").map(str::to_string),
+        attributes: unknown_attributes(e, attributes),
+        unknown: match kind {
+            S::InitCmdKind::Other { .. } => vec![],
+            _ => e
+                .children()
+                .filter(|c| {
+                    let name = c.name.as_str();
+                    !INIT_CMD_ELEMENTS.contains(&name) && !elements.contains(&name)
+                })
+                .cloned()
+                .collect(),
+        },
+        kind,
+    })
+}
+
+fn init_cmd_sdo(e: &XmlElement) -> Result<S::InitCmdKind> {
+    let idx = u16_from_hex_dec_value(required(e, "Index")?)?;
+    Ok(S::InitCmdKind::Sdo {
+        idx: ec::Idx::from(idx),
+        sub_idx: ec::SubIdx::from(
+            text(e, "SubIndex")
+                .map(u8_from_hex_dec_value)
+                .transpose()?
+                .unwrap_or(0),
+        ),
+        complete_access: flag(e, "CompleteAccess")?,
+        depend_on_slot: match child(e, "Index") {
+            Some(index) => flag(index, "DependOnSlot")?,
+            None => false,
+        },
+    })
+}
+
+fn flag(e: &XmlElement, name: &str) -> Result<bool> {
+    Ok(attribute(e, name)
+        .map(bool_from_str)
+        .transpose()?
+        .unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+
+    #[test]
+    fn convert_mailbox() {
+        let s = r##"
+        <Mailbox DataLinkLayer="true">
+            <EoE IP="true">
+                <InitCmd>
+                    <Transition>PS</Transition>
+                    <Type>1</Type>
+                    <Data>C0A80001</Data>
+                </InitCmd>
+            </EoE>
+            <CoE CompleteAccess="true" SdoInfo="true">
+                <InitCmd>
+                    <Transition>PS</Transition>
+                    <Index DependOnSlot="1">#x8000</Index>
+                    <SubIndex>10</SubIndex>
+                    <Data>001F7E40</Data>
+                    <Comment>Module id</Comment>
+                </InitCmd>
+                <InitCmd CompleteAccess="1">
+                    <Transition>IP</Transition>
+                    <Transition>PS</Transition>
+                    <Index>#x1C12</Index>
+                    <Data>0100 0016</Data>
+                    <Timeout>500</Timeout>
+                </InitCmd>
+            </CoE>
+            <SoE>
+                <InitCmd Chn="1">
+                    <Transition>PS</Transition>
+                    <IDN>32</IDN>
+                    <Data>0200</Data>
+                </InitCmd>
+            </SoE>
+        </Mailbox>"##;
+        let mailbox = mailbox_from(&xml::parse(s).unwrap(), &mut Tolerance::strict()).unwrap();
+        assert_eq!(
+            mailbox.attributes,
+            vec![("DataLinkLayer".to_string(), "true".to_string())]
+        );
+        assert_eq!(mailbox.init_cmds.len(), 4);
+
+        let eoe = &mailbox.init_cmds[0];
+        assert_eq!(
+            eoe.kind,
+            S::InitCmdKind::Other {
+                protocol: "EoE".to_string(),
+                fields: vec![("Type".to_string(), "1".to_string())],
+            }
+        );
+        assert_eq!(eoe.data, vec![0xC0, 0xA8, 0x00, 0x01]);
+
+        let module_id = &mailbox.init_cmds[1];
+        assert_eq!(module_id.transitions, vec![S::Transition::PreopToSafeop]);
+        assert_eq!(
+            module_id.kind,
+            S::InitCmdKind::Sdo {
+                idx: ec::Idx::from(0x8000),
+                sub_idx: ec::SubIdx::from(10),
+                complete_access: false,
+                depend_on_slot: true,
+            }
+        );
+        assert_eq!(module_id.data, vec![0x00, 0x1F, 0x7E, 0x40]);
+        assert_eq!(module_id.comment.as_deref(), Some("Module id"));
+
+        let assignment = &mailbox.init_cmds[2];
+        assert_eq!(assignment.transitions.len(), 2);
+        assert_eq!(assignment.data, vec![0x01, 0x00, 0x00, 0x16]);
+        assert_eq!(assignment.timeout, Some(Duration::from_millis(500)));
+        assert!(matches!(
+            assignment.kind,
+            S::InitCmdKind::Sdo {
+                complete_access: true,
+                sub_idx,
+                ..
+            } if sub_idx == ec::SubIdx::from(0)
+        ));
+
+        assert_eq!(
+            mailbox.init_cmds[3].kind,
+            S::InitCmdKind::Idn {
+                idn: 32,
+                drive_no: 1
+            }
+        );
+
+        let protocols: Vec<_> = mailbox.unknown.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(protocols, vec!["EoE", "CoE", "SoE"]);
+        assert!(mailbox.unknown.iter().all(|p| p.children().count() == 0));
+        assert_eq!(mailbox.unknown[0].attributes.len(), 1);
+        assert_eq!(mailbox.unknown[1].attributes.len(), 2);
+    }
+
+    #[test]
+    fn reject_unknown_transitions() {
+        let s = "<Mailbox><CoE><InitCmd><Transition>XY</Transition><Index>1</Index></InitCmd></CoE></Mailbox>";
+        let err = mailbox_from(&xml::parse(s).unwrap(), &mut Tolerance::strict()).unwrap_err();
+        assert!(err.to_string().contains("`XY`"));
+    }
+
+    #[test]
+    fn keep_invalid_init_cmds_in_lenient_mode() {
+        let s = r##"
+        <Mailbox>
+            <CoE>
+                <InitCmd><Transition>XY</Transition><Index>#x1C12</Index></InitCmd>
+                <InitCmd><Transition>PS</Transition><Index>#x1C12</Index><Data>0100</Data></InitCmd>
+                <InitCmd><Transition>PS</Transition><Index>#x1C13</Index><Data>01G0</Data></InitCmd>
+            </CoE>
+        </Mailbox>"##;
+        let mut warnings = vec![];
+        let mut t = Tolerance::lenient("/Device", &mut warnings);
+        let mailbox = mailbox_from(&xml::parse(s).unwrap(), &mut t.nested("Mailbox")).unwrap();
+        assert_eq!(mailbox.init_cmds.len(), 1);
+        assert_eq!(mailbox.init_cmds[0].data, vec![0x01, 0x00]);
+
+        let kept: Vec<_> = mailbox.unknown[0].children().collect();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(text(kept[0], "Transition"), Some("XY"));
+        assert_eq!(text(kept[1], "Data"), Some("01G0"));
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].path, "/Device/Mailbox/CoE/InitCmd[1]");
+        assert!(warnings[0].message.contains("`XY`"));
+        assert_eq!(warnings[1].path, "/Device/Mailbox/CoE/InitCmd[3]");
+    }
+}
@@ -1,37 +1,50 @@
-use serde::Deserialize;
+use crate::xml::{XmlElement, XmlNode};
 use std::{
     convert::TryInto,
     io::{Error, ErrorKind, Result},
 };
 
 mod conversions;
+mod dictionary;
+mod fmmu;
+mod from_tree;
+mod info;
+mod lenient;
+mod mailbox;
+mod tree;
+
+pub(crate) use dictionary::dictionary_from;
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct EtherCATInfo {
     Version: Option<String>,
     InfoReference: Option<String>,
-    Vendor: Vendor,
+    Vendor: Option<Vendor>,
     Descriptions: Option<Descriptions>,
     Modules: Option<Modules>,
+    attributes: Vec<(String, String)>,
+    namespaces: Vec<(String, String)>,
+    unknown: Vec<XmlElement>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 struct Vendor {
-    FileVersion: Option<u32>,
-    Id: String,
-    Name: Option<Vec<Name>>,
+    FileVersion: Option<String>,
+    Id: Option<String>,
+    Name: Vec<Name>,
     Comment: Option<String>,
     URL: Option<String>,
     DescriptionURL: Option<String>,
     Image16x14: Option<String>,
     ImageFile16x14: Option<String>,
     ImageData16x14: Option<String>,
+    unknown: Vec<XmlElement>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Descriptions {
     Groups: Option<Groups>,
     Devices: Devices,
@@ -39,37 +52,34 @@ pub struct Descriptions {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Groups {
-    #[serde(rename = "$value")]
-    items: Option<Vec<Group>>,
+    items: Vec<Group>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Devices {
-    #[serde(rename = "$value")]
-    items: Option<Vec<Device>>,
+    items: Vec<Device>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Modules {
-    #[serde(rename = "$value")]
-    items: Option<Vec<Module>>,
+    items: Vec<Module>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Group {
-    SortOrder: Option<i32>,
+    SortOrder: Option<String>,
     ParentGroup: Option<String>,
-    #[serde(rename = "$value")]
     items: Vec<GroupProperty>,
+    unknown: Vec<XmlElement>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum GroupProperty {
     Type(String),
     Name(Vec<Name>),
@@ -80,159 +90,119 @@ pub enum GroupProperty {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Device {
     Physics: Option<String>,
-    #[serde(rename = "$value")]
     items: Vec<DeviceProperty>,
+    info: Option<XmlElement>,
+    fmmu: Vec<XmlElement>,
+    mailbox: Option<XmlElement>,
+    profiles: Vec<XmlElement>,
+    attributes: Vec<(String, String)>,
+    unknown: Vec<XmlElement>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Name {
     LcId: Option<String>,
-    #[serde(rename = "$value")]
     value: Option<String>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub enum DeviceProperty {
     Type(DeviceType),
     Name(Vec<Name>),
     RxPdo(Vec<RxPdo>),
     TxPdo(Vec<TxPdo>),
     Sm(Vec<Sm>),
-    Info {
-        // TODO
-    },
-    HideType {
-        // TODO
-    },
-    GroupType {
-        // TODO
-    },
-    URL {
-        // TODO
-    },
-    Profile {
-        // TODO
-    },
-    Eeprom {
-        // TODO
-    },
-    Fmmu {
-        // TODO
-    },
+    GroupType(String),
     Image16x14(String),
     ImageFile16x14(String),
     ImageData16x14(String),
-    Mailbox {
-        // TODO
-    },
-    Dc {
-        // TODO
-    },
-    Slots {
-        // TODO
-    },
-    Su {
-        // TODO
-    },
-    AlternativeType {
-        // TODO
-    },
-    VendorSpecific {
-        // TODO
-    },
-    SubDevice {
-        // TODO
-    },
-    ESC {
-        // TODO
-    },
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct DeviceType {
-    ModulePdoGroup: Option<String>,
     ProductCode: Option<String>,
     RevisionNo: Option<String>,
-    #[serde(rename = "$value")]
     Description: String,
+    attributes: Vec<(String, String)>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Sm {
-    Enable: Option<u8>,
-    StartAddress: String,
+    Enable: Option<String>,
+    StartAddress: Option<String>,
     ControlByte: Option<String>,
     DefaultSize: Option<String>,
     Virtual: Option<String>,
+    text: Option<String>,
+    attributes: Vec<(String, String)>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Entry {
-    Index: Index,
+    Index: Option<String>,
+    /// `DependOnSlot` attribute of `Index`.
+    DependOnSlot: Option<String>,
     SubIndex: Option<String>,
-    BitLen: usize,
-    Name: Option<Vec<Name>>,
+    BitLen: Option<String>,
+    Name: Vec<Name>,
     DataType: Option<String>,
+    unknown: Vec<XmlElement>,
 }
 
 pub type RxPdo = Pdo;
 pub type TxPdo = Pdo;
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Pdo {
-    Sm: Option<u8>,
+    Sm: Option<String>,
     Fixed: Option<String>,
     Mandatory: Option<String>,
-    Index: Index,
+    Index: Option<String>,
+    /// `DependOnSlot` attribute of `Index`.
+    DependOnSlot: Option<String>,
     Name: Vec<Name>,
-    Entry: Option<Vec<Entry>>,
+    Entry: Vec<Entry>,
+    attributes: Vec<(String, String)>,
+    unknown: Vec<XmlElement>,
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Deserialize, PartialEq)]
-pub struct Index {
-    DependOnSlot: Option<usize>,
-    #[serde(rename = "$value")]
-    value: String,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Module {
-    Type: String,
+    Type: Option<String>,
     Name: Vec<Name>,
-    TxPdo: Option<Vec<TxPdo>>,
-    RxPdo: Option<Vec<RxPdo>>,
-    Mailbox: Option<Mailbox>,
-    Profile: Option<Profile>,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
-pub struct Profile {
-    // TODO
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Deserialize, PartialEq)]
-pub struct Mailbox {
-    // TODO
+    TxPdo: Vec<TxPdo>,
+    RxPdo: Vec<RxPdo>,
+    mailbox: Option<XmlElement>,
+    profiles: Vec<XmlElement>,
+    attributes: Vec<(String, String)>,
+    type_attributes: Vec<(String, String)>,
+    unknown: Vec<XmlElement>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_xml_rs::from_str;
-    use std::{fs::File, io::prelude::*};
+    use crate::xml;
+    use std::fs;
+
+    fn from_str<'a, T: From<&'a XmlElement>>(tree: &'a XmlElement) -> T {
+        T::from(tree)
+    }
+
+    fn fixture(file_name: &str) -> EtherCATInfo {
+        let xml_string = fs::read_to_string(format!("tests/fixtures/{}", file_name)).unwrap();
+        EtherCATInfo::from(&xml::parse(&xml_string).unwrap())
+    }
 
     #[test]
     fn ethercat_info() {
@@ -250,67 +220,69 @@ mod tests {
             </Descriptions>
         </EtherCATInfo>
         "##;
-        let info: EtherCATInfo = from_str(s).unwrap();
+        let info: EtherCATInfo = from_str(&xml::parse(s).unwrap());
 
         assert_eq!(
             info,
             EtherCATInfo {
                 Version: Some("1.11".to_string()),
                 InfoReference: Some("FooBar.xml".to_string()),
-                Vendor: Vendor {
-                    FileVersion: Some(99),
-                    Id: "#x00000000".to_string(),
-                    Name: Some(vec![Name {
+                Vendor: Some(Vendor {
+                    FileVersion: Some("0099".to_string()),
+                    Id: Some("#x00000000".to_string()),
+                    Name: vec![Name {
                         LcId: None,
                         value: Some("Vendor Foo".to_string()),
-                    }]),
+                    }],
                     Comment: None,
                     URL: None,
                     DescriptionURL: None,
                     Image16x14: None,
                     ImageFile16x14: None,
                     ImageData16x14: Some("7D".to_string()),
-                },
+                    unknown: vec![],
+                }),
                 Descriptions: Some(Descriptions {
-                    Groups: Some(Groups { items: None }),
-                    Devices: Devices { items: None },
+                    Groups: Some(Groups { items: vec![] }),
+                    Devices: Devices { items: vec![] },
                     Modules: None,
                 }),
                 Modules: None,
+                attributes: vec![(
+                    "xsi:noNamespaceSchemaLocation".to_string(),
+                    "EtherCATInfo.xsd".to_string()
+                )],
+                namespaces: vec![(
+                    "xsi".to_string(),
+                    "http://www.w3.org/2001/XMLSchema-instance".to_string()
+                )],
+                unknown: vec![],
             }
         );
     }
 
     #[test]
     fn ethercat_info_crated_by_beckhoff() {
-        let mut file = File::open("tests/fixtures/Beckhoff_EK11xx.xml").unwrap();
-        let mut xml_string = String::new();
-        file.read_to_string(&mut xml_string).unwrap();
-        let _: EtherCATInfo = from_str(&xml_string).unwrap();
+        let info = fixture("Beckhoff_EK11xx.xml");
+        assert!(info.Vendor.is_some());
     }
 
     #[test]
     fn ethercat_info_crated_by_weidmueller() {
-        let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
-        let mut xml_string = String::new();
-        file.read_to_string(&mut xml_string).unwrap();
-        let _: EtherCATInfo = from_str(&xml_string).unwrap();
+        let info = fixture("Weidmueller_UR20_FBC.xml");
+        assert!(info.Vendor.is_some());
     }
 
     #[test]
     fn ethercat_info_crated_by_weidmueller_module_information() {
-        let mut file = File::open("tests/fixtures/Weidmueller_UR20_IO.xml").unwrap();
-        let mut xml_string = String::new();
-        file.read_to_string(&mut xml_string).unwrap();
-        let _: EtherCATInfo = from_str(&xml_string).unwrap();
+        let info = fixture("Weidmueller_UR20_IO.xml");
+        assert!(info.Vendor.is_some());
     }
 
     #[test]
     fn ethercat_info_crated_by_igh() {
-        let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC_from_IgH.xml").unwrap();
-        let mut xml_string = String::new();
-        file.read_to_string(&mut xml_string).unwrap();
-        let _: EtherCATInfo = from_str(&xml_string).unwrap();
+        let info = fixture("Weidmueller_UR20_FBC_from_IgH.xml");
+        assert!(info.Vendor.is_some());
     }
 
     #[test]
@@ -322,14 +294,14 @@ mod tests {
                         <Name LcId="1049">Vendör Näme</Name>
                         <ImageData16x14>7D7D7D7</ImageData16x14>
                 </Vendor>"##;
-        let vendor: Vendor = from_str(s).unwrap();
+        let vendor: Vendor = from_str(&xml::parse(s).unwrap());
 
         assert_eq!(
             vendor,
             Vendor {
-                FileVersion: Some(45),
-                Id: "#x00000999".to_string(),
-                Name: Some(vec![
+                FileVersion: Some("0045".to_string()),
+                Id: Some("#x00000999".to_string()),
+                Name: vec![
                     Name {
                         LcId: Some("1033".to_string()),
                         value: Some("Vendor Name".to_string()),
@@ -338,13 +310,14 @@ mod tests {
                         LcId: Some("1049".to_string()),
                         value: Some("Vendör Näme".to_string()),
                     }
-                ]),
+                ],
                 Comment: None,
                 URL: None,
                 DescriptionURL: None,
                 Image16x14: None,
                 ImageFile16x14: None,
                 ImageData16x14: Some("7D7D7D7".to_string()),
+                unknown: vec![],
             }
         )
     }
@@ -362,13 +335,13 @@ mod tests {
                                 </Groups>
                                 <Devices></Devices>
                         </Descriptions>"##;
-        let descriptions: Descriptions = from_str(s).unwrap();
+        let descriptions: Descriptions = from_str(&xml::parse(s).unwrap());
         assert_eq!(
             descriptions,
             Descriptions {
                 Groups: Some(Groups {
-                    items: Some(vec![Group {
-                        SortOrder: Some(0),
+                    items: vec![Group {
+                        SortOrder: Some("0".to_string()),
                         ParentGroup: None,
                         items: vec![
                             GroupProperty::Type("Coupler".to_string()),
@@ -377,10 +350,11 @@ mod tests {
                                 value: Some("Coupler".to_string()),
                             }]),
                             GroupProperty::ImageData16x14("44".to_string()),
-                        ]
-                    }]),
+                        ],
+                        unknown: vec![],
+                    }],
                 }),
-                Devices: Devices { items: None },
+                Devices: Devices { items: vec![] },
                 Modules: None,
             }
         );
@@ -396,21 +370,20 @@ mod tests {
             <Name></Name>
             <DataType>BOOL</DataType>
           </Entry>"##;
-        let entry: Entry = from_str(s).unwrap();
+        let entry: Entry = from_str(&xml::parse(s).unwrap());
         assert_eq!(
             entry,
             Entry {
-                Index: Index {
-                    DependOnSlot: None,
-                    value: "#xf200".to_string(),
-                },
+                Index: Some("#xf200".to_string()),
+                DependOnSlot: None,
                 SubIndex: Some("2".into()),
-                BitLen: 1,
-                Name: Some(vec![Name {
+                BitLen: Some("1".to_string()),
+                Name: vec![Name {
                     LcId: None,
                     value: None
-                }]),
+                }],
                 DataType: Some("BOOL".to_string()),
+                unknown: vec![],
             }
         );
     }
@@ -429,34 +402,33 @@ mod tests {
             <DataType>BOOL</DataType>
           </Entry>
         </RxPdo>"##;
-        let pdo: RxPdo = from_str(s).unwrap();
+        let pdo: RxPdo = from_str(&xml::parse(s).unwrap());
         assert_eq!(
             pdo,
             RxPdo {
-                Sm: Some(2),
+                Sm: Some("2".to_string()),
                 Fixed: Some("1".to_string()),
                 Mandatory: Some("true".to_string()),
-                Index: Index {
-                    DependOnSlot: None,
-                    value: "#x16ff".to_string(),
-                },
+                Index: Some("#x16ff".to_string()),
+                DependOnSlot: None,
                 Name: vec![Name {
                     LcId: None,
                     value: None
                 }],
-                Entry: Some(vec![Entry {
-                    Index: Index {
-                        DependOnSlot: None,
-                        value: "#xf200".to_string(),
-                    },
+                Entry: vec![Entry {
+                    Index: Some("#xf200".to_string()),
+                    DependOnSlot: None,
                     SubIndex: Some("3".into()),
-                    BitLen: 1,
-                    Name: Some(vec![Name {
+                    BitLen: Some("1".to_string()),
+                    Name: vec![Name {
                         LcId: None,
                         value: None
-                    }]),
+                    }],
                     DataType: Some("BOOL".to_string()),
-                }])
+                    unknown: vec![],
+                }],
+                attributes: vec![],
+                unknown: vec![],
             }
         );
     }
@@ -472,7 +444,7 @@ mod tests {
           <Sm            StartAddress="#x1800" ControlByte="#x64"                     Virtual="true" />
           <Sm Enable="0" StartAddress="#x2400"                    DefaultSize="0" />
         </Device>"##;
-        let device: Device = from_str(s).unwrap();
+        let device: Device = from_str(&xml::parse(s).unwrap());
         assert_eq!(
             device,
             Device {
@@ -480,9 +452,9 @@ mod tests {
                 items: vec![
                     DeviceProperty::Type(DeviceType {
                         Description: "Foo".to_string(),
-                        ModulePdoGroup: None,
                         ProductCode: Some("#x45".to_string()),
                         RevisionNo: Some("#x001".to_string()),
+                        attributes: vec![],
                     }),
                     DeviceProperty::Name(vec![Name {
                         LcId: None,
@@ -490,35 +462,49 @@ mod tests {
                     }]),
                     DeviceProperty::Sm(vec![
                         Sm {
-                            Enable: Some(1),
-                            StartAddress: "#x1000".to_string(),
+                            Enable: Some("1".to_string()),
+                            StartAddress: Some("#x1000".to_string()),
                             ControlByte: Some("#x26".to_string()),
                             DefaultSize: Some("512".to_string()),
                             Virtual: Some("1".to_string()),
+                            text: None,
+                            attributes: vec![],
                         },
                         Sm {
-                            Enable: Some(1),
-                            StartAddress: "#x1400".to_string(),
+                            Enable: Some("1".to_string()),
+                            StartAddress: Some("#x1400".to_string()),
                             ControlByte: Some("#x22".to_string()),
                             DefaultSize: Some("#x200".to_string()),
                             Virtual: Some("0".to_string()),
+                            text: None,
+                            attributes: vec![],
                         },
                         Sm {
                             Enable: None,
-                            StartAddress: "#x1800".to_string(),
+                            StartAddress: Some("#x1800".to_string()),
                             ControlByte: Some("#x64".to_string()),
                             DefaultSize: None,
                             Virtual: Some("true".to_string()),
+                            text: None,
+                            attributes: vec![],
                         },
                         Sm {
-                            Enable: Some(0),
-                            StartAddress: "#x2400".to_string(),
+                            Enable: Some("0".to_string()),
+                            StartAddress: Some("#x2400".to_string()),
                             ControlByte: None,
                             DefaultSize: Some("0".to_string()),
                             Virtual: None,
+                            text: None,
+                            attributes: vec![],
                         }
                     ]),
-                ]
+                ],
+                info: None,
+                fmmu: vec![],
+                mailbox: None,
+                profiles: vec![],
+                attributes: vec![],
+                unknown: vec![],
             }
         );
     }
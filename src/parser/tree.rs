@@ -0,0 +1,47 @@
+//! Helpers for the conversions from the element tree.
+
+use super::*;
+
+pub(super) fn children<'a>(
+    e: &'a XmlElement,
+    name: &'a str,
+) -> impl Iterator<Item = &'a XmlElement> {
+    e.children().filter(move |c| c.name == name)
+}
+
+pub(super) fn child<'a>(e: &'a XmlElement, name: &'a str) -> Option<&'a XmlElement> {
+    children(e, name).next()
+}
+
+/// The trimmed text of the first child element with the given name.
+pub(super) fn text<'a>(e: &'a XmlElement, name: &'a str) -> Option<&'a str> {
+    child(e, name).and_then(XmlElement::text).map(str::trim)
+}
+
+pub(super) fn attribute<'a>(e: &'a XmlElement, name: &str) -> Option<&'a str> {
+    e.attribute(name)
+}
+
+pub(super) fn required<'a>(e: &'a XmlElement, name: &'a str) -> Result<&'a str> {
+    text(e, name).ok_or_else(|| {
+        Error::new(
+            ErrorKind::Other,
+            format!("Mandatory element `{}` of `{}` not found", name, e.name),
+        )
+    })
+}
+
+pub(super) fn unknown_children(e: &XmlElement, known: &[&str]) -> Vec<XmlElement> {
+    e.children()
+        .filter(|c| !known.contains(&c.name.as_str()))
+        .cloned()
+        .collect()
+}
+
+pub(super) fn unknown_attributes(e: &XmlElement, known: &[&str]) -> Vec<(String, String)> {
+    e.attributes
+        .iter()
+        .filter(|(name, _)| !known.contains(&name.as_str()))
+        .cloned()
+        .collect()
+}
@@ -0,0 +1,877 @@
+//! Structural conformance checks against `EtherCATInfo.xsd` and
+//! `EtherCATModule.xsd` (version 1.x).
+//!
+//! The schema is modelled down to the level of PDO entries, `InitCmd`
+//! elements, dictionary objects and DC operation modes.
+//! The content of elements like `VendorSpecific`, `Slots`, `Eeprom`
+//! or the data types of a dictionary is not checked.
+
+use crate::xml::{self, XmlElement, XmlNode};
+use std::{collections::HashMap, error, fmt, io::Result};
+
+/// A part of the document that does not conform to the ESI schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    /// Location of the violation (e.g. `/EtherCATInfo/Vendor/Id`).
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Error returned by [`EtherCatInfo::from_xml_str_strict`](crate::EtherCatInfo::from_xml_str_strict).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError(pub Vec<SchemaViolation>);
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} schema violation(s)", self.0.len())?;
+        for v in &self.0 {
+            write!(f, "\n{}", v)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for SchemaError {}
+
+/// Check a XML document against the structure of `EtherCATInfo.xsd`
+/// (or `EtherCATModule.xsd` for module description files).
+///
+/// Returns all elements, attributes and orderings that are not allowed
+/// by the schema.
+pub fn check_schema(xml: &str) -> Result<Vec<SchemaViolation>> {
+    let root = xml::parse(xml)?;
+    Ok(check_tree(&root))
+}
+
+pub(crate) fn check_tree(root: &XmlElement) -> Vec<SchemaViolation> {
+    let mut violations = vec![];
+    let path = format!("/{}", root.name);
+    if root.name == "EtherCATInfo" {
+        check_element(root, &ETHERCAT_INFO, &path, &mut violations);
+    } else if root.name == "EtherCATModule" {
+        check_element(root, &ETHERCAT_MODULE, &path, &mut violations);
+    } else {
+        violations.push(SchemaViolation {
+            path,
+            message: format!("unexpected root element `{}`", root.name),
+        });
+    }
+    violations
+}
+
+/// Sort the child elements of `root` and its descendants into the order
+/// of the schema.
+///
+/// Elements with the same position keep their order,
+/// elements that are not part of the schema are moved to the end.
+pub(crate) fn sort_tree(root: &mut XmlElement) {
+    match root.name.as_str() {
+        "EtherCATInfo" => sort_element(root, &ETHERCAT_INFO),
+        "EtherCATModule" => sort_element(root, &ETHERCAT_MODULE),
+        _ => {}
+    }
+}
+
+fn sort_element(e: &mut XmlElement, ty: &TypeDecl) {
+    let particles = match ty.content {
+        Content::Sequence(particles) => particles,
+        _ => return,
+    };
+    let position = |n: &XmlNode| match n {
+        XmlNode::Element(c) => particles
+            .iter()
+            .position(|p| p.type_of(&c.name).is_some())
+            .unwrap_or(particles.len()),
+        XmlNode::Text(_) => particles.len(),
+    };
+    e.nodes.sort_by_key(position);
+    for n in &mut e.nodes {
+        if let XmlNode::Element(c) = n {
+            if let Some(ty) = particles.iter().find_map(|p| p.type_of(&c.name)) {
+                sort_element(c, ty);
+            }
+        }
+    }
+}
+
+struct TypeDecl {
+    attributes: &'static [&'static str],
+    content: Content,
+}
+
+enum Content {
+    /// Text only, no child elements.
+    Text,
+    /// Child elements in the given order.
+    Sequence(&'static [Particle]),
+    /// Anything, including attributes (not checked).
+    Any,
+}
+
+struct Particle {
+    names: &'static [(&'static str, &'static TypeDecl)],
+    min: usize,
+    max: Option<usize>,
+}
+
+impl Particle {
+    fn type_of(&self, name: &str) -> Option<&'static TypeDecl> {
+        self.names.iter().find(|(n, _)| *n == name).map(|(_, t)| *t)
+    }
+
+    fn allows_more(&self, count: usize) -> bool {
+        self.max.map(|max| count < max).unwrap_or(true)
+    }
+
+    fn describe(&self) -> String {
+        let names: Vec<_> = self.names.iter().map(|(n, _)| format!("`{}`", n)).collect();
+        if names.len() == 1 {
+            format!("element {}", names[0])
+        } else {
+            format!("one of {}", names.join(", "))
+        }
+    }
+}
+
+macro_rules! particle {
+    ($min:expr, $max:expr, $($name:literal: $ty:expr),+) => {
+        Particle {
+            names: &[$(($name, &$ty)),+],
+            min: $min,
+            max: $max,
+        }
+    };
+}
+
+const UNBOUNDED: Option<usize> = None;
+
+static TEXT: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Text,
+};
+
+static ANY: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Any,
+};
+
+static NAME: TypeDecl = TypeDecl {
+    attributes: &["LcId"],
+    content: Content::Text,
+};
+
+static INDEX: TypeDecl = TypeDecl {
+    attributes: &["DependOnSlot", "DependOnSlotGroup"],
+    content: Content::Text,
+};
+
+static ETHERCAT_INFO: TypeDecl = TypeDecl {
+    attributes: &["Version"],
+    content: Content::Sequence(&[
+        particle!(0, UNBOUNDED, "InfoReference": TEXT),
+        particle!(1, Some(1), "Vendor": VENDOR),
+        particle!(1, Some(1), "Descriptions": DESCRIPTIONS),
+    ]),
+};
+
+static ETHERCAT_MODULE: TypeDecl = TypeDecl {
+    attributes: &["Version"],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Vendor": VENDOR),
+        particle!(1, Some(1), "Modules": MODULES),
+    ]),
+};
+
+static VENDOR: TypeDecl = TypeDecl {
+    attributes: &["FileVersion", "UniqueName"],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Id": TEXT),
+        particle!(0, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Comment": NAME),
+        particle!(0, UNBOUNDED, "URL": NAME),
+        particle!(0, Some(1), "DescriptionURL": TEXT),
+        particle!(0, Some(1), "Image16x14": TEXT, "ImageFile16x14": TEXT, "ImageData16x14": TEXT),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+static DESCRIPTIONS: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Groups": GROUPS),
+        particle!(1, Some(1), "Devices": DEVICES),
+        particle!(0, Some(1), "Modules": MODULES),
+    ]),
+};
+
+static GROUPS: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "Group": GROUP)]),
+};
+
+static DEVICES: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "Device": DEVICE)]),
+};
+
+static MODULES: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "Module": MODULE)]),
+};
+
+static GROUP: TypeDecl = TypeDecl {
+    attributes: &["SortOrder", "ParentGroup"],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Type": TEXT),
+        particle!(1, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Comment": NAME),
+        particle!(0, Some(1), "Image16x14": TEXT, "ImageFile16x14": TEXT, "ImageData16x14": TEXT),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+static DEVICE: TypeDecl = TypeDecl {
+    attributes: &["Physics", "Invisible", "Crc32"],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Type": DEVICE_TYPE),
+        particle!(0, UNBOUNDED, "HideType": HIDE_TYPE),
+        particle!(0, UNBOUNDED, "AlternativeType": ALTERNATIVE_TYPE),
+        particle!(0, UNBOUNDED, "SubDevice": ANY),
+        particle!(1, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Comment": NAME),
+        particle!(0, UNBOUNDED, "URL": NAME),
+        particle!(0, Some(1), "Info": INFO),
+        particle!(1, Some(1), "GroupType": TEXT),
+        particle!(0, UNBOUNDED, "Profile": PROFILE),
+        particle!(0, UNBOUNDED, "Fmmu": FMMU),
+        particle!(0, UNBOUNDED, "Sm": SM),
+        particle!(0, UNBOUNDED, "Su": ANY),
+        particle!(0, UNBOUNDED, "RxPdo": PDO),
+        particle!(0, UNBOUNDED, "TxPdo": PDO),
+        particle!(0, Some(1), "Mailbox": MAILBOX),
+        particle!(0, Some(1), "Dc": DC),
+        particle!(0, Some(1), "Slots": ANY),
+        particle!(0, Some(1), "ESC": ANY),
+        particle!(0, Some(1), "Eeprom": ANY),
+        particle!(0, Some(1), "Image16x14": TEXT, "ImageFile16x14": TEXT, "ImageData16x14": TEXT),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+static DEVICE_TYPE: TypeDecl = TypeDecl {
+    attributes: &[
+        "ProductCode",
+        "RevisionNo",
+        "SerialNo",
+        "CheckProductCode",
+        "CheckRevisionNo",
+        "CheckSerialNo",
+        "TcSmClass",
+        "TcCfgModeSafeOp",
+        "UseLrdLwr",
+        "ModulePdoGroup",
+        "ShowHideableObjects",
+        "DownloadModuleList",
+    ],
+    content: Content::Text,
+};
+
+static HIDE_TYPE: TypeDecl = TypeDecl {
+    attributes: &[
+        "ProductCode",
+        "RevisionNo",
+        "ProductRevision",
+        "ModuleIdent",
+    ],
+    content: Content::Text,
+};
+
+static ALTERNATIVE_TYPE: TypeDecl = TypeDecl {
+    attributes: &["ProductCode", "RevisionNo"],
+    content: Content::Text,
+};
+
+static FMMU: TypeDecl = TypeDecl {
+    attributes: &["OpOnly", "Sm", "Su"],
+    content: Content::Text,
+};
+
+static SM: TypeDecl = TypeDecl {
+    attributes: &[
+        "MinSize",
+        "MaxSize",
+        "DefaultSize",
+        "StartAddress",
+        "ControlByte",
+        "Enable",
+        "OneByteMode",
+        "Virtual",
+        "Watchdog",
+        "OpOnly",
+        "FixedAssignment",
+    ],
+    content: Content::Text,
+};
+
+static PDO: TypeDecl = TypeDecl {
+    attributes: &[
+        "Fixed",
+        "Mandatory",
+        "Virtual",
+        "Sm",
+        "Su",
+        "PdoOrder",
+        "OSFac",
+        "OSMin",
+        "OSMax",
+        "OSIndexInc",
+        "OverwrittenByModule",
+    ],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Index": INDEX),
+        particle!(1, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Exclude": TEXT),
+        particle!(0, UNBOUNDED, "Entry": ENTRY),
+    ]),
+};
+
+static ENTRY: TypeDecl = TypeDecl {
+    attributes: &["Fixed"],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Index": INDEX),
+        particle!(0, Some(1), "SubIndex": TEXT),
+        particle!(1, Some(1), "BitLen": TEXT),
+        particle!(0, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Comment": NAME),
+        particle!(0, Some(1), "DataType": DATA_TYPE),
+    ]),
+};
+
+static DATA_TYPE: TypeDecl = TypeDecl {
+    attributes: &["DScale", "SwapData"],
+    content: Content::Text,
+};
+
+static MODULE: TypeDecl = TypeDecl {
+    attributes: &["Crc32"],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Type": MODULE_TYPE),
+        particle!(1, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Comment": NAME),
+        particle!(0, UNBOUNDED, "RxPdo": PDO),
+        particle!(0, UNBOUNDED, "TxPdo": PDO),
+        particle!(0, Some(1), "SafetyParaMapping": PDO),
+        particle!(0, Some(1), "Mailbox": MAILBOX),
+        particle!(0, UNBOUNDED, "Profile": PROFILE),
+        particle!(0, Some(1), "DcOpModes": ANY),
+        particle!(0, Some(1), "Image16x14": TEXT, "ImageFile16x14": TEXT, "ImageData16x14": TEXT),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+static MODULE_TYPE: TypeDecl = TypeDecl {
+    attributes: &[
+        "ModuleIdent",
+        "ModuleClass",
+        "ModulePdoGroup",
+        "SRA_ParameterSupported",
+    ],
+    content: Content::Text,
+};
+
+static INFO: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(0, Some(1), "Electrical": ANY),
+        particle!(0, Some(1), "StateMachine": STATE_MACHINE),
+        particle!(0, Some(1), "Mailbox": INFO_MAILBOX),
+        particle!(0, Some(1), "EtherCATController": ANY),
+        particle!(0, Some(4), "Port": ANY),
+        particle!(0, UNBOUNDED, "ExecutionUnit": ANY),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+        particle!(0, Some(1), "IdentificationAdo": TEXT),
+        particle!(0, Some(1), "IdentificationReg134": TEXT),
+        particle!(0, UNBOUNDED, "DeviceFeature": ANY),
+    ]),
+};
+
+static STATE_MACHINE: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(0, Some(1), "Timeout": STATE_MACHINE_TIMEOUT),
+        particle!(0, Some(1), "Behavior": ANY),
+    ]),
+};
+
+static STATE_MACHINE_TIMEOUT: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "PreopTimeout": TEXT),
+        particle!(1, Some(1), "SafeopOpTimeout": TEXT),
+        particle!(1, Some(1), "BackToInitTimeout": TEXT),
+        particle!(1, Some(1), "BackToSafeopTimeout": TEXT),
+    ]),
+};
+
+static INFO_MAILBOX: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, Some(1), "Timeout": MAILBOX_TIMEOUT)]),
+};
+
+static MAILBOX_TIMEOUT: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "RequestTimeout": TEXT),
+        particle!(1, Some(1), "ResponseTimeout": TEXT),
+    ]),
+};
+
+static MAILBOX: TypeDecl = TypeDecl {
+    attributes: &["DataLinkLayer", "RealTimeMode"],
+    content: Content::Sequence(&[
+        particle!(0, Some(1), "AoE": AOE),
+        particle!(0, Some(1), "EoE": EOE),
+        particle!(0, Some(1), "CoE": COE),
+        particle!(0, Some(1), "FoE": ANY),
+        particle!(0, Some(1), "SoE": SOE),
+        particle!(0, Some(1), "VoE": ANY),
+    ]),
+};
+
+static AOE: TypeDecl = TypeDecl {
+    attributes: &["AdsRouter", "GenerateOwnNetId", "InitializeOwnNetId"],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "InitCmd": ANY)]),
+};
+
+static EOE: TypeDecl = TypeDecl {
+    attributes: &["IP", "MAC", "TimeStamp"],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "InitCmd": ANY)]),
+};
+
+static COE: TypeDecl = TypeDecl {
+    attributes: &[
+        "SdoInfo",
+        "PdoAssign",
+        "PdoConfig",
+        "PdoUpload",
+        "CompleteAccess",
+        "EdsFile",
+        "DS402Channels",
+        "SegmentedSdo",
+        "DiagHistory",
+        "SdoUploadWithMaxLength",
+        "TimeDistribution",
+    ],
+    content: Content::Sequence(&[
+        particle!(0, UNBOUNDED, "Object": ANY),
+        particle!(0, UNBOUNDED, "InitCmd": COE_INIT_CMD),
+    ]),
+};
+
+static COE_INIT_CMD: TypeDecl = TypeDecl {
+    attributes: &["Fixed", "CompleteAccess", "OverwrittenByModule"],
+    content: Content::Sequence(&[
+        particle!(1, UNBOUNDED, "Transition": TEXT),
+        particle!(1, Some(1), "Index": INDEX),
+        particle!(0, Some(1), "SubIndex": TEXT),
+        particle!(1, Some(1), "Data": INIT_CMD_DATA),
+        particle!(0, Some(1), "Timeout": TEXT),
+        particle!(0, Some(1), "Comment": TEXT),
+    ]),
+};
+
+static SOE: TypeDecl = TypeDecl {
+    attributes: &["ChannelCount", "DriveFollowsBootstrapAddressing"],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "InitCmd": SOE_INIT_CMD)]),
+};
+
+static SOE_INIT_CMD: TypeDecl = TypeDecl {
+    attributes: &["Chn"],
+    content: Content::Sequence(&[
+        particle!(1, UNBOUNDED, "Transition": TEXT),
+        particle!(1, Some(1), "IDN": TEXT),
+        particle!(1, Some(1), "Data": INIT_CMD_DATA),
+        particle!(0, Some(1), "Timeout": TEXT),
+        particle!(0, Some(1), "Comment": TEXT),
+    ]),
+};
+
+static INIT_CMD_DATA: TypeDecl = TypeDecl {
+    attributes: &["AdaptAutomatically"],
+    content: Content::Text,
+};
+
+static PROFILE: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(0, Some(1), "ProfileNo": TEXT),
+        particle!(0, Some(1), "AddInfo": TEXT),
+        particle!(0, Some(1), "ChannelCount": TEXT),
+        particle!(0, UNBOUNDED, "ChannelInfo": ANY),
+        particle!(0, Some(1), "DictionaryFile": TEXT, "Dictionary": DICTIONARY),
+        particle!(0, Some(1), "DiagMessages": DIAG_MESSAGES),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+static DICTIONARY: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(0, Some(1), "DataTypes": DATA_TYPES),
+        particle!(1, Some(1), "Objects": OBJECTS),
+    ]),
+};
+
+static DATA_TYPES: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "DataType": ANY)]),
+};
+
+static OBJECTS: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "Object": OBJECT)]),
+};
+
+static OBJECT: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Index": INDEX),
+        particle!(1, UNBOUNDED, "Name": NAME),
+        particle!(0, UNBOUNDED, "Comment": NAME),
+        particle!(1, Some(1), "Type": TEXT),
+        particle!(1, Some(1), "BitSize": TEXT),
+        particle!(0, Some(1), "Info": ANY),
+        particle!(0, Some(1), "Flags": ANY),
+        particle!(0, Some(1), "Properties": ANY),
+    ]),
+};
+
+static DIAG_MESSAGES: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[particle!(0, UNBOUNDED, "DiagMessage": ANY)]),
+};
+
+static DC: TypeDecl = TypeDecl {
+    attributes: &["UnknownFRMW", "Unknown64Bit"],
+    content: Content::Sequence(&[
+        particle!(0, UNBOUNDED, "OpMode": OP_MODE),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+static OP_MODE: TypeDecl = TypeDecl {
+    attributes: &[],
+    content: Content::Sequence(&[
+        particle!(1, Some(1), "Name": TEXT),
+        particle!(0, UNBOUNDED, "Desc": NAME),
+        particle!(1, Some(1), "AssignActivate": TEXT),
+        particle!(0, Some(1), "CycleTimeSync0": ANY),
+        particle!(0, Some(1), "ShiftTimeSync0": ANY),
+        particle!(0, Some(1), "CycleTimeSync1": ANY),
+        particle!(0, Some(1), "ShiftTimeSync1": ANY),
+        particle!(0, UNBOUNDED, "Sm": ANY),
+        particle!(0, Some(1), "VendorSpecific": ANY),
+    ]),
+};
+
+fn check_element(e: &XmlElement, ty: &TypeDecl, path: &str, out: &mut Vec<SchemaViolation>) {
+    if let Content::Any = ty.content {
+        return;
+    }
+    for (name, _) in &e.attributes {
+        // Namespace related attributes like `xsi:noNamespaceSchemaLocation`
+        // are always allowed.
+        if name.contains(':') {
+            continue;
+        }
+        if !ty.attributes.contains(&name.as_str()) {
+            out.push(SchemaViolation {
+                path: path.to_string(),
+                message: format!("attribute `{}` is not allowed", name),
+            });
+        }
+    }
+    match ty.content {
+        Content::Any => {}
+        Content::Text => {
+            for (child, child_path) in child_paths(e, path) {
+                out.push(SchemaViolation {
+                    path: child_path,
+                    message: format!("element `{}` is not allowed here", child.name),
+                });
+            }
+        }
+        Content::Sequence(particles) => {
+            if e.nodes
+                .iter()
+                .any(|n| matches!(n, XmlNode::Text(t) if !t.trim().is_empty()))
+            {
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: "text content is not allowed here".to_string(),
+                });
+            }
+            check_sequence(e, particles, path, out);
+        }
+    }
+}
+
+fn check_sequence(
+    e: &XmlElement,
+    particles: &[Particle],
+    path: &str,
+    out: &mut Vec<SchemaViolation>,
+) {
+    let mut current = 0;
+    let mut count = 0;
+
+    let missing = |p: &Particle, out: &mut Vec<SchemaViolation>| {
+        out.push(SchemaViolation {
+            path: path.to_string(),
+            message: format!("missing {}", p.describe()),
+        });
+    };
+
+    for (child, child_path) in child_paths(e, path) {
+        let next = particles
+            .iter()
+            .enumerate()
+            .skip(current)
+            .find(|(i, p)| {
+                p.type_of(&child.name).is_some() && (*i != current || p.allows_more(count))
+            })
+            .map(|(i, _)| i);
+        match next {
+            Some(i) => {
+                if i != current {
+                    if count < particles[current].min {
+                        missing(&particles[current], out);
+                    }
+                    for p in &particles[current + 1..i] {
+                        if p.min > 0 {
+                            missing(p, out);
+                        }
+                    }
+                    current = i;
+                    count = 0;
+                }
+                count += 1;
+                let ty = particles[i].type_of(&child.name).unwrap_or(&ANY);
+                check_element(child, ty, &child_path, out);
+            }
+            None => {
+                let known = particles.iter().any(|p| p.type_of(&child.name).is_some());
+                let message = if known {
+                    format!(
+                        "element `{}` is out of order or repeated too often",
+                        child.name
+                    )
+                } else {
+                    format!("element `{}` is not allowed here", child.name)
+                };
+                out.push(SchemaViolation {
+                    path: child_path,
+                    message,
+                });
+            }
+        }
+    }
+
+    if let Some(p) = particles.get(current) {
+        if count < p.min {
+            missing(p, out);
+        }
+    }
+    for p in particles.iter().skip(current + 1) {
+        if p.min > 0 {
+            missing(p, out);
+        }
+    }
+}
+
+/// Child elements with their XPath like location.
+fn child_paths<'a>(e: &'a XmlElement, path: &str) -> Vec<(&'a XmlElement, String)> {
+    let mut totals = HashMap::new();
+    for c in e.children() {
+        *totals.entry(c.name.as_str()).or_insert(0) += 1;
+    }
+    let mut positions = HashMap::new();
+    e.children()
+        .map(|c| {
+            let pos = positions.entry(c.name.as_str()).or_insert(0);
+            *pos += 1;
+            let child_path = if totals[c.name.as_str()] > 1 {
+                format!("{}/{}[{}]", path, c.name, pos)
+            } else {
+                format!("{}/{}", path, c.name)
+            };
+            (c, child_path)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = r##"
+        <EtherCATInfo Version="1.11" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:noNamespaceSchemaLocation="EtherCATInfo.xsd">
+            <Vendor FileVersion="0099">
+                <Id>#x00000000</Id>
+                <Name>Vendor Foo</Name>
+            </Vendor>
+            <Descriptions>
+                <Groups>
+                    <Group SortOrder="0">
+                        <Type>Coupler</Type>
+                        <Name>Coupler</Name>
+                    </Group>
+                </Groups>
+                <Devices>
+                    <Device Physics="YY">
+                        <Type ProductCode="#x45" RevisionNo="#x001">Foo</Type>
+                        <Name>Bar</Name>
+                        <GroupType>Coupler</GroupType>
+                        <Sm StartAddress="#x1000" ControlByte="#x64">Outputs</Sm>
+                        <RxPdo Sm="0">
+                            <Index>#x1600</Index>
+                            <Name>Out</Name>
+                            <Entry>
+                                <Index>#x7000</Index>
+                                <SubIndex>1</SubIndex>
+                                <BitLen>1</BitLen>
+                                <DataType>BOOL</DataType>
+                            </Entry>
+                        </RxPdo>
+                    </Device>
+                </Devices>
+            </Descriptions>
+        </EtherCATInfo>"##;
+
+    #[test]
+    fn valid_document() {
+        assert_eq!(check_schema(VALID).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn unknown_element() {
+        let s = VALID.replace("<Name>Bar</Name>", "<Name>Bar</Name><Foo/>");
+        let violations = check_schema(&s).unwrap();
+        assert_eq!(
+            violations,
+            vec![SchemaViolation {
+                path: "/EtherCATInfo/Descriptions/Devices/Device/Foo".to_string(),
+                message: "element `Foo` is not allowed here".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_attribute() {
+        let s = VALID.replace("<Sm ", "<Sm Foo=\"1\" ");
+        let violations = check_schema(&s).unwrap();
+        assert_eq!(
+            violations,
+            vec![SchemaViolation {
+                path: "/EtherCATInfo/Descriptions/Devices/Device/Sm".to_string(),
+                message: "attribute `Foo` is not allowed".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn wrong_order() {
+        let s = VALID.replace(
+            "<Name>Bar</Name>\n                        <GroupType>Coupler</GroupType>",
+            "<GroupType>Coupler</GroupType><Name>Bar</Name>",
+        );
+        let violations = check_schema(&s).unwrap();
+        assert_eq!(
+            violations,
+            vec![
+                SchemaViolation {
+                    path: "/EtherCATInfo/Descriptions/Devices/Device".to_string(),
+                    message: "missing element `Name`".to_string(),
+                },
+                SchemaViolation {
+                    path: "/EtherCATInfo/Descriptions/Devices/Device/Name".to_string(),
+                    message: "element `Name` is out of order or repeated too often".to_string(),
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_element() {
+        let s = VALID.replace("<GroupType>Coupler</GroupType>", "");
+        let violations = check_schema(&s).unwrap();
+        assert_eq!(
+            violations,
+            vec![SchemaViolation {
+                path: "/EtherCATInfo/Descriptions/Devices/Device".to_string(),
+                message: "missing element `GroupType`".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn multiple_images() {
+        let s = VALID.replace(
+            "<Name>Vendor Foo</Name>",
+            "<Name>Vendor Foo</Name><Image16x14>a</Image16x14><ImageData16x14>7D</ImageData16x14>",
+        );
+        let violations = check_schema(&s).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "/EtherCATInfo/Vendor/ImageData16x14");
+    }
+
+    #[test]
+    fn check_mailbox_and_info() {
+        let s = VALID.replace(
+            "<GroupType>Coupler</GroupType>",
+            "<Info><Mailbox/><StateMachine/></Info>
+             <GroupType>Coupler</GroupType>",
+        );
+        let s = s.replace(
+            "</RxPdo>",
+            "</RxPdo>
+             <Mailbox DataLinkLayer=\"true\">
+                 <CoE Foo=\"1\">
+                     <InitCmd><Transition>PS</Transition><Data>00</Data></InitCmd>
+                 </CoE>
+             </Mailbox>",
+        );
+        let violations = check_schema(&s).unwrap();
+        let device = "/EtherCATInfo/Descriptions/Devices/Device";
+        assert_eq!(
+            violations,
+            vec![
+                SchemaViolation {
+                    path: format!("{}/Info/StateMachine", device),
+                    message: "element `StateMachine` is out of order or repeated too often"
+                        .to_string(),
+                },
+                SchemaViolation {
+                    path: format!("{}/Mailbox/CoE", device),
+                    message: "attribute `Foo` is not allowed".to_string(),
+                },
+                SchemaViolation {
+                    path: format!("{}/Mailbox/CoE/InitCmd", device),
+                    message: "missing element `Index`".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unexpected_root() {
+        let violations = check_schema("<EtherCATFoo/>").unwrap();
+        assert_eq!(violations[0].path, "/EtherCATFoo");
+    }
+}
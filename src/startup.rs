@@ -0,0 +1,461 @@
+//! CoE startup list of a slave.
+
+use crate::{codec::value_to_bytes, data_type::data_type_from_str, structs::*};
+use ethercat_types::{DataType, SdoIdx, Value};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Result},
+};
+
+/// SDO download that is sent to a slave in PREOP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StartupSdo {
+    pub idx: SdoIdx,
+    /// Value in little endian byte order.
+    pub data: Vec<u8>,
+    /// Purpose of the download (e.g. `PDO assignment of SM 2`).
+    pub comment: String,
+}
+
+impl Device {
+    /// SDO downloads to parameterize the device and configure the
+    /// given PDOs.
+    ///
+    /// The list contains
+    ///
+    /// 1. the `DefaultData` of the writable objects and sub items of the
+    ///    [profile](Device::profiles) dictionaries, replaced or followed by
+    ///    the `overrides` (each encoded according to the data type of its
+    ///    object),
+    /// 2. the mapping of the PDOs that are not fixed
+    ///    (`0x1600`..`0x1BFF`), if the device supports it (`PdoConfig`
+    ///    attribute of `Mailbox/CoE`), and
+    /// 3. the PDO assignment of the sync managers (`0x1C10` + SM), as in
+    ///    [`Device::process_image_layout`], if the device supports it
+    ///    (`PdoAssign` attribute of `Mailbox/CoE`).
+    ///
+    /// Defaults of the mapping and assignment objects are omitted.
+    pub fn startup_sdos(
+        &self,
+        pdos: &[PdoIdx],
+        overrides: &[(SdoIdx, Value)],
+    ) -> Result<Vec<StartupSdo>> {
+        let dictionaries: Vec<_> = self
+            .profiles
+            .iter()
+            .filter_map(|p| p.dictionary.as_ref())
+            .collect();
+
+        let mut sdos = vec![];
+        for d in &dictionaries {
+            sdos.extend(parameters(d)?);
+        }
+        for (idx, value) in overrides {
+            let dt = dictionaries
+                .iter()
+                .find_map(|d| data_type_of(d, *idx))
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::NotFound,
+                        format!("Object {} not found", sdo_name(*idx)),
+                    )
+                })?;
+            let data = value_to_bytes(dt, value)?;
+            match sdos.iter_mut().find(|s| s.idx == *idx) {
+                Some(sdo) => sdo.data = data,
+                None => sdos.push(StartupSdo {
+                    idx: *idx,
+                    data,
+                    comment: format!("Parameter {}", sdo_name(*idx)),
+                }),
+            }
+        }
+        sdos.extend(self.pdo_sdos(pdos)?);
+        Ok(sdos)
+    }
+
+    fn pdo_sdos(&self, pdos: &[PdoIdx]) -> Result<Vec<StartupSdo>> {
+        let layout = self.process_image_layout(pdos)?;
+        let mut sdos = vec![];
+        let configurable = self.coe_flag("PdoConfig");
+        for sm in layout.sms.iter().filter(|_| configurable) {
+            for pdo in &sm.pdos {
+                let pdo = self
+                    .rx_pdo
+                    .iter()
+                    .chain(self.tx_pdo.iter())
+                    .find(|p| p.idx == *pdo)
+                    .expect("PDO of the layout");
+                if pdo.fixed {
+                    continue;
+                }
+                let idx = u16::from(pdo.idx);
+                let comment = format!("PDO mapping 0x{:04X}", idx);
+                sdos.push(sdo(idx, 0, vec![0], &comment));
+                for (i, e) in pdo.entries.iter().enumerate() {
+                    let bit_len = u8::try_from(e.bit_len).map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Entry {} of PDO 0x{:04X} is too long", i + 1, idx),
+                        )
+                    })?;
+                    let value = u32::from(u16::from(e.entry_idx.idx)) << 16
+                        | u32::from(u8::from(e.entry_idx.sub_idx)) << 8
+                        | u32::from(bit_len);
+                    sdos.push(sdo(
+                        idx,
+                        count(i + 1)?,
+                        value.to_le_bytes().to_vec(),
+                        &comment,
+                    ));
+                }
+                sdos.push(sdo(idx, 0, vec![count(pdo.entries.len())?], &comment));
+            }
+        }
+        let assignable = self.coe_flag("PdoAssign");
+        for sm in layout.sms.iter().filter(|_| assignable) {
+            let idx = 0x1C10 + u16::from(u8::from(sm.sm));
+            let comment = format!("PDO assignment of SM {}", u8::from(sm.sm));
+            sdos.push(sdo(idx, 0, vec![0], &comment));
+            for (i, pdo) in sm.pdos.iter().enumerate() {
+                let data = u16::from(*pdo).to_le_bytes().to_vec();
+                sdos.push(sdo(idx, count(i + 1)?, data, &comment));
+            }
+            sdos.push(sdo(idx, 0, vec![count(sm.pdos.len())?], &comment));
+        }
+        Ok(sdos)
+    }
+
+    /// Boolean attribute of the `CoE` element of the mailbox
+    /// (e.g. `PdoAssign`), `false` if it is missing or invalid.
+    fn coe_flag(&self, name: &str) -> bool {
+        self.mailbox
+            .iter()
+            .flat_map(|m| &m.unknown)
+            .find(|e| e.name == "CoE")
+            .and_then(|e| e.attribute(name))
+            .is_some_and(|v| matches!(&*v.trim().to_lowercase(), "1" | "true"))
+    }
+}
+
+/// Objects that are configured by [`Device::startup_sdos`] itself.
+fn is_pdo_config(idx: u16) -> bool {
+    (0x1600..=0x1BFF).contains(&idx) || (0x1C10..=0x1C2F).contains(&idx)
+}
+
+fn is_writable(flags: Option<&ObjectFlags>) -> bool {
+    flags
+        .and_then(|f| f.access.as_deref())
+        .is_some_and(|a| a.contains('w'))
+}
+
+/// Defaults of the writable objects and sub items, in dictionary order.
+fn parameters(d: &Dictionary) -> Result<Vec<StartupSdo>> {
+    let mut sdos = vec![];
+    for o in &d.objects {
+        let idx = u16::from(o.idx);
+        let info = match &o.info {
+            Some(info) if !is_pdo_config(idx) => info,
+            _ => continue,
+        };
+        let name = o.name.default_text().unwrap_or_default();
+        let def = d.data_types.iter().find(|t| t.name == o.r#type);
+        match def.filter(|t| !t.sub_items.is_empty()) {
+            Some(def) => {
+                for item in &info.sub_items {
+                    let sub = match def.sub_items.iter().find(|s| s.name == item.name) {
+                        Some(sub) => sub,
+                        None => continue,
+                    };
+                    let flags = sub.flags.as_ref().or(o.flags.as_ref());
+                    let sub_idx = match sub.sub_idx.map(u8::from) {
+                        Some(sub_idx) if sub_idx > 0 => sub_idx,
+                        _ => continue,
+                    };
+                    if let (Some(data), true) = (&item.info.default_data, is_writable(flags)) {
+                        let comment = format!("{} - {}", name, item.name);
+                        sdos.push(sdo(idx, sub_idx, data.to_bytes()?, &comment));
+                    }
+                }
+            }
+            None => {
+                if let (Some(data), true) = (&info.default_data, is_writable(o.flags.as_ref())) {
+                    sdos.push(sdo(idx, 0, data.to_bytes()?, name));
+                }
+            }
+        }
+    }
+    Ok(sdos)
+}
+
+/// Data type of an object or sub item of the dictionary.
+fn data_type_of(d: &Dictionary, idx: SdoIdx) -> Option<DataType> {
+    let o = d.objects.iter().find(|o| o.idx == idx.idx)?;
+    let def = d.data_types.iter().find(|t| t.name == o.r#type);
+    let name = match def.filter(|t| !t.sub_items.is_empty()) {
+        Some(def) => {
+            let sub = def
+                .sub_items
+                .iter()
+                .find(|s| s.sub_idx == Some(idx.sub_idx))?;
+            resolve(d, &sub.r#type)
+        }
+        None => resolve(d, &o.r#type),
+    };
+    Some(data_type_from_str(name).unwrap_or(DataType::Raw))
+}
+
+/// Follow the base type of derived types (e.g. enumerations).
+fn resolve<'a>(d: &'a Dictionary, name: &'a str) -> &'a str {
+    let mut name = name;
+    for _ in 0..d.data_types.len() {
+        match d
+            .data_types
+            .iter()
+            .find(|t| t.name == name)
+            .and_then(|t| t.base_type.as_deref())
+        {
+            Some(base) => name = base,
+            None => break,
+        }
+    }
+    name
+}
+
+fn sdo(idx: u16, sub_idx: u8, data: Vec<u8>, comment: &str) -> StartupSdo {
+    StartupSdo {
+        idx: SdoIdx::new(idx, sub_idx),
+        data,
+        comment: comment.to_string(),
+    }
+}
+
+fn count(n: usize) -> Result<u8> {
+    u8::try_from(n).map_err(|_| Error::new(ErrorKind::InvalidData, "Too many entries"))
+}
+
+fn sdo_name(idx: SdoIdx) -> String {
+    format!("0x{:04X}:{:02X}", u16::from(idx.idx), u8::from(idx.sub_idx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{self, sm},
+        xml::XmlElement,
+    };
+
+    fn pdo(idx: u16, sm: u8, fixed: bool, entries: Vec<(u16, u8, usize)>) -> Pdo {
+        let entries = entries
+            .into_iter()
+            .map(|(idx, sub_idx, bit_len)| PdoEntry {
+                name: LocalizedText::from("Entry"),
+                ..fixtures::entry(idx, sub_idx, bit_len)
+            })
+            .collect();
+        Pdo {
+            fixed,
+            name: LocalizedText::from("PDO"),
+            ..fixtures::pdo(idx, sm, entries)
+        }
+    }
+
+    fn flags(access: &str) -> Option<ObjectFlags> {
+        Some(ObjectFlags {
+            access: Some(access.to_string()),
+            ..ObjectFlags::default()
+        })
+    }
+
+    fn object(idx: u16, r#type: &str, info: ObjectInfo, flags: Option<ObjectFlags>) -> Object {
+        Object {
+            idx: Idx::from(idx),
+            depend_on_slot: false,
+            name: LocalizedText::from("Object"),
+            r#type: r#type.to_string(),
+            bit_size: 16,
+            info: Some(info),
+            flags,
+        }
+    }
+
+    fn default_data(hex: &str) -> ObjectInfo {
+        ObjectInfo {
+            default_data: Some(HexBinary(hex.to_string())),
+            ..ObjectInfo::default()
+        }
+    }
+
+    fn sub_item(sub_idx: u8, name: &str, r#type: &str, access: &str) -> SubItemDef {
+        SubItemDef {
+            sub_idx: Some(SubIdx::from(sub_idx)),
+            name: name.to_string(),
+            r#type: r#type.to_string(),
+            bit_size: 16,
+            bit_offs: 16 * usize::from(sub_idx),
+            flags: flags(access),
+        }
+    }
+
+    fn dictionary() -> Dictionary {
+        let settings = DataTypeDef {
+            name: "DT8000".to_string(),
+            base_type: None,
+            bit_size: 48,
+            array_info: None,
+            sub_items: vec![
+                sub_item(0, "SubIndex 000", "USINT", "ro"),
+                sub_item(1, "Filter", "UINT", "rw"),
+                sub_item(2, "Version", "UINT", "ro"),
+            ],
+            enum_info: vec![],
+        };
+        let sub_info = |name: &str, hex: &str| SubItemInfo {
+            name: name.to_string(),
+            info: default_data(hex),
+        };
+        Dictionary {
+            data_types: vec![settings],
+            objects: vec![
+                object(0x1000, "UDINT", default_data("91010000"), flags("ro")),
+                object(
+                    0x8000,
+                    "DT8000",
+                    ObjectInfo {
+                        sub_items: vec![
+                            sub_info("SubIndex 000", "02"),
+                            sub_info("Filter", "0A00"),
+                            sub_info("Version", "0100"),
+                        ],
+                        ..ObjectInfo::default()
+                    },
+                    None,
+                ),
+                object(0xF800, "INT", default_data("FFFF"), flags("rw")),
+                object(0x1C12, "UINT", default_data("0000"), flags("rw")),
+            ],
+            unknown: vec![],
+        }
+    }
+
+    fn coe(attributes: &[(&str, &str)]) -> Option<Mailbox> {
+        let mut coe = XmlElement::new("CoE");
+        for (name, value) in attributes {
+            coe.attributes.push((name.to_string(), value.to_string()));
+        }
+        Some(Mailbox {
+            unknown: vec![coe],
+            ..Mailbox::default()
+        })
+    }
+
+    fn device() -> Device {
+        Device {
+            name: LocalizedText::from("Foo"),
+            desc: "Foo".to_string(),
+            sm: vec![
+                sm(0x1000, Some(0x26)),
+                sm(0x1400, Some(0x22)),
+                sm(0x1800, Some(0x64)),
+                sm(0x1C00, Some(0x20)),
+            ],
+            rx_pdo: vec![pdo(0x1600, 2, false, vec![(0x7000, 1, 16), (0, 0, 8)])],
+            tx_pdo: vec![pdo(0x1A00, 3, true, vec![(0x6000, 1, 32)])],
+            profiles: vec![Profile {
+                dictionary: Some(dictionary()),
+                ..Profile::default()
+            }],
+            mailbox: coe(&[("PdoAssign", "true"), ("PdoConfig", "1")]),
+            ..Device::default()
+        }
+    }
+
+    fn short(sdos: &[StartupSdo]) -> Vec<(u16, u8, Vec<u8>)> {
+        sdos.iter()
+            .map(|s| {
+                (
+                    u16::from(s.idx.idx),
+                    u8::from(s.idx.sub_idx),
+                    s.data.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn startup_list() {
+        let d = device();
+        let pdos = [PdoIdx::from(0x1600), PdoIdx::from(0x1A00)];
+        let sdos = d.startup_sdos(&pdos, &[]).unwrap();
+        assert_eq!(
+            short(&sdos),
+            vec![
+                (0x8000, 1, vec![0x0A, 0x00]),
+                (0xF800, 0, vec![0xFF, 0xFF]),
+                (0x1600, 0, vec![0]),
+                (0x1600, 1, vec![0x10, 0x01, 0x00, 0x70]),
+                (0x1600, 2, vec![0x08, 0x00, 0x00, 0x00]),
+                (0x1600, 0, vec![2]),
+                (0x1C12, 0, vec![0]),
+                (0x1C12, 1, vec![0x00, 0x16]),
+                (0x1C12, 0, vec![1]),
+                (0x1C13, 0, vec![0]),
+                (0x1C13, 1, vec![0x00, 0x1A]),
+                (0x1C13, 0, vec![1]),
+            ]
+        );
+        assert_eq!(sdos[0].comment, "Object - Filter");
+        assert_eq!(sdos[6].comment, "PDO assignment of SM 2");
+    }
+
+    #[test]
+    fn configure_pdos_only_if_supported() {
+        let pdos = [PdoIdx::from(0x1600), PdoIdx::from(0x1A00)];
+        let sdo_indices = |mailbox| {
+            let d = Device {
+                mailbox,
+                ..device()
+            };
+            let sdos = d.startup_sdos(&pdos, &[]).unwrap();
+            let mut indices: Vec<_> = sdos.iter().map(|s| u16::from(s.idx.idx)).collect();
+            indices.dedup();
+            indices
+        };
+        assert_eq!(
+            sdo_indices(coe(&[("PdoAssign", "TRUE")])),
+            vec![0x8000, 0xF800, 0x1C12, 0x1C13]
+        );
+        assert_eq!(
+            sdo_indices(coe(&[("PdoConfig", "true"), ("PdoAssign", "false")])),
+            vec![0x8000, 0xF800, 0x1600]
+        );
+        assert_eq!(sdo_indices(coe(&[])), vec![0x8000, 0xF800]);
+        assert_eq!(sdo_indices(None), vec![0x8000, 0xF800]);
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let d = device();
+        let overrides = [
+            (SdoIdx::new(0x8000, 1), Value::U16(100)),
+            (SdoIdx::new(0x1000, 0), Value::U32(1)),
+        ];
+        let sdos = d.startup_sdos(&[], &overrides).unwrap();
+        assert_eq!(
+            short(&sdos),
+            vec![
+                (0x8000, 1, vec![100, 0]),
+                (0xF800, 0, vec![0xFF, 0xFF]),
+                (0x1000, 0, vec![1, 0, 0, 0]),
+            ]
+        );
+
+        let wrong_type = [(SdoIdx::new(0xF800, 0), Value::U32(1))];
+        let err = d.startup_sdos(&[], &wrong_type).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let unknown = [(SdoIdx::new(0x2000, 0), Value::U8(1))];
+        let err = d.startup_sdos(&[], &unknown).unwrap_err();
+        assert_eq!(err.to_string(), "Object 0x2000:00 not found");
+    }
+}
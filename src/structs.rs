@@ -1,31 +1,60 @@
-pub use ethercat_types::{PdoEntryIdx, PdoIdx, SmIdx, SubIdx};
+pub use ethercat_types::{Idx, PdoEntryIdx, PdoIdx, SmIdx, SubIdx};
+
+pub use crate::localized_text::LocalizedText;
+
+use crate::xml::XmlElement;
+use std::time::Duration;
 
 /// EtherCAT Slave Information (ESI).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct EtherCatInfo {
     pub version: Option<String>,
     pub info_reference: Option<String>,
     pub vendor: Vendor,
     pub description: Description,
+    /// Other attributes of the root element
+    /// (e.g. `xsi:noNamespaceSchemaLocation`), kept verbatim.
+    pub attributes: Vec<(String, String)>,
+    /// Namespaces declared by the root element (e.g. `xsi`).
+    pub namespaces: Vec<(String, String)>,
+    /// Unrecognised child elements, kept verbatim.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Module description file (`EtherCATModule`).
+///
+/// Modular devices refer to such files with `InfoReference`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EtherCatModule {
+    pub version: Option<String>,
+    pub vendor: Vendor,
+    pub modules: Vec<Module>,
+    /// See [`EtherCatInfo::attributes`].
+    pub attributes: Vec<(String, String)>,
+    /// See [`EtherCatInfo::namespaces`].
+    pub namespaces: Vec<(String, String)>,
+    /// Unrecognised child elements, kept verbatim.
+    pub unknown: Vec<XmlElement>,
 }
 
 /// Vendor information.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Vendor {
     pub file_version: Option<u32>,
     pub id: u32,
-    pub name: Names,
+    pub name: LocalizedText,
     pub comment: Option<String>,
     pub url: Option<String>,
     pub desc_url: Option<String>,
     pub image: Option<Image>,
+    /// Unrecognised child elements (e.g. `VendorSpecific`) and further
+    /// occurrences of the interpreted ones (e.g. a second `Comment`),
+    /// kept verbatim.
+    pub unknown: Vec<XmlElement>,
 }
 
-/// A collection of human readable names, with attached language IDs.
-pub type Names = Vec<(String, Option<u16>)>;
-
 /// Further slave descriptions.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Description {
     pub groups: Vec<Group>,
     pub devices: Vec<Device>,
@@ -33,7 +62,7 @@ pub struct Description {
 }
 
 /// Image data (BMP file format).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Image {
     /// Obsolete
     Image16x14(String),
@@ -41,48 +70,180 @@ pub enum Image {
     ImageData16x14(HexBinary),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Group {
     pub sort_order: Option<i32>,
     pub parent_group: Option<String>,
     pub r#type: String,
-    pub name: Names,
+    pub name: LocalizedText,
     pub comment: Option<String>,
     pub image: Option<Image>,
-    // TODO: Optional 'VendorSpecific'
+    /// See [`Vendor::unknown`].
+    pub unknown: Vec<XmlElement>,
+    /// File the item was read from (see [`EtherCatInfo::set_source`]).
+    pub source: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Device {
-    pub physics: Option<String>,
-    pub name: Names,
+    /// Physical layer of the ports (`Physics`), see [`Device::ports`].
+    pub physics: Vec<PortType>,
+    pub name: LocalizedText,
     pub desc: String,
     pub product_code: Option<u32>,
     pub revision_no: Option<u32>,
+    /// Type of the group the device belongs to (see [`Description::group_tree`]).
+    pub group_type: Option<String>,
+    pub info: Option<DeviceInfo>,
+    /// Usage of the FMMUs, ordered by FMMU index (see [`Device::fmmu_config`]).
+    pub fmmu: Vec<FmmuUsage>,
     pub sm: Vec<Sm>,
     pub rx_pdo: Vec<Pdo>,
     pub tx_pdo: Vec<Pdo>,
+    pub mailbox: Option<Mailbox>,
+    pub image: Option<Image>,
+    pub profiles: Vec<Profile>,
+    /// Other attributes of the `Device` element, kept verbatim.
+    pub attributes: Vec<(String, String)>,
+    /// Other attributes of the `Type` element (e.g. `CheckRevisionNo`),
+    /// kept verbatim.
+    pub type_attributes: Vec<(String, String)>,
+    /// Unrecognised child elements (e.g. `VendorSpecific`), kept verbatim.
+    pub unknown: Vec<XmlElement>,
+    /// File the item was read from (see [`EtherCatInfo::set_source`]).
+    pub source: Option<String>,
+}
+
+/// Additional information about a device (`Info`).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceInfo {
+    /// Timeouts of the state transitions (`StateMachine/Timeout`).
+    pub state_machine_timeouts: Option<StateMachineTimeouts>,
+    /// Timeouts of the mailbox communication (`Mailbox/Timeout`).
+    pub mailbox_timeouts: Option<MailboxTimeouts>,
+    /// Register to read the identification value from
+    /// (`IdentificationAdo`, e.g. `0x1000`).
+    pub identification_ado: Option<u16>,
+    pub electrical: Option<Electrical>,
+    /// Descriptions of the ports, ordered by port number.
+    pub ports: Vec<Port>,
+    /// Unrecognised child elements (e.g. `EtherCATController`) and the
+    /// rest of `StateMachine` and `Mailbox` (e.g. `Behavior`), kept verbatim.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Electrical data of a device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Electrical {
+    /// Current consumed from the E-Bus in mA.
+    ///
+    /// Negative values are supplied to the E-Bus
+    /// (e.g. by couplers and power feed terminals),
+    /// see [`EBusBudget`](crate::EBusBudget).
+    pub ebus_current: i32,
+    /// Unrecognised child elements, kept verbatim.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Physical layer of a port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortType {
+    /// Ethernet (`Y` in `Physics`, `MII` in `Port/Type`).
+    Mii,
+    /// E-Bus (LVDS), the backplane of terminals
+    /// (`K` in `Physics`, `EBUS` in `Port/Type`).
+    Ebus,
+    /// Not connected (space in `Physics`, `NONE` in `Port/Type`).
+    Unused,
+    /// Other physical layers as given in the document.
+    Other(String),
+}
+
+/// Description of a port.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Port {
+    pub r#type: PortType,
+    /// Connector type (e.g. `RJ45`).
+    pub connection: Option<String>,
+    /// Label printed on the device (e.g. `X1 IN`).
+    pub label: Option<String>,
+    /// Unrecognised child elements, kept verbatim.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Timeouts of the EtherCAT state machine transitions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateMachineTimeouts {
+    /// INIT to PREOP.
+    pub preop: Duration,
+    /// PREOP to SAFEOP and SAFEOP to OP.
+    pub safeop_op: Duration,
+    /// Any state to INIT.
+    pub back_to_init: Duration,
+    /// OP to SAFEOP.
+    pub back_to_safeop: Duration,
+}
+
+/// Timeouts of the mailbox communication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MailboxTimeouts {
+    /// Time to write a request to the mailbox.
+    pub request: Duration,
+    /// Time to wait for the response of the device.
+    pub response: Duration,
+}
+
+/// Usage of a Fieldbus Memory Management Unit (FMMU).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FmmuUsage {
+    pub r#type: FmmuType,
+    /// Sync manager that is mapped by the FMMU.
+    pub sm: Option<SmIdx>,
+    /// The FMMU is only activated in OP.
+    pub op_only: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FmmuType {
+    Outputs,
+    Inputs,
+    /// Status of the input mailbox.
+    MBoxState,
+    /// The FMMU is not used.
+    Unused,
+    /// Other usages as given in the document.
+    Other(String),
 }
 
 /// Sync Manager (SM).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Sm {
     pub enable: bool,
     pub start_address: u16,
     pub control_byte: Option<u8>,
     pub default_size: Option<usize>,
     pub r#virtual: bool,
+    /// Usage given as text of the element (e.g. `MBoxOut` or `Outputs`).
+    pub r#type: Option<String>,
+    /// Other attributes (e.g. `MinSize`), kept verbatim.
+    pub attributes: Vec<(String, String)>,
 }
 
 /// Process Data Object (PDO).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Pdo {
     pub sm: Option<SmIdx>,
     pub fixed: bool,
     pub mandatory: bool,
     pub idx: PdoIdx,
-    pub name: Names,
+    /// The index is relative to the slot of the module (`DependOnSlot`).
+    pub depend_on_slot: bool,
+    pub name: LocalizedText,
     pub entries: Vec<PdoEntry>,
+    /// Other attributes (e.g. `OSMin`), kept verbatim.
+    pub attributes: Vec<(String, String)>,
+    /// Unrecognised child elements (e.g. `Exclude`), kept verbatim.
+    pub unknown: Vec<XmlElement>,
 }
 
 /// Service Data Object (SDO).
@@ -92,32 +253,228 @@ pub struct Sdo {
 }
 
 /// PDO Entry.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PdoEntry {
     pub entry_idx: PdoEntryIdx,
+    /// The index is relative to the slot of the module (`DependOnSlot`).
+    pub depend_on_slot: bool,
     pub bit_len: usize,
-    pub name: Names,
+    pub name: LocalizedText,
     pub data_type: Option<String>,
+    /// Unrecognised child elements (e.g. `Comment`), kept verbatim.
+    pub unknown: Vec<XmlElement>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Module {
     pub r#type: String,
-    pub name: Names,
+    pub name: LocalizedText,
     pub tx_pdo: Vec<Pdo>,
     pub rx_pdo: Vec<Pdo>,
     pub mailbox: Option<Mailbox>,
-    pub profile: Option<Profile>,
+    pub profiles: Vec<Profile>,
+    /// Other attributes of the `Module` element (e.g. `Crc32`), kept verbatim.
+    pub attributes: Vec<(String, String)>,
+    /// Other attributes of the `Type` element (e.g. `ModuleIdent`),
+    /// kept verbatim.
+    pub type_attributes: Vec<(String, String)>,
+    /// Unrecognised child elements (e.g. `VendorSpecific`), kept verbatim.
+    pub unknown: Vec<XmlElement>,
+    /// File the item was read from (see [`EtherCatInfo::set_source`]).
+    pub source: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// Mailbox of a device or module.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Mailbox {
-    // TODO
+    /// Attributes of the `Mailbox` element (e.g. `DataLinkLayer`), kept verbatim.
+    pub attributes: Vec<(String, String)>,
+    /// Commands of all protocols, in document order
+    /// (see [`Mailbox::init_cmds_for`]).
+    pub init_cmds: Vec<InitCmd>,
+    /// The protocol elements (e.g. `CoE` or `EoE`) without their
+    /// interpreted `InitCmd` elements, kept verbatim.
+    pub unknown: Vec<XmlElement>,
 }
 
-#[derive(Debug, Clone)]
+/// Command that is sent to a slave during a state transition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InitCmd {
+    pub transitions: Vec<Transition>,
+    pub kind: InitCmdKind,
+    pub data: Vec<u8>,
+    pub timeout: Option<Duration>,
+    pub comment: Option<String>,
+    /// Other attributes (e.g. `Fixed`), kept verbatim.
+    pub attributes: Vec<(String, String)>,
+    /// Unrecognised child elements of `CoE` and `SoE` commands, kept verbatim.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Target of an [`InitCmd`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InitCmdKind {
+    /// SDO download (`CoE`).
+    Sdo {
+        idx: Idx,
+        sub_idx: SubIdx,
+        complete_access: bool,
+        /// The index is relative to the slot of the module
+        /// (`DependOnSlot`).
+        depend_on_slot: bool,
+    },
+    /// IDN write (`SoE`).
+    Idn { idn: u16, drive_no: u8 },
+    /// Command of another protocol (e.g. `EoE` or `AoE`) with the text
+    /// of its specific child elements (e.g. `Type`), in document order.
+    Other {
+        protocol: String,
+        fields: Vec<(String, String)>,
+    },
+}
+
+/// Transition of the EtherCAT state machine.
+///
+/// See [`Transition::code`] for the abbreviations used by the ESI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transition {
+    InitToPreop,
+    PreopToSafeop,
+    PreopToInit,
+    SafeopToOp,
+    SafeopToPreop,
+    SafeopToInit,
+    OpToSafeop,
+    OpToPreop,
+    OpToInit,
+    InitToBoot,
+    BootToInit,
+    InitToInit,
+    PreopToPreop,
+    SafeopToSafeop,
+}
+
+/// Device profile (e.g. CiA 402) with its object dictionary.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Profile {
-    // TODO
+    pub profile_no: Option<u32>,
+    pub add_info: Option<u32>,
+    /// Object dictionary, either given inline or loaded from
+    /// [`Profile::dictionary_file`] (see [`EtherCatInfo::attach_dictionary`]).
+    pub dictionary: Option<Dictionary>,
+    /// Reference to an `EtherCATDict` document.
+    pub dictionary_file: Option<String>,
+    /// Texts of the diagnosis messages (see [`Device::diag_texts`]).
+    pub diag_messages: Vec<DiagMessage>,
+    /// Unrecognised child elements (e.g. `ChannelInfo`), kept verbatim.
+    /// In lenient mode invalid values and messages are kept here as well.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Text of a diagnosis message (ETG.1020).
+///
+/// The text may contain placeholders like `%d` for the parameters
+/// of the message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiagMessage {
+    pub text_id: u16,
+    pub text: LocalizedText,
+}
+
+/// Object dictionary (CoE).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dictionary {
+    pub data_types: Vec<DataTypeDef>,
+    pub objects: Vec<Object>,
+    /// Unrecognised child elements (e.g. `Enums`), kept verbatim.
+    /// In lenient mode invalid data types and objects are kept here
+    /// in `DataTypes` and `Objects` elements.
+    pub unknown: Vec<XmlElement>,
+}
+
+/// Definition of a data type of an object dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataTypeDef {
+    pub name: String,
+    pub base_type: Option<String>,
+    pub bit_size: usize,
+    /// Bounds of an array type.
+    pub array_info: Option<ArrayInfo>,
+    /// Members of a record type.
+    pub sub_items: Vec<SubItemDef>,
+    /// Values of an enumeration type.
+    pub enum_info: Vec<EnumValue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArrayInfo {
+    pub l_bound: u32,
+    pub elements: u32,
+}
+
+/// Member of a record data type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubItemDef {
+    /// Missing for the elements of an array member.
+    pub sub_idx: Option<SubIdx>,
+    pub name: String,
+    pub r#type: String,
+    pub bit_size: usize,
+    pub bit_offs: usize,
+    pub flags: Option<ObjectFlags>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumValue {
+    pub text: LocalizedText,
+    pub value: i64,
+}
+
+/// Object of an object dictionary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub idx: Idx,
+    /// The index is relative to the slot of the module (`DependOnSlot`).
+    pub depend_on_slot: bool,
+    pub name: LocalizedText,
+    /// Name of the data type (see [`Dictionary::data_types`]).
+    pub r#type: String,
+    pub bit_size: usize,
+    pub info: Option<ObjectInfo>,
+    pub flags: Option<ObjectFlags>,
+}
+
+/// Default and limit values of an object or sub item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectInfo {
+    pub default_data: Option<HexBinary>,
+    pub min_data: Option<HexBinary>,
+    pub max_data: Option<HexBinary>,
+    pub default_value: Option<String>,
+    pub min_value: Option<String>,
+    pub max_value: Option<String>,
+    /// Values of the sub items, in the order of the data type members.
+    pub sub_items: Vec<SubItemInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubItemInfo {
+    pub name: String,
+    pub info: ObjectInfo,
+}
+
+/// Access rights of an object or sub item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjectFlags {
+    /// `ro`, `rw` or `wo`.
+    pub access: Option<String>,
+    /// `m` (mandatory), `o` (optional) or `c` (conditional).
+    pub category: Option<String>,
+    /// `R`, `T`, `RT`, `TR` or empty.
+    pub pdo_mapping: Option<String>,
+    pub sdo_access: Option<String>,
+    /// Unrecognised child elements (e.g. `SafetyMapping`), kept verbatim.
+    pub unknown: Vec<XmlElement>,
 }
 
 /// HexBinary represents arbitrary hex-encoded binary data.
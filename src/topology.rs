@@ -0,0 +1,160 @@
+//! Physical topology of a line of devices.
+
+use crate::structs::*;
+use std::io::{Error, ErrorKind, Result};
+
+impl Device {
+    /// The ports of the device, ordered by port number.
+    ///
+    /// The port descriptions of `Info` are used if given, otherwise the
+    /// ports are derived from [`Device::physics`] (without labels).
+    pub fn ports(&self) -> Vec<Port> {
+        match &self.info {
+            Some(info) if !info.ports.is_empty() => info.ports.clone(),
+            _ => self
+                .physics
+                .iter()
+                .map(|t| Port {
+                    r#type: t.clone(),
+                    connection: None,
+                    label: None,
+                    unknown: vec![],
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Connection between two neighbouring devices of a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// Position of the upstream device.
+    pub from: usize,
+    /// Port of the upstream device.
+    pub from_port: usize,
+    /// Port of the downstream device (at position `from + 1`).
+    pub to_port: usize,
+    pub r#type: PortType,
+}
+
+/// Connect a line of devices, each one to its predecessor.
+///
+/// A device is connected with its first used port (usually port 0, the
+/// input) to the next free port of the predecessor with the same physical
+/// layer, e.g. an E-Bus terminal can only follow a device with an E-Bus
+/// port. Fails with [`ErrorKind::InvalidInput`] if two devices can not be
+/// connected or a device has no port description.
+pub fn connect_line(devices: &[&Device]) -> Result<Vec<Link>> {
+    let ports: Vec<_> = devices.iter().map(|d| d.ports()).collect();
+    for (d, p) in devices.iter().zip(&ports) {
+        if p.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{}: No port description", d.desc),
+            ));
+        }
+    }
+    let input = |ports: &[Port]| ports.iter().position(|p| p.r#type != PortType::Unused);
+
+    let mut links = vec![];
+    for from in 0..devices.len().saturating_sub(1) {
+        let to = from + 1;
+        let to_port = input(&ports[to]).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("{}: No used port", devices[to].desc),
+            )
+        })?;
+        let r#type = ports[to][to_port].r#type.clone();
+        let from_input = input(&ports[from]);
+        let from_port = (0..ports[from].len())
+            .find(|&i| Some(i) != from_input && ports[from][i].r#type == r#type)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "{} can not be connected to {}: no free {} port",
+                        devices[to].desc,
+                        devices[from].desc,
+                        type_name(&r#type)
+                    ),
+                )
+            })?;
+        links.push(Link {
+            from,
+            from_port,
+            to_port,
+            r#type,
+        });
+    }
+    Ok(links)
+}
+
+fn type_name(t: &PortType) -> &str {
+    match t {
+        PortType::Mii => "Ethernet",
+        PortType::Ebus => "E-Bus",
+        PortType::Unused => "unused",
+        PortType::Other(t) => t,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PortType::*;
+
+    fn device(name: &str, physics: Vec<PortType>) -> Device {
+        Device {
+            physics,
+            name: LocalizedText::from(name),
+            desc: name.to_string(),
+            ..Device::default()
+        }
+    }
+
+    #[test]
+    fn connect_coupler_and_terminals() {
+        let ek1100 = device("EK1100", vec![Mii, Ebus, Mii]);
+        let el1008 = device("EL1008", vec![Ebus, Ebus]);
+        let ek1110 = device("EK1110", vec![Ebus, Mii]);
+        let ek1100_2 = device("EK1100", vec![Mii, Ebus, Mii]);
+        let links = connect_line(&[&ek1100, &el1008, &ek1110, &ek1100_2]).unwrap();
+        let ports: Vec<_> = links
+            .iter()
+            .map(|l| (l.from, l.from_port, l.to_port))
+            .collect();
+        assert_eq!(ports, vec![(0, 1, 0), (1, 1, 0), (2, 1, 0)]);
+        assert_eq!(links[2].r#type, Mii);
+
+        let couplers = connect_line(&[&ek1100, &ek1100_2]).unwrap();
+        assert_eq!(couplers[0].from_port, 2);
+        assert!(connect_line(&[&ek1100]).unwrap().is_empty());
+        assert!(connect_line(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reject_unconnectable_devices() {
+        let rj45 = device("EL9800", vec![Mii, Mii]);
+        let el1008 = device("EL1008", vec![Ebus, Ebus]);
+        let err = connect_line(&[&rj45, &el1008]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(
+            err.to_string(),
+            "EL1008 can not be connected to EL9800: no free E-Bus port"
+        );
+
+        let end = device("EL9011", vec![Ebus]);
+        assert!(connect_line(&[&end, &el1008]).is_err());
+        let unknown = device("Foo", vec![]);
+        assert!(connect_line(&[&rj45, &unknown]).is_err());
+    }
+
+    #[test]
+    fn skip_unused_ports() {
+        let ek1110_0044 = device("EK1110-0044", vec![Ebus, Ebus, Unused, Mii]);
+        let ej1008 = device("EJ1008", vec![Unused, Ebus]);
+        let links = connect_line(&[&ek1110_0044, &ej1008]).unwrap();
+        assert_eq!((links[0].from_port, links[0].to_port), (1, 1));
+    }
+}
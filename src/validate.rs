@@ -0,0 +1,345 @@
+//! Semantic validation of ESI data.
+
+use crate::{data_type::bit_len_from_str, structs::*};
+use std::{collections::HashMap, fmt};
+
+/// Severity of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Validation rule that produced a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// A PDO is assigned to a sync manager that is not declared.
+    UnknownSm,
+    /// A PDO index is used more than once within a device.
+    DuplicatePdoIdx,
+    /// The same object entry is mapped more than once.
+    OverlappingPdoEntry,
+    /// The bit length of an entry does not match its data type.
+    BitLenMismatch,
+}
+
+impl Rule {
+    /// Stable rule ID.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Rule::UnknownSm => "ESI001",
+            Rule::DuplicatePdoIdx => "ESI002",
+            Rule::OverlappingPdoEntry => "ESI003",
+            Rule::BitLenMismatch => "ESI004",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// A single validation finding.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// Location of the finding (e.g. `devices[0]/rx_pdo[1]/entries[2]`).
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            self.severity, self.rule, self.path, self.message
+        )
+    }
+}
+
+/// Check the ESI data for semantic errors.
+pub fn validate(info: &EtherCatInfo) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for (i, d) in info.description.devices.iter().enumerate() {
+        let path = format!("devices[{}]", i);
+        validate_pdos(&path, &d.rx_pdo, &d.tx_pdo, Some(&d.sm), &mut diagnostics);
+    }
+    for (i, m) in info.description.modules.iter().enumerate() {
+        let path = format!("modules[{}]", i);
+        validate_pdos(&path, &m.rx_pdo, &m.tx_pdo, None, &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn validate_pdos(
+    path: &str,
+    rx_pdo: &[Pdo],
+    tx_pdo: &[Pdo],
+    sm: Option<&[Sm]>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut pdo_indices = HashMap::new();
+    let pdos = rx_pdo
+        .iter()
+        .enumerate()
+        .map(|(i, pdo)| (format!("{}/rx_pdo[{}]", path, i), pdo))
+        .chain(
+            tx_pdo
+                .iter()
+                .enumerate()
+                .map(|(i, pdo)| (format!("{}/tx_pdo[{}]", path, i), pdo)),
+        );
+
+    // Maps each object entry to the first PDO that contains it.
+    let mut mapped_entries = HashMap::new();
+
+    for (pdo_path, pdo) in pdos {
+        let pdo_idx = u16::from(pdo.idx);
+
+        if let (Some(sms), Some(sm_idx)) = (sm, pdo.sm) {
+            if usize::from(u8::from(sm_idx)) >= sms.len() {
+                diagnostics.push(Diagnostic {
+                    rule: Rule::UnknownSm,
+                    severity: Severity::Error,
+                    path: pdo_path.clone(),
+                    message: format!(
+                        "PDO 0x{:04X} is assigned to SM {} but only {} SMs are declared",
+                        pdo_idx,
+                        u8::from(sm_idx),
+                        sms.len()
+                    ),
+                });
+            }
+        }
+
+        let first = pdo_indices
+            .entry(pdo_idx)
+            .or_insert_with(|| pdo_path.clone());
+        if *first != pdo_path {
+            diagnostics.push(Diagnostic {
+                rule: Rule::DuplicatePdoIdx,
+                severity: Severity::Error,
+                path: pdo_path.clone(),
+                message: format!("PDO index 0x{:04X} is already used by {}", pdo_idx, first),
+            });
+        }
+
+        let mut entries_of_pdo = HashMap::new();
+
+        for (i, e) in pdo.entries.iter().enumerate() {
+            let entry_path = format!("{}/entries[{}]", pdo_path, i);
+            let idx = u16::from(e.entry_idx.idx);
+            let sub_idx = u8::from(e.entry_idx.sub_idx);
+
+            if let Some(expected) = e.data_type.as_deref().and_then(bit_len_from_str) {
+                if expected != e.bit_len {
+                    diagnostics.push(Diagnostic {
+                        rule: Rule::BitLenMismatch,
+                        severity: Severity::Error,
+                        path: entry_path.clone(),
+                        message: format!(
+                            "entry 0x{:04X}:{:02X} has a bit length of {} but {} requires {}",
+                            idx,
+                            sub_idx,
+                            e.bit_len,
+                            e.data_type.as_deref().unwrap_or_default(),
+                            expected
+                        ),
+                    });
+                }
+            }
+
+            // Gaps (index 0) may appear any number of times.
+            if idx == 0 {
+                continue;
+            }
+
+            if let Some(first) = entries_of_pdo.insert((idx, sub_idx), entry_path.clone()) {
+                diagnostics.push(Diagnostic {
+                    rule: Rule::OverlappingPdoEntry,
+                    severity: Severity::Error,
+                    path: entry_path,
+                    message: format!(
+                        "entry 0x{:04X}:{:02X} is already mapped by {}",
+                        idx, sub_idx, first
+                    ),
+                });
+                continue;
+            }
+
+            // Alternative PDOs may legitimately map the same object,
+            // so this is only reported as a warning.
+            match mapped_entries.get(&(idx, sub_idx)) {
+                Some((other_pdo, _)) if *other_pdo != pdo_idx => {
+                    diagnostics.push(Diagnostic {
+                        rule: Rule::OverlappingPdoEntry,
+                        severity: Severity::Warning,
+                        path: entry_path,
+                        message: format!(
+                            "entry 0x{:04X}:{:02X} is also mapped by PDO 0x{:04X}",
+                            idx, sub_idx, other_pdo
+                        ),
+                    });
+                }
+                Some(_) => {}
+                None => {
+                    mapped_entries.insert((idx, sub_idx), (pdo_idx, entry_path));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, sm};
+
+    fn entry(idx: u16, sub_idx: u8, bit_len: usize, data_type: &str) -> PdoEntry {
+        PdoEntry {
+            data_type: Some(data_type.to_string()),
+            ..fixtures::entry(idx, sub_idx, bit_len)
+        }
+    }
+
+    fn pdo(idx: u16, sm: u8, entries: Vec<PdoEntry>) -> Pdo {
+        Pdo {
+            mandatory: true,
+            ..fixtures::pdo(idx, sm, entries)
+        }
+    }
+
+    fn info(device: Device) -> EtherCatInfo {
+        EtherCatInfo {
+            description: Description {
+                devices: vec![device],
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    fn device(rx_pdo: Vec<Pdo>, tx_pdo: Vec<Pdo>) -> Device {
+        Device {
+            desc: "Foo".to_string(),
+            sm: vec![
+                sm(0x1000, None),
+                sm(0x1400, None),
+                sm(0x1800, None),
+                sm(0x1C00, None),
+            ],
+            rx_pdo,
+            tx_pdo,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn valid_device() {
+        let d = device(
+            vec![pdo(
+                0x1600,
+                2,
+                vec![
+                    entry(0x7000, 1, 1, "BOOL"),
+                    entry(0, 0, 7, ""),
+                    entry(0, 0, 8, ""),
+                ],
+            )],
+            vec![pdo(0x1A00, 3, vec![entry(0x6000, 1, 16, "INT")])],
+        );
+        assert!(validate(&info(d)).is_empty());
+    }
+
+    #[test]
+    fn unknown_sm() {
+        let d = device(vec![pdo(0x1600, 4, vec![])], vec![]);
+        let diagnostics = validate(&info(d));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, Rule::UnknownSm);
+        assert_eq!(diagnostics[0].path, "devices[0]/rx_pdo[0]");
+    }
+
+    #[test]
+    fn duplicate_pdo_idx() {
+        let d = device(vec![pdo(0x1600, 2, vec![])], vec![pdo(0x1600, 3, vec![])]);
+        let diagnostics = validate(&info(d));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, Rule::DuplicatePdoIdx);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].path, "devices[0]/tx_pdo[0]");
+
+        let d = device(
+            vec![pdo(0x1600, 2, vec![])],
+            vec![pdo(0x1600, 3, vec![]), pdo(0x1600, 3, vec![])],
+        );
+        let diagnostics = validate(&info(d));
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.message.ends_with("already used by devices[0]/rx_pdo[0]")));
+    }
+
+    #[test]
+    fn overlapping_entries() {
+        let d = device(
+            vec![
+                pdo(
+                    0x1600,
+                    2,
+                    vec![entry(0x7000, 1, 8, "USINT"), entry(0x7000, 1, 8, "USINT")],
+                ),
+                pdo(0x1601, 2, vec![entry(0x7000, 1, 8, "USINT")]),
+            ],
+            vec![],
+        );
+        let diagnostics = validate(&info(d));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].rule, Rule::OverlappingPdoEntry);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].path, "devices[0]/rx_pdo[0]/entries[1]");
+        assert_eq!(diagnostics[1].rule, Rule::OverlappingPdoEntry);
+        assert_eq!(diagnostics[1].severity, Severity::Warning);
+        assert_eq!(diagnostics[1].path, "devices[0]/rx_pdo[1]/entries[0]");
+    }
+
+    #[test]
+    fn bit_len_mismatch() {
+        let d = device(
+            vec![],
+            vec![pdo(
+                0x1A00,
+                3,
+                vec![entry(0x6000, 1, 8, "INT"), entry(0x6000, 2, 8, "DT0800")],
+            )],
+        );
+        let diagnostics = validate(&info(d));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].rule, Rule::BitLenMismatch);
+        assert_eq!(diagnostics[0].path, "devices[0]/tx_pdo[0]/entries[0]");
+    }
+
+    #[test]
+    fn display_diagnostic() {
+        let d = Diagnostic {
+            rule: Rule::UnknownSm,
+            severity: Severity::Error,
+            path: "devices[0]/rx_pdo[0]".to_string(),
+            message: "foo".to_string(),
+        };
+        assert_eq!(d.to_string(), "error[ESI001] devices[0]/rx_pdo[0]: foo");
+    }
+}
@@ -0,0 +1,840 @@
+//! Serialization of the data model to XML.
+
+use crate::{
+    schema::sort_tree,
+    structs::*,
+    xml::{XmlElement, XmlNode},
+};
+use std::time::Duration;
+
+impl EtherCatInfo {
+    /// Write the description as an `EtherCATInfo` document.
+    ///
+    /// Unrecognised elements and attributes are written back verbatim,
+    /// in the order of `EtherCATInfo.xsd`.
+    pub fn to_xml_string(&self) -> String {
+        let mut root = XmlElement::new("EtherCATInfo");
+        root.namespaces = self.namespaces.clone();
+        attr(&mut root, "Version", self.version.as_deref());
+        root.attributes.extend(self.attributes.iter().cloned());
+        if let Some(r) = &self.info_reference {
+            push_text(&mut root, "InfoReference", r);
+        }
+        push(&mut root, vendor(&self.vendor));
+
+        let mut descriptions = XmlElement::new("Descriptions");
+        let mut groups = XmlElement::new("Groups");
+        for g in &self.description.groups {
+            push(&mut groups, group(g));
+        }
+        push(&mut descriptions, groups);
+        let mut devices = XmlElement::new("Devices");
+        for d in &self.description.devices {
+            push(&mut devices, device(d));
+        }
+        push(&mut descriptions, devices);
+        if !self.description.modules.is_empty() {
+            push(&mut descriptions, modules(&self.description.modules));
+        }
+        push(&mut root, descriptions);
+        push_all(&mut root, &self.unknown);
+
+        sort_tree(&mut root);
+        root.to_document_string()
+    }
+}
+
+impl EtherCatModule {
+    /// Write the modules as an `EtherCATModule` document.
+    ///
+    /// See [`EtherCatInfo::to_xml_string`].
+    pub fn to_xml_string(&self) -> String {
+        let mut root = XmlElement::new("EtherCATModule");
+        root.namespaces = self.namespaces.clone();
+        attr(&mut root, "Version", self.version.as_deref());
+        root.attributes.extend(self.attributes.iter().cloned());
+        push(&mut root, vendor(&self.vendor));
+        push(&mut root, modules(&self.modules));
+        push_all(&mut root, &self.unknown);
+
+        sort_tree(&mut root);
+        root.to_document_string()
+    }
+}
+
+fn vendor(v: &Vendor) -> XmlElement {
+    let mut e = XmlElement::new("Vendor");
+    attr(&mut e, "FileVersion", v.file_version.map(|x| x.to_string()));
+    push_text(&mut e, "Id", hex(u64::from(v.id), 8));
+    push_names(&mut e, "Name", &v.name);
+    if let Some(c) = &v.comment {
+        push_text(&mut e, "Comment", c);
+    }
+    if let Some(url) = &v.url {
+        push_text(&mut e, "URL", url);
+    }
+    if let Some(url) = &v.desc_url {
+        push_text(&mut e, "DescriptionURL", url);
+    }
+    push_image(&mut e, v.image.as_ref());
+    push_all(&mut e, &v.unknown);
+    e
+}
+
+fn group(g: &Group) -> XmlElement {
+    let mut e = XmlElement::new("Group");
+    attr(&mut e, "SortOrder", g.sort_order.map(|x| x.to_string()));
+    attr(&mut e, "ParentGroup", g.parent_group.as_deref());
+    push_text(&mut e, "Type", &g.r#type);
+    push_names(&mut e, "Name", &g.name);
+    if let Some(c) = &g.comment {
+        push_text(&mut e, "Comment", c);
+    }
+    push_image(&mut e, g.image.as_ref());
+    push_all(&mut e, &g.unknown);
+    e
+}
+
+fn device(d: &Device) -> XmlElement {
+    let mut e = XmlElement::new("Device");
+    if !d.physics.is_empty() {
+        let physics: String = d
+            .physics
+            .iter()
+            .map(|p| match p {
+                PortType::Mii => "Y",
+                PortType::Ebus => "K",
+                PortType::Unused => " ",
+                PortType::Other(other) => other.as_str(),
+            })
+            .collect();
+        attr(&mut e, "Physics", Some(physics));
+    }
+    e.attributes.extend(d.attributes.iter().cloned());
+    let mut t = XmlElement::with_text("Type", d.desc.as_str());
+    attr(
+        &mut t,
+        "ProductCode",
+        d.product_code.map(|x| hex(u64::from(x), 8)),
+    );
+    attr(
+        &mut t,
+        "RevisionNo",
+        d.revision_no.map(|x| hex(u64::from(x), 8)),
+    );
+    t.attributes.extend(d.type_attributes.iter().cloned());
+    push(&mut e, t);
+    push_names(&mut e, "Name", &d.name);
+    if let Some(info) = &d.info {
+        push(&mut e, device_info(info));
+    }
+    if let Some(t) = &d.group_type {
+        push_text(&mut e, "GroupType", t);
+    }
+    for p in &d.profiles {
+        push(&mut e, profile(p));
+    }
+    for f in &d.fmmu {
+        push(&mut e, fmmu(f));
+    }
+    for sm in &d.sm {
+        push(&mut e, sync_manager(sm));
+    }
+    for pdo in &d.rx_pdo {
+        push(&mut e, process_data_object("RxPdo", pdo));
+    }
+    for pdo in &d.tx_pdo {
+        push(&mut e, process_data_object("TxPdo", pdo));
+    }
+    if let Some(m) = &d.mailbox {
+        push(&mut e, mailbox(m));
+    }
+    push_image(&mut e, d.image.as_ref());
+    push_all(&mut e, &d.unknown);
+    e
+}
+
+fn device_info(info: &DeviceInfo) -> XmlElement {
+    let mut e = XmlElement::new("Info");
+    // The rest of `StateMachine` and `Mailbox` (e.g. `Behavior`) is unknown.
+    let mut unknown = info.unknown.clone();
+    if let Some(t) = &info.state_machine_timeouts {
+        let mut timeout = XmlElement::new("Timeout");
+        push_text(&mut timeout, "PreopTimeout", millis(t.preop));
+        push_text(&mut timeout, "SafeopOpTimeout", millis(t.safeop_op));
+        push_text(&mut timeout, "BackToInitTimeout", millis(t.back_to_init));
+        push_text(
+            &mut timeout,
+            "BackToSafeopTimeout",
+            millis(t.back_to_safeop),
+        );
+        push(element(&mut unknown, "StateMachine"), timeout);
+    }
+    if let Some(t) = &info.mailbox_timeouts {
+        let mut timeout = XmlElement::new("Timeout");
+        push_text(&mut timeout, "RequestTimeout", millis(t.request));
+        push_text(&mut timeout, "ResponseTimeout", millis(t.response));
+        push(element(&mut unknown, "Mailbox"), timeout);
+    }
+    if let Some(ado) = info.identification_ado {
+        push_text(&mut e, "IdentificationAdo", hex(u64::from(ado), 4));
+    }
+    if let Some(electrical) = &info.electrical {
+        let mut el = XmlElement::new("Electrical");
+        push_text(&mut el, "EBusCurrent", electrical.ebus_current.to_string());
+        push_all(&mut el, &electrical.unknown);
+        push(&mut e, el);
+    }
+    for port in &info.ports {
+        let mut p = XmlElement::new("Port");
+        let r#type = match &port.r#type {
+            PortType::Mii => "MII",
+            PortType::Ebus => "EBUS",
+            PortType::Unused => "NONE",
+            PortType::Other(other) => other.as_str(),
+        };
+        push_text(&mut p, "Type", r#type);
+        if let Some(c) = &port.connection {
+            push_text(&mut p, "Connection", c);
+        }
+        if let Some(l) = &port.label {
+            push_text(&mut p, "Label", l);
+        }
+        push_all(&mut p, &port.unknown);
+        push(&mut e, p);
+    }
+    push_all(&mut e, &unknown);
+    e
+}
+
+fn fmmu(f: &FmmuUsage) -> XmlElement {
+    let mut e = XmlElement::new("Fmmu");
+    match &f.r#type {
+        FmmuType::Outputs => e.nodes.push(XmlNode::Text("Outputs".to_string())),
+        FmmuType::Inputs => e.nodes.push(XmlNode::Text("Inputs".to_string())),
+        FmmuType::MBoxState => e.nodes.push(XmlNode::Text("MBoxState".to_string())),
+        FmmuType::Unused => {}
+        FmmuType::Other(other) => e.nodes.push(XmlNode::Text(other.clone())),
+    }
+    attr(&mut e, "Sm", f.sm.map(|sm| u8::from(sm).to_string()));
+    if f.op_only {
+        attr(&mut e, "OpOnly", Some("1"));
+    }
+    e
+}
+
+fn sync_manager(sm: &Sm) -> XmlElement {
+    let mut e = match &sm.r#type {
+        Some(t) => XmlElement::with_text("Sm", t.as_str()),
+        None => XmlElement::new("Sm"),
+    };
+    attr(
+        &mut e,
+        "DefaultSize",
+        sm.default_size.map(|x| x.to_string()),
+    );
+    attr(
+        &mut e,
+        "StartAddress",
+        Some(hex(u64::from(sm.start_address), 4)),
+    );
+    attr(
+        &mut e,
+        "ControlByte",
+        sm.control_byte.map(|x| hex(u64::from(x), 2)),
+    );
+    attr(&mut e, "Enable", Some(if sm.enable { "1" } else { "0" }));
+    if sm.r#virtual {
+        attr(&mut e, "Virtual", Some("1"));
+    }
+    e.attributes.extend(sm.attributes.iter().cloned());
+    e
+}
+
+fn process_data_object(name: &str, pdo: &Pdo) -> XmlElement {
+    let mut e = XmlElement::new(name);
+    if pdo.fixed {
+        attr(&mut e, "Fixed", Some("1"));
+    }
+    if pdo.mandatory {
+        attr(&mut e, "Mandatory", Some("1"));
+    }
+    attr(&mut e, "Sm", pdo.sm.map(|sm| u8::from(sm).to_string()));
+    e.attributes.extend(pdo.attributes.iter().cloned());
+    push(&mut e, index(u16::from(pdo.idx), pdo.depend_on_slot));
+    push_names(&mut e, "Name", &pdo.name);
+    for entry in &pdo.entries {
+        let mut en = XmlElement::new("Entry");
+        let idx = u16::from(entry.entry_idx.idx);
+        push(&mut en, index(idx, entry.depend_on_slot));
+        // Also written for gaps, as some documents give it there.
+        let sub_idx = u8::from(entry.entry_idx.sub_idx);
+        push_text(&mut en, "SubIndex", sub_idx.to_string());
+        push_text(&mut en, "BitLen", entry.bit_len.to_string());
+        push_names(&mut en, "Name", &entry.name);
+        if let Some(t) = &entry.data_type {
+            push_text(&mut en, "DataType", t);
+        }
+        push_all(&mut en, &entry.unknown);
+        push(&mut e, en);
+    }
+    push_all(&mut e, &pdo.unknown);
+    e
+}
+
+fn mailbox(m: &Mailbox) -> XmlElement {
+    let mut e = XmlElement::new("Mailbox");
+    e.attributes = m.attributes.clone();
+    let mut protocols = m.unknown.clone();
+    for cmd in &m.init_cmds {
+        let protocol = match &cmd.kind {
+            InitCmdKind::Sdo { .. } => "CoE",
+            InitCmdKind::Idn { .. } => "SoE",
+            InitCmdKind::Other { protocol, .. } => protocol,
+        };
+        push(element(&mut protocols, protocol), init_cmd(cmd));
+    }
+    push_all(&mut e, &protocols);
+    e
+}
+
+fn init_cmd(cmd: &InitCmd) -> XmlElement {
+    let mut e = XmlElement::new("InitCmd");
+    for t in &cmd.transitions {
+        push_text(&mut e, "Transition", t.code());
+    }
+    match &cmd.kind {
+        &InitCmdKind::Sdo {
+            idx,
+            sub_idx,
+            complete_access,
+            depend_on_slot,
+        } => {
+            if complete_access {
+                attr(&mut e, "CompleteAccess", Some("1"));
+            }
+            push(&mut e, index(u16::from(idx), depend_on_slot));
+            push_text(&mut e, "SubIndex", u8::from(sub_idx).to_string());
+        }
+        &InitCmdKind::Idn { idn, drive_no } => {
+            if drive_no != 0 {
+                attr(&mut e, "Chn", Some(drive_no.to_string()));
+            }
+            push_text(&mut e, "IDN", idn.to_string());
+        }
+        InitCmdKind::Other { fields, .. } => {
+            for (name, value) in fields {
+                push_text(&mut e, name, value);
+            }
+        }
+    }
+    push_text(&mut e, "Data", hex_binary(&cmd.data));
+    if let Some(t) = cmd.timeout {
+        push_text(&mut e, "Timeout", millis(t));
+    }
+    if let Some(c) = &cmd.comment {
+        push_text(&mut e, "Comment", c);
+    }
+    e.attributes.extend(cmd.attributes.iter().cloned());
+    push_all(&mut e, &cmd.unknown);
+    e
+}
+
+fn profile(p: &Profile) -> XmlElement {
+    let mut e = XmlElement::new("Profile");
+    if let Some(no) = p.profile_no {
+        push_text(&mut e, "ProfileNo", no.to_string());
+    }
+    if let Some(add_info) = p.add_info {
+        push_text(&mut e, "AddInfo", add_info.to_string());
+    }
+    // A dictionary that was loaded from the file is not written inline.
+    if let Some(file) = &p.dictionary_file {
+        push_text(&mut e, "DictionaryFile", file);
+    } else if let Some(dict) = &p.dictionary {
+        push(&mut e, dictionary(dict));
+    }
+    // Messages that were tolerated are kept in an unknown `DiagMessages`.
+    let mut unknown = p.unknown.clone();
+    if !p.diag_messages.is_empty() {
+        let messages = element(&mut unknown, "DiagMessages");
+        for m in &p.diag_messages {
+            let mut msg = XmlElement::new("DiagMessage");
+            push_text(&mut msg, "TextId", hex(u64::from(m.text_id), 4));
+            push_names(&mut msg, "MessageText", &m.text);
+            push(messages, msg);
+        }
+    }
+    push_all(&mut e, &unknown);
+    e
+}
+
+fn dictionary(d: &Dictionary) -> XmlElement {
+    let mut e = XmlElement::new("Dictionary");
+    // Items that were tolerated are kept in unknown `DataTypes` and `Objects`.
+    let mut unknown = d.unknown.clone();
+    let data_types = element(&mut unknown, "DataTypes");
+    for dt in &d.data_types {
+        push(data_types, data_type(dt));
+    }
+    let objects = element(&mut unknown, "Objects");
+    for o in &d.objects {
+        let mut obj = XmlElement::new("Object");
+        push(&mut obj, index(u16::from(o.idx), o.depend_on_slot));
+        push_names(&mut obj, "Name", &o.name);
+        push_text(&mut obj, "Type", &o.r#type);
+        push_text(&mut obj, "BitSize", o.bit_size.to_string());
+        if let Some(info) = &o.info {
+            push(&mut obj, object_info(info));
+        }
+        if let Some(flags) = &o.flags {
+            push(&mut obj, object_flags(flags));
+        }
+        push(objects, obj);
+    }
+    push_all(&mut e, &unknown);
+    e
+}
+
+fn data_type(dt: &DataTypeDef) -> XmlElement {
+    let mut e = XmlElement::new("DataType");
+    push_text(&mut e, "Name", &dt.name);
+    if let Some(t) = &dt.base_type {
+        push_text(&mut e, "BaseType", t);
+    }
+    push_text(&mut e, "BitSize", dt.bit_size.to_string());
+    if let Some(a) = &dt.array_info {
+        let mut info = XmlElement::new("ArrayInfo");
+        push_text(&mut info, "LBound", a.l_bound.to_string());
+        push_text(&mut info, "Elements", a.elements.to_string());
+        push(&mut e, info);
+    }
+    for item in &dt.sub_items {
+        let mut s = XmlElement::new("SubItem");
+        if let Some(sub_idx) = item.sub_idx {
+            push_text(&mut s, "SubIdx", u8::from(sub_idx).to_string());
+        }
+        push_text(&mut s, "Name", &item.name);
+        push_text(&mut s, "Type", &item.r#type);
+        push_text(&mut s, "BitSize", item.bit_size.to_string());
+        push_text(&mut s, "BitOffs", item.bit_offs.to_string());
+        if let Some(flags) = &item.flags {
+            push(&mut s, object_flags(flags));
+        }
+        push(&mut e, s);
+    }
+    for v in &dt.enum_info {
+        let mut info = XmlElement::new("EnumInfo");
+        push_names(&mut info, "Text", &v.text);
+        push_text(&mut info, "Enum", v.value.to_string());
+        push(&mut e, info);
+    }
+    e
+}
+
+fn object_info(info: &ObjectInfo) -> XmlElement {
+    let mut e = XmlElement::new("Info");
+    let data = [
+        ("MinData", &info.min_data),
+        ("MaxData", &info.max_data),
+        ("DefaultData", &info.default_data),
+    ];
+    for (name, value) in data.iter() {
+        if let Some(HexBinary(v)) = value {
+            push_text(&mut e, name, v);
+        }
+    }
+    let values = [
+        ("MinValue", &info.min_value),
+        ("MaxValue", &info.max_value),
+        ("DefaultValue", &info.default_value),
+    ];
+    for (name, value) in values.iter() {
+        if let Some(v) = value {
+            push_text(&mut e, name, v);
+        }
+    }
+    for item in &info.sub_items {
+        let mut s = XmlElement::new("SubItem");
+        push_text(&mut s, "Name", &item.name);
+        push(&mut s, object_info(&item.info));
+        push(&mut e, s);
+    }
+    e
+}
+
+fn object_flags(flags: &ObjectFlags) -> XmlElement {
+    let mut e = XmlElement::new("Flags");
+    let values = [
+        ("Access", &flags.access),
+        ("Category", &flags.category),
+        ("PdoMapping", &flags.pdo_mapping),
+        ("SdoAccess", &flags.sdo_access),
+    ];
+    for (name, value) in values.iter() {
+        if let Some(v) = value {
+            push_text(&mut e, name, v);
+        }
+    }
+    push_all(&mut e, &flags.unknown);
+    e
+}
+
+fn modules(modules: &[Module]) -> XmlElement {
+    let mut e = XmlElement::new("Modules");
+    for m in modules {
+        let mut module = XmlElement::new("Module");
+        module.attributes = m.attributes.clone();
+        let mut t = XmlElement::with_text("Type", m.r#type.as_str());
+        t.attributes = m.type_attributes.clone();
+        push(&mut module, t);
+        push_names(&mut module, "Name", &m.name);
+        for pdo in &m.rx_pdo {
+            push(&mut module, process_data_object("RxPdo", pdo));
+        }
+        for pdo in &m.tx_pdo {
+            push(&mut module, process_data_object("TxPdo", pdo));
+        }
+        if let Some(mb) = &m.mailbox {
+            push(&mut module, mailbox(mb));
+        }
+        for p in &m.profiles {
+            push(&mut module, profile(p));
+        }
+        push_all(&mut module, &m.unknown);
+        push(&mut e, module);
+    }
+    e
+}
+
+/// The element with the given name, appended if there is none.
+fn element<'a>(elements: &'a mut Vec<XmlElement>, name: &str) -> &'a mut XmlElement {
+    let i = match elements.iter().position(|e| e.name == name) {
+        Some(i) => i,
+        None => {
+            elements.push(XmlElement::new(name));
+            elements.len() - 1
+        }
+    };
+    &mut elements[i]
+}
+
+/// An `Index` element, relative to the slot of the module if `depend_on_slot`.
+fn index(idx: u16, depend_on_slot: bool) -> XmlElement {
+    let mut e = XmlElement::with_text("Index", hex(u64::from(idx), 4));
+    if depend_on_slot {
+        attr(&mut e, "DependOnSlot", Some("1"));
+    }
+    e
+}
+
+fn push(e: &mut XmlElement, child: XmlElement) {
+    e.nodes.push(XmlNode::Element(child));
+}
+
+fn push_all(e: &mut XmlElement, children: &[XmlElement]) {
+    e.nodes
+        .extend(children.iter().cloned().map(XmlNode::Element));
+}
+
+fn push_text(e: &mut XmlElement, name: &str, text: impl Into<String>) {
+    push(e, XmlElement::with_text(name, text));
+}
+
+fn push_names(e: &mut XmlElement, name: &str, names: &LocalizedText) {
+    for (text, lc_id) in names.iter() {
+        let mut n = XmlElement::with_text(name, text);
+        attr(&mut n, "LcId", lc_id.map(|id| id.to_string()));
+        push(e, n);
+    }
+}
+
+fn push_image(e: &mut XmlElement, image: Option<&Image>) {
+    match image {
+        Some(Image::Image16x14(img)) => push_text(e, "Image16x14", img),
+        Some(Image::ImageFile16x14(img)) => push_text(e, "ImageFile16x14", img),
+        Some(Image::ImageData16x14(HexBinary(img))) => push_text(e, "ImageData16x14", img),
+        None => {}
+    }
+}
+
+fn attr(e: &mut XmlElement, name: &str, value: Option<impl Into<String>>) {
+    if let Some(v) = value {
+        e.attributes.push((name.to_string(), v.into()));
+    }
+}
+
+fn hex(value: u64, digits: usize) -> String {
+    format!("#x{:0width$X}", value, width = digits)
+}
+
+fn hex_binary(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn millis(d: Duration) -> String {
+    d.as_millis().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml;
+    use std::fs;
+
+    #[test]
+    fn write_fixtures_and_read_them_back() {
+        for file_name in &[
+            "Beckhoff_EK11xx.xml",
+            "Weidmueller_UR20_FBC.xml",
+            "Weidmueller_UR20_FBC_from_IgH.xml",
+        ] {
+            let s = fs::read_to_string(format!("tests/fixtures/{}", file_name)).unwrap();
+            let info = EtherCatInfo::from_xml_str(&s).unwrap();
+            let written = info.to_xml_string();
+            assert_eq!(
+                EtherCatInfo::from_xml_str(&written).unwrap(),
+                info,
+                "{}",
+                file_name
+            );
+        }
+    }
+
+    /// The paths of all elements, attributes and texts of the tree.
+    fn paths(e: &XmlElement, parent: &str, out: &mut Vec<String>) {
+        let path = format!("{}/{}", parent, e.name);
+        for (name, _) in &e.attributes {
+            out.push(format!("{}@{}", path, name));
+        }
+        if e.text().is_some() {
+            out.push(format!("{}/text()", path));
+        }
+        for c in e.children() {
+            paths(c, &path, out);
+        }
+        out.push(path);
+    }
+
+    /// The paths of the input tree that are missing in the written one.
+    fn lost(input: &str, written: &str) -> Vec<String> {
+        let mut remaining = vec![];
+        paths(&xml::parse(written).unwrap(), "", &mut remaining);
+        let mut input_paths = vec![];
+        paths(&xml::parse(input).unwrap(), "", &mut input_paths);
+        let mut lost = vec![];
+        for p in input_paths {
+            match remaining.iter().position(|r| *r == p) {
+                Some(i) => {
+                    remaining.swap_remove(i);
+                }
+                None => lost.push(p),
+            }
+        }
+        lost
+    }
+
+    #[test]
+    fn write_fixtures_without_losing_content() {
+        for file_name in &[
+            "Beckhoff_EK11xx.xml",
+            "Weidmueller_UR20_FBC.xml",
+            "Weidmueller_UR20_FBC_from_IgH.xml",
+        ] {
+            let s = fs::read_to_string(format!("tests/fixtures/{}", file_name)).unwrap();
+            let written = EtherCatInfo::from_xml_str(&s).unwrap().to_xml_string();
+            assert_eq!(lost(&s, &written), Vec::<String>::new(), "{}", file_name);
+        }
+        let s = fs::read_to_string("tests/fixtures/Weidmueller_UR20_IO.xml").unwrap();
+        let written = EtherCatModule::from_xml_str(&s).unwrap().to_xml_string();
+        assert_eq!(lost(&s, &written), Vec::<String>::new());
+    }
+
+    #[test]
+    fn write_unmodelled_content_of_interpreted_elements() {
+        let s = r##"
+        <EtherCATInfo>
+            <Vendor>
+                <Id>2</Id>
+                <Name>Foo</Name>
+                <Comment>First</Comment>
+                <Comment>Second</Comment>
+                <URL>http://a</URL>
+                <URL>http://b</URL>
+            </Vendor>
+            <Descriptions>
+                <Groups>
+                    <Group><Type>Coupler</Type><Name>Coupler</Name><Comment>A</Comment><Comment>B</Comment></Group>
+                </Groups>
+                <Devices>
+                    <Device>
+                        <Type ProductCode="#x1" RevisionNo="#x2">Foo</Type>
+                        <Name>Bar</Name>
+                        <GroupType>Coupler</GroupType>
+                        <Sm MinSize="34" MaxSize="128" DefaultSize="128" StartAddress="#x1000" ControlByte="#x26" Enable="1">MBoxOut</Sm>
+                        <RxPdo Sm="0" Fixed="1">
+                            <Index>#x1600</Index>
+                            <Name>Outputs</Name>
+                            <Exclude>#x1601</Exclude>
+                            <Entry>
+                                <Index>#x7000</Index>
+                                <SubIndex>1</SubIndex>
+                                <BitLen>1</BitLen>
+                                <Name>Output</Name>
+                                <DataType>BOOL</DataType>
+                                <Comment>Channel 1</Comment>
+                            </Entry>
+                        </RxPdo>
+                        <Mailbox>
+                            <CoE>
+                                <InitCmd Fixed="true">
+                                    <Transition>PS</Transition>
+                                    <Index>#x8000</Index>
+                                    <SubIndex>1</SubIndex>
+                                    <Data>01</Data>
+                                </InitCmd>
+                            </CoE>
+                        </Mailbox>
+                    </Device>
+                </Devices>
+            </Descriptions>
+        </EtherCATInfo>"##;
+        let info = EtherCatInfo::from_xml_str(s).unwrap();
+        let written = info.to_xml_string();
+        assert_eq!(lost(s, &written), Vec::<String>::new());
+        assert_eq!(EtherCatInfo::from_xml_str(&written).unwrap(), info);
+
+        let device = &info.description.devices[0];
+        assert_eq!(device.sm[0].r#type.as_deref(), Some("MBoxOut"));
+        let init_cmd = &device.mailbox.as_ref().unwrap().init_cmds[0];
+        assert_eq!(
+            init_cmd.attributes,
+            vec![("Fixed".to_string(), "true".to_string())]
+        );
+        let root = xml::parse(&written).unwrap();
+        let vendor = root.children().find(|c| c.name == "Vendor").unwrap();
+        let comments: Vec<_> = vendor
+            .children()
+            .filter(|c| c.name == "Comment")
+            .filter_map(XmlElement::text)
+            .collect();
+        assert_eq!(comments, vec!["First", "Second"]);
+    }
+
+    #[test]
+    fn write_unknown_elements_verbatim_in_schema_order() {
+        let s = r##"
+        <EtherCATInfo xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+            <Vendor>
+                <Id>2</Id>
+                <VendorSpecific><Foo xmlns="urn:foo">a <b>b</b> c</Foo></VendorSpecific>
+            </Vendor>
+            <Descriptions>
+                <Groups/>
+                <Devices>
+                    <Device Physics="YY">
+                        <Type ProductCode="#x1" RevisionNo="#x2">Foo</Type>
+                        <HideType RevisionNo="#x1"/>
+                        <Name>Bar</Name>
+                        <GroupType>Coupler</GroupType>
+                        <Sm StartAddress="#x1000" ControlByte="#x26" Enable="1"/>
+                        <Dc><OpMode><Name>DC</Name><AssignActivate>#x300</AssignActivate></OpMode></Dc>
+                        <VendorSpecific><TwinCAT/></VendorSpecific>
+                    </Device>
+                </Devices>
+            </Descriptions>
+        </EtherCATInfo>"##;
+        let info = EtherCatInfo::from_xml_str(s).unwrap();
+        let written = info.to_xml_string();
+        assert!(
+            crate::check_schema(&written).unwrap().is_empty(),
+            "{}",
+            written
+        );
+
+        let root = xml::parse(&written).unwrap();
+        let vendor = root.children().find(|c| c.name == "Vendor").unwrap();
+        assert_eq!(
+            vendor.children().last().unwrap().to_string(),
+            r#"<VendorSpecific><Foo xmlns="urn:foo">a <b>b</b> c</Foo></VendorSpecific>"#
+        );
+        let device = root
+            .children()
+            .flat_map(|c| c.children())
+            .flat_map(|c| c.children())
+            .find(|c| c.name == "Device")
+            .unwrap();
+        let names: Vec<_> = device.children().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Type",
+                "HideType",
+                "Name",
+                "GroupType",
+                "Sm",
+                "Dc",
+                "VendorSpecific"
+            ]
+        );
+        assert_eq!(EtherCatInfo::from_xml_str(&written).unwrap(), info);
+    }
+
+    #[test]
+    fn write_mailbox_with_attributes_and_other_init_cmds() {
+        let m = Mailbox {
+            attributes: vec![("DataLinkLayer".to_string(), "true".to_string())],
+            init_cmds: vec![InitCmd {
+                transitions: vec![Transition::PreopToSafeop],
+                kind: InitCmdKind::Other {
+                    protocol: "EoE".to_string(),
+                    fields: vec![("Type".to_string(), "1".to_string())],
+                },
+                data: vec![0xC0, 0xA8],
+                timeout: None,
+                comment: None,
+                attributes: vec![],
+                unknown: vec![],
+            }],
+            unknown: vec![],
+        };
+        assert_eq!(
+            mailbox(&m).to_string(),
+            r#"<Mailbox DataLinkLayer="true"><EoE><InitCmd><Transition>PS</Transition><Type>1</Type><Data>C0A8</Data></InitCmd></EoE></Mailbox>"#
+        );
+    }
+
+    #[test]
+    fn write_timeouts_into_kept_state_machine() {
+        let info = DeviceInfo {
+            state_machine_timeouts: Some(StateMachineTimeouts {
+                preop: Duration::from_secs(2),
+                safeop_op: Duration::from_secs(9),
+                back_to_init: Duration::from_secs(5),
+                back_to_safeop: Duration::from_millis(200),
+            }),
+            unknown: vec![xml::parse(
+                r#"<StateMachine><Behavior StartToSafeopNoSync="true"/></StateMachine>"#,
+            )
+            .unwrap()],
+            ..DeviceInfo::default()
+        };
+        let e = device_info(&info);
+        let state_machines: Vec<_> = e.children().collect();
+        assert_eq!(state_machines.len(), 1);
+        let names: Vec<_> = state_machines[0]
+            .children()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Behavior", "Timeout"]);
+    }
+
+    #[test]
+    fn write_module_document() {
+        let s = fs::read_to_string("tests/fixtures/Weidmueller_UR20_IO.xml").unwrap();
+        let module = EtherCatModule::from_xml_str(&s).unwrap();
+        let written = module.to_xml_string();
+        assert!(written.contains("<EtherCATModule"));
+        assert_eq!(EtherCatModule::from_xml_str(&written).unwrap(), module);
+    }
+}
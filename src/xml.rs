@@ -0,0 +1,333 @@
+//! Generic XML element tree.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{Error, ErrorKind, Result},
+};
+use xml::{
+    namespace::{
+        NS_EMPTY_URI, NS_NO_PREFIX, NS_XMLNS_PREFIX, NS_XMLNS_URI, NS_XML_PREFIX, NS_XML_URI,
+    },
+    reader::{EventReader, XmlEvent},
+};
+
+/// An XML element with its attributes, namespace declarations and content.
+///
+/// It is used to keep XML that is not interpreted by this crate
+/// (e.g. `VendorSpecific`). The [`Display`](fmt::Display) implementation
+/// writes the element back as XML, [`XmlElement::to_document_string`]
+/// writes a whole indented document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    /// Namespaces declared by this element (`xmlns` attributes),
+    /// as pairs of prefix and URI. The prefix of the default namespace is empty.
+    pub namespaces: Vec<(String, String)>,
+    /// Child elements and text, in document order.
+    pub nodes: Vec<XmlNode>,
+}
+
+/// Content of an [`XmlElement`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+}
+
+impl XmlElement {
+    pub fn new(name: impl Into<String>) -> Self {
+        XmlElement {
+            name: name.into(),
+            attributes: vec![],
+            namespaces: vec![],
+            nodes: vec![],
+        }
+    }
+
+    /// Create an element that only contains the given text.
+    pub fn with_text(name: impl Into<String>, text: impl Into<String>) -> Self {
+        let mut e = XmlElement::new(name);
+        e.nodes.push(XmlNode::Text(text.into()));
+        e
+    }
+
+    /// The child elements, without the text in between.
+    pub fn children(&self) -> impl Iterator<Item = &XmlElement> {
+        self.nodes.iter().filter_map(|n| match n {
+            XmlNode::Element(e) => Some(e),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    /// The first text node, i.e. the whole text of an element
+    /// without child elements.
+    pub fn text(&self) -> Option<&str> {
+        self.nodes.iter().find_map(|n| match n {
+            XmlNode::Text(t) => Some(t.as_str()),
+            XmlNode::Element(_) => None,
+        })
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Write the element as an indented XML document.
+    ///
+    /// Elements that contain text are written on a single line,
+    /// so the text is kept as it is.
+    pub fn to_document_string(&self) -> String {
+        let mut s = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        self.write_indented(&mut s, 0);
+        s
+    }
+
+    fn write_indented(&self, out: &mut String, level: usize) {
+        let indent = "\t".repeat(level);
+        out.push_str(&indent);
+        let has_text = self.nodes.iter().any(|n| matches!(n, XmlNode::Text(_)));
+        if has_text || self.nodes.is_empty() {
+            out.push_str(&self.to_string());
+            out.push('\n');
+            return;
+        }
+        self.write_start_tag(out);
+        out.push_str(">\n");
+        for c in self.children() {
+            c.write_indented(out, level + 1);
+        }
+        out.push_str(&indent);
+        out.push_str(&format!("</{}>\n", self.name));
+    }
+
+    fn write_start_tag(&self, out: &mut String) {
+        out.push('<');
+        out.push_str(&self.name);
+        for (prefix, uri) in &self.namespaces {
+            if prefix.is_empty() {
+                out.push_str(&format!(" xmlns=\"{}\"", escape(uri)));
+            } else {
+                out.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape(uri)));
+            }
+        }
+        for (name, value) in &self.attributes {
+            out.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+        }
+    }
+}
+
+impl fmt::Display for XmlElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut start = String::new();
+        self.write_start_tag(&mut start);
+        f.write_str(&start)?;
+        if self.nodes.is_empty() {
+            return write!(f, "/>");
+        }
+        write!(f, ">")?;
+        for n in &self.nodes {
+            match n {
+                XmlNode::Element(e) => write!(f, "{}", e)?,
+                XmlNode::Text(t) => write!(f, "{}", escape(t))?,
+            }
+        }
+        write!(f, "</{}>", self.name)
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Parse a XML document into a tree of elements.
+///
+/// Comments, processing instructions and whitespace between elements
+/// are discarded, adjacent text and CDATA sections are merged.
+pub(crate) fn parse(xml: &str) -> Result<XmlElement> {
+    let mut stack: Vec<XmlElement> = vec![];
+    let mut scopes: Vec<BTreeMap<String, String>> = vec![predefined_namespaces()];
+    for event in EventReader::new(xml.as_bytes()) {
+        let event = event.map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        match event {
+            XmlEvent::StartElement {
+                name,
+                attributes,
+                namespace,
+            } => {
+                let mut element = XmlElement::new(qualified_name(&name));
+                element.attributes = attributes
+                    .into_iter()
+                    .map(|a| (qualified_name(&a.name), a.value))
+                    .collect();
+                let parent = scopes.last().expect("at least the predefined namespaces");
+                element.namespaces = namespace
+                    .0
+                    .iter()
+                    .filter(|(prefix, uri)| parent.get(*prefix) != Some(*uri))
+                    .map(|(prefix, uri)| (prefix.clone(), uri.clone()))
+                    .collect();
+                scopes.push(namespace.0);
+                stack.push(element);
+            }
+            XmlEvent::EndElement { .. } => {
+                scopes.pop();
+                let element = stack
+                    .pop()
+                    .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Unbalanced XML"))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.nodes.push(XmlNode::Element(element)),
+                    None => return Ok(element),
+                }
+            }
+            XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                if let Some(current) = stack.last_mut() {
+                    match current.nodes.last_mut() {
+                        Some(XmlNode::Text(t)) => t.push_str(&text),
+                        _ => current.nodes.push(XmlNode::Text(text)),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(Error::new(ErrorKind::InvalidData, "Missing root element"))
+}
+
+/// The namespaces that are known without a declaration.
+fn predefined_namespaces() -> BTreeMap<String, String> {
+    [
+        (NS_NO_PREFIX, NS_EMPTY_URI),
+        (NS_XML_PREFIX, NS_XML_URI),
+        (NS_XMLNS_PREFIX, NS_XMLNS_URI),
+    ]
+    .iter()
+    .map(|(prefix, uri)| (prefix.to_string(), uri.to_string()))
+    .collect()
+}
+
+fn qualified_name(name: &xml::name::OwnedName) -> String {
+    match &name.prefix {
+        Some(prefix) => format!("{}:{}", prefix, name.local_name),
+        None => name.local_name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_element_tree() {
+        let s = r##"<?xml version="1.0"?>
+        <Vendor FileVersion="0045">
+          <!-- comment -->
+          <Id>#x00000999</Id>
+          <Name LcId="1033">Vendor Name</Name>
+        </Vendor>"##;
+        let root = parse(s).unwrap();
+        assert_eq!(root.name, "Vendor");
+        assert_eq!(
+            root.attributes,
+            vec![("FileVersion".to_string(), "0045".to_string())]
+        );
+        assert_eq!(root.text(), None);
+        let children: Vec<_> = root.children().collect();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].name, "Id");
+        assert_eq!(children[0].text(), Some("#x00000999"));
+        assert_eq!(children[1].attribute("LcId"), Some("1033"));
+    }
+
+    #[test]
+    fn write_element_tree() {
+        let s = r##"<VendorSpecific>
+          <TwinCAT>
+            <AlternativeSmMapping Default="1"/>
+            <Text>a &lt; b &amp; "c"</Text>
+          </TwinCAT>
+        </VendorSpecific>"##;
+        let root = parse(s).unwrap();
+        assert_eq!(
+            root.to_string(),
+            r#"<VendorSpecific><TwinCAT><AlternativeSmMapping Default="1"/><Text>a &lt; b &amp; &quot;c&quot;</Text></TwinCAT></VendorSpecific>"#
+        );
+        assert_eq!(parse(&root.to_string()).unwrap(), root);
+    }
+
+    #[test]
+    fn keep_mixed_content_in_order() {
+        let s = "<Comment>before <b>bold</b> after<![CDATA[ <raw> ]]></Comment>";
+        let root = parse(s).unwrap();
+        assert_eq!(
+            root.nodes,
+            vec![
+                XmlNode::Text("before ".to_string()),
+                XmlNode::Element(XmlElement::with_text("b", "bold")),
+                XmlNode::Text(" after <raw> ".to_string()),
+            ]
+        );
+        assert_eq!(
+            root.to_string(),
+            "<Comment>before <b>bold</b> after &lt;raw&gt; </Comment>"
+        );
+        assert_eq!(parse(&root.to_string()).unwrap(), root);
+    }
+
+    #[test]
+    fn keep_namespace_declarations() {
+        let s = r#"<EtherCATInfo xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:noNamespaceSchemaLocation="EtherCATInfo.xsd">
+            <VendorSpecific>
+                <Foo xmlns="urn:foo" xmlns:bar="urn:bar"><bar:Baz/><Qux/></Foo>
+            </VendorSpecific>
+        </EtherCATInfo>"#;
+        let root = parse(s).unwrap();
+        assert_eq!(
+            root.namespaces,
+            vec![(
+                "xsi".to_string(),
+                "http://www.w3.org/2001/XMLSchema-instance".to_string()
+            )]
+        );
+        let foo = root.children().next().unwrap().children().next().unwrap();
+        assert_eq!(
+            foo.namespaces,
+            vec![
+                ("".to_string(), "urn:foo".to_string()),
+                ("bar".to_string(), "urn:bar".to_string()),
+            ]
+        );
+        assert!(foo.children().all(|c| c.namespaces.is_empty()));
+        assert_eq!(
+            foo.to_string(),
+            r#"<Foo xmlns="urn:foo" xmlns:bar="urn:bar"><bar:Baz/><Qux/></Foo>"#
+        );
+
+        let doc = root.to_document_string();
+        assert!(
+            doc.starts_with("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<EtherCATInfo xmlns:xsi=")
+        );
+        assert_eq!(parse(&doc).unwrap(), root);
+    }
+
+    #[test]
+    fn parse_invalid_xml() {
+        assert!(parse("<Vendor><Id></Vendor>").is_err());
+        assert!(parse("").is_err());
+    }
+}
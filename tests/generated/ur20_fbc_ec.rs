@@ -0,0 +1,427 @@
+// Generated by ethercat-esi from device `UR20-FBC-EC`. Do not edit.
+
+/// Outputs of `UR20-FBC-EC`.
+///
+/// UR20-FBC-EC / 1334910000
+///
+/// PDOs: `0x16FF`, `0x1600`, `0x1602`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Outputs(pub [u8; 15]);
+
+impl Outputs {
+    /// Size in bytes.
+    pub const SIZE: usize = 15;
+
+    /// `0xF200:01` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.0
+    pub fn entry_f200_01(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// Set `entry_f200_01`.
+    pub fn set_entry_f200_01(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x01;
+        } else {
+            self.0[0] &= !0x01;
+        }
+    }
+
+    /// `0xF200:02` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.1
+    pub fn entry_f200_02(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    /// Set `entry_f200_02`.
+    pub fn set_entry_f200_02(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x02;
+        } else {
+            self.0[0] &= !0x02;
+        }
+    }
+
+    /// `0xF200:03` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.2
+    pub fn entry_f200_03(&self) -> bool {
+        self.0[0] & 0x04 != 0
+    }
+
+    /// Set `entry_f200_03`.
+    pub fn set_entry_f200_03(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x04;
+        } else {
+            self.0[0] &= !0x04;
+        }
+    }
+
+    /// `0xF200:04` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.3
+    pub fn entry_f200_04(&self) -> bool {
+        self.0[0] & 0x08 != 0
+    }
+
+    /// Set `entry_f200_04`.
+    pub fn set_entry_f200_04(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x08;
+        } else {
+            self.0[0] &= !0x08;
+        }
+    }
+
+    /// `0xF200:05` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.4
+    pub fn entry_f200_05(&self) -> bool {
+        self.0[0] & 0x10 != 0
+    }
+
+    /// Set `entry_f200_05`.
+    pub fn set_entry_f200_05(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x10;
+        } else {
+            self.0[0] &= !0x10;
+        }
+    }
+
+    /// `0xF200:06` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.5
+    pub fn entry_f200_06(&self) -> bool {
+        self.0[0] & 0x20 != 0
+    }
+
+    /// Set `entry_f200_06`.
+    pub fn set_entry_f200_06(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x20;
+        } else {
+            self.0[0] &= !0x20;
+        }
+    }
+
+    /// `0xF200:07` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.6
+    pub fn entry_f200_07(&self) -> bool {
+        self.0[0] & 0x40 != 0
+    }
+
+    /// Set `entry_f200_07`.
+    pub fn set_entry_f200_07(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x40;
+        } else {
+            self.0[0] &= !0x40;
+        }
+    }
+
+    /// `0xF200:08` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 0.7
+    pub fn entry_f200_08(&self) -> bool {
+        self.0[0] & 0x80 != 0
+    }
+
+    /// Set `entry_f200_08`.
+    pub fn set_entry_f200_08(&mut self, v: bool) {
+        if v {
+            self.0[0] |= 0x80;
+        } else {
+            self.0[0] &= !0x80;
+        }
+    }
+
+    /// `0xF200:09` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.0
+    pub fn entry_f200_09(&self) -> bool {
+        self.0[1] & 0x01 != 0
+    }
+
+    /// Set `entry_f200_09`.
+    pub fn set_entry_f200_09(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x01;
+        } else {
+            self.0[1] &= !0x01;
+        }
+    }
+
+    /// `0xF200:0A` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.1
+    pub fn entry_f200_0a(&self) -> bool {
+        self.0[1] & 0x02 != 0
+    }
+
+    /// Set `entry_f200_0a`.
+    pub fn set_entry_f200_0a(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x02;
+        } else {
+            self.0[1] &= !0x02;
+        }
+    }
+
+    /// `0xF200:0B` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.2
+    pub fn entry_f200_0b(&self) -> bool {
+        self.0[1] & 0x04 != 0
+    }
+
+    /// Set `entry_f200_0b`.
+    pub fn set_entry_f200_0b(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x04;
+        } else {
+            self.0[1] &= !0x04;
+        }
+    }
+
+    /// `0xF200:0C` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.3
+    pub fn entry_f200_0c(&self) -> bool {
+        self.0[1] & 0x08 != 0
+    }
+
+    /// Set `entry_f200_0c`.
+    pub fn set_entry_f200_0c(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x08;
+        } else {
+            self.0[1] &= !0x08;
+        }
+    }
+
+    /// `0xF200:0D` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.4
+    pub fn entry_f200_0d(&self) -> bool {
+        self.0[1] & 0x10 != 0
+    }
+
+    /// Set `entry_f200_0d`.
+    pub fn set_entry_f200_0d(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x10;
+        } else {
+            self.0[1] &= !0x10;
+        }
+    }
+
+    /// `0xF200:0E` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.5
+    pub fn entry_f200_0e(&self) -> bool {
+        self.0[1] & 0x20 != 0
+    }
+
+    /// Set `entry_f200_0e`.
+    pub fn set_entry_f200_0e(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x20;
+        } else {
+            self.0[1] &= !0x20;
+        }
+    }
+
+    /// `0xF200:0F` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.6
+    pub fn entry_f200_0f(&self) -> bool {
+        self.0[1] & 0x40 != 0
+    }
+
+    /// Set `entry_f200_0f`.
+    pub fn set_entry_f200_0f(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x40;
+        } else {
+            self.0[1] &= !0x40;
+        }
+    }
+
+    /// `0xF200:10` of PDO `0x16FF`, `BOOL`, 1 bit(s) at offset 1.7
+    pub fn entry_f200_10(&self) -> bool {
+        self.0[1] & 0x80 != 0
+    }
+
+    /// Set `entry_f200_10`.
+    pub fn set_entry_f200_10(&mut self, v: bool) {
+        if v {
+            self.0[1] |= 0x80;
+        } else {
+            self.0[1] &= !0x80;
+        }
+    }
+
+    /// `0x7000:01` of PDO `0x1600`, `UINT32`, 32 bit(s) at offset 2.0
+    pub fn entry_7000_01(&self) -> u32 {
+        u32::from_le_bytes([self.0[2], self.0[3], self.0[4], self.0[5]])
+    }
+
+    /// Set `entry_7000_01`.
+    pub fn set_entry_7000_01(&mut self, v: u32) {
+        self.0[2..6].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// `0x7000:02` of PDO `0x1600`, `UINT32`, 32 bit(s) at offset 6.0
+    pub fn entry_7000_02(&self) -> u32 {
+        u32::from_le_bytes([self.0[6], self.0[7], self.0[8], self.0[9]])
+    }
+
+    /// Set `entry_7000_02`.
+    pub fn set_entry_7000_02(&mut self, v: u32) {
+        self.0[6..10].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// `0x7000:03` of PDO `0x1600`, `UINT16`, 16 bit(s) at offset 10.0
+    pub fn entry_7000_03(&self) -> u16 {
+        u16::from_le_bytes([self.0[10], self.0[11]])
+    }
+
+    /// Set `entry_7000_03`.
+    pub fn set_entry_7000_03(&mut self, v: u16) {
+        self.0[10..12].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// `0x7000:04` of PDO `0x1600`, `UINT16`, 16 bit(s) at offset 12.0
+    pub fn entry_7000_04(&self) -> u16 {
+        u16::from_le_bytes([self.0[12], self.0[13]])
+    }
+
+    /// Set `entry_7000_04`.
+    pub fn set_entry_7000_04(&mut self, v: u16) {
+        self.0[12..14].copy_from_slice(&v.to_le_bytes());
+    }
+
+    /// `0x7020:01` of PDO `0x1602`, `BOOL`, 1 bit(s) at offset 14.0
+    pub fn entry_7020_01(&self) -> bool {
+        self.0[14] & 0x01 != 0
+    }
+
+    /// Set `entry_7020_01`.
+    pub fn set_entry_7020_01(&mut self, v: bool) {
+        if v {
+            self.0[14] |= 0x01;
+        } else {
+            self.0[14] &= !0x01;
+        }
+    }
+
+    /// `0x7020:02` of PDO `0x1602`, `BOOL`, 1 bit(s) at offset 14.1
+    pub fn entry_7020_02(&self) -> bool {
+        self.0[14] & 0x02 != 0
+    }
+
+    /// Set `entry_7020_02`.
+    pub fn set_entry_7020_02(&mut self, v: bool) {
+        if v {
+            self.0[14] |= 0x02;
+        } else {
+            self.0[14] &= !0x02;
+        }
+    }
+
+    /// `0x7020:03` of PDO `0x1602`, `BOOL`, 1 bit(s) at offset 14.2
+    pub fn entry_7020_03(&self) -> bool {
+        self.0[14] & 0x04 != 0
+    }
+
+    /// Set `entry_7020_03`.
+    pub fn set_entry_7020_03(&mut self, v: bool) {
+        if v {
+            self.0[14] |= 0x04;
+        } else {
+            self.0[14] &= !0x04;
+        }
+    }
+
+    /// `0x7020:04` of PDO `0x1602`, `BOOL`, 1 bit(s) at offset 14.3
+    pub fn entry_7020_04(&self) -> bool {
+        self.0[14] & 0x08 != 0
+    }
+
+    /// Set `entry_7020_04`.
+    pub fn set_entry_7020_04(&mut self, v: bool) {
+        if v {
+            self.0[14] |= 0x08;
+        } else {
+            self.0[14] &= !0x08;
+        }
+    }
+}
+
+/// Inputs of `UR20-FBC-EC`.
+///
+/// UR20-FBC-EC / 1334910000
+///
+/// PDOs: `0x1AFF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Inputs(pub [u8; 2]);
+
+impl Inputs {
+    /// Size in bytes.
+    pub const SIZE: usize = 2;
+
+    /// `0xF100:01` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.0
+    pub fn entry_f100_01(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    /// `0xF100:02` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.1
+    pub fn entry_f100_02(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    /// `0xF100:03` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.2
+    pub fn entry_f100_03(&self) -> bool {
+        self.0[0] & 0x04 != 0
+    }
+
+    /// `0xF100:04` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.3
+    pub fn entry_f100_04(&self) -> bool {
+        self.0[0] & 0x08 != 0
+    }
+
+    /// `0xF100:05` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.4
+    pub fn entry_f100_05(&self) -> bool {
+        self.0[0] & 0x10 != 0
+    }
+
+    /// `0xF100:06` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.5
+    pub fn entry_f100_06(&self) -> bool {
+        self.0[0] & 0x20 != 0
+    }
+
+    /// `0xF100:07` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.6
+    pub fn entry_f100_07(&self) -> bool {
+        self.0[0] & 0x40 != 0
+    }
+
+    /// `0xF100:08` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 0.7
+    pub fn entry_f100_08(&self) -> bool {
+        self.0[0] & 0x80 != 0
+    }
+
+    /// `0xF100:09` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.0
+    pub fn entry_f100_09(&self) -> bool {
+        self.0[1] & 0x01 != 0
+    }
+
+    /// `0xF100:0A` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.1
+    pub fn entry_f100_0a(&self) -> bool {
+        self.0[1] & 0x02 != 0
+    }
+
+    /// `0xF100:0B` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.2
+    pub fn entry_f100_0b(&self) -> bool {
+        self.0[1] & 0x04 != 0
+    }
+
+    /// `0xF100:0C` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.3
+    pub fn entry_f100_0c(&self) -> bool {
+        self.0[1] & 0x08 != 0
+    }
+
+    /// `0xF100:0D` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.4
+    pub fn entry_f100_0d(&self) -> bool {
+        self.0[1] & 0x10 != 0
+    }
+
+    /// `0xF100:0E` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.5
+    pub fn entry_f100_0e(&self) -> bool {
+        self.0[1] & 0x20 != 0
+    }
+
+    /// `0xF100:0F` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.6
+    pub fn entry_f100_0f(&self) -> bool {
+        self.0[1] & 0x40 != 0
+    }
+
+    /// `0xF100:10` of PDO `0x1AFF`, `BOOL`, 1 bit(s) at offset 1.7
+    pub fn entry_f100_10(&self) -> bool {
+        self.0[1] & 0x80 != 0
+    }
+}
@@ -1,6 +1,10 @@
-use ethercat_esi::EtherCatInfo;
+use ethercat_esi::{
+    connect_line, DiagHistoryMessage, DiagType, Dictionary, EBusBudget, EsiDocument, EtherCatInfo,
+    EtherCatModule, FmmuType, HexBinary, Image, InitCmdKind, ParseOptions, PortType, SchemaError,
+    Transition,
+};
 use ethercat_types as ec;
-use std::{fs::File, io::prelude::*};
+use std::{fs::File, io::prelude::*, time::Duration};
 
 #[test]
 fn parse_xml_crated_by_weidmueller() {
@@ -27,6 +31,31 @@ fn parse_xml_crated_by_weidmueller() {
         dev_0.rx_pdo[0].entries[0].entry_idx.sub_idx,
         ec::SubIdx::from(1)
     );
+    let info = dev_0.info.as_ref().unwrap();
+    let timeouts = info.state_machine_timeouts.unwrap();
+    assert_eq!(timeouts.preop, Duration::from_millis(2000));
+    assert_eq!(timeouts.safeop_op, Duration::from_millis(9000));
+    assert_eq!(timeouts.back_to_init, Duration::from_millis(5000));
+    assert_eq!(timeouts.back_to_safeop, Duration::from_millis(200));
+    let mailbox = info.mailbox_timeouts.unwrap();
+    assert_eq!(mailbox.request, Duration::from_millis(100));
+    assert_eq!(mailbox.response, Duration::from_millis(2000));
+    let init_cmds = dev_0.init_cmds(Transition::PreopToSafeop);
+    assert_eq!(init_cmds.len(), 1);
+    assert_eq!(init_cmds[0].data, vec![0x00, 0x09, 0x01, 0x00]);
+    let fmmu: Vec<_> = dev_0.fmmu.iter().map(|f| f.r#type.clone()).collect();
+    assert_eq!(
+        fmmu,
+        vec![FmmuType::Outputs, FmmuType::Inputs, FmmuType::MBoxState]
+    );
+    let configs = dev_0
+        .fmmu_config(&dev_0.assigned_pdos(), 0x1000, 0x2000)
+        .unwrap();
+    assert_eq!(configs.len(), 2);
+    assert_eq!(configs[0].physical_start_address, 0x1800);
+    assert_eq!(configs[0].logical_start_address, 0x1000);
+    assert_eq!(configs[1].physical_start_address, 0x2400);
+    assert_eq!(configs[1].logical_start_address, 0x2000);
 }
 
 #[test]
@@ -39,6 +68,119 @@ fn parse_xml_crated_by_weidmueller_module_information() {
     assert_eq!(esi.description.modules.len(), 82);
     let m = &esi.description.modules[0];
     assert_eq!(m.tx_pdo[0].entries.len(), 6);
+    let init_cmds: usize = esi
+        .description
+        .modules
+        .iter()
+        .map(|m| m.init_cmds(Transition::PreopToSafeop).len())
+        .sum();
+    assert_eq!(init_cmds, 164);
+    let cmd = m.init_cmds(Transition::PreopToSafeop)[0];
+    assert!(matches!(
+        cmd.kind,
+        InitCmdKind::Sdo {
+            depend_on_slot: true,
+            ..
+        }
+    ));
+    assert!(m.init_cmds(Transition::SafeopToOp).is_empty());
+}
+
+#[test]
+fn parse_module_description_file() {
+    let xml = std::fs::read_to_string("tests/fixtures/Weidmueller_UR20_IO.xml").unwrap();
+    let m = EtherCatModule::from_xml_str(&xml).unwrap();
+    assert_eq!(m.vendor.id, 0x0000_0230);
+    assert_eq!(m.version.as_deref(), Some("1.12"));
+    assert_eq!(m.modules.len(), 82);
+    let strict = ParseOptions {
+        strict: true,
+        ..Default::default()
+    };
+    assert!(EtherCatModule::from_xml_str_with_options(&xml, &strict).is_ok());
+    match EsiDocument::from_xml_str(&xml).unwrap() {
+        EsiDocument::Module(doc) => assert_eq!(doc.modules.len(), 82),
+        _ => panic!("Expected a module description"),
+    }
+
+    let xml = std::fs::read_to_string("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    assert!(EtherCatModule::from_xml_str(&xml).is_err());
+    assert!(matches!(
+        EsiDocument::from_xml_str(&xml).unwrap(),
+        EsiDocument::Info(_)
+    ));
+}
+
+#[test]
+fn parse_object_dictionary_of_weidmueller_coupler() {
+    let xml = std::fs::read_to_string("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    let esi = EtherCatInfo::from_xml_str(&xml).unwrap();
+    let profile = &esi.description.devices[0].profiles[0];
+    assert_eq!(profile.profile_no, Some(5001));
+    let dict = profile.dictionary.as_ref().unwrap();
+    let identity = dict
+        .objects
+        .iter()
+        .find(|o| o.idx == ec::Idx::from(0x1018))
+        .unwrap();
+    assert_eq!(identity.r#type, "DT1018");
+    let info = identity.info.as_ref().unwrap();
+    assert_eq!(
+        info.sub_items[1].info.default_data,
+        Some(HexBinary("02300000".to_string()))
+    );
+    let dt = dict.data_types.iter().find(|t| t.name == "DT1018").unwrap();
+    assert_eq!(dt.sub_items.len(), 5);
+
+    // The same dictionary as separate document
+    let dict_xml = format!(
+        "<EtherCATDict>{}</EtherCATDict>",
+        &xml[xml.find("<Dictionary>").unwrap()..xml.find("</Dictionary>").unwrap() + 13]
+    );
+    assert_eq!(Dictionary::from_xml_str(&dict_xml).unwrap(), *dict);
+    assert!(matches!(
+        EsiDocument::from_xml_str(&dict_xml).unwrap(),
+        EsiDocument::Dict(_)
+    ));
+    assert!(Dictionary::from_xml_str(&xml).is_err());
+
+    let device = &esi.description.devices[0];
+    let sdos = device.startup_sdos(&device.assigned_pdos(), &[]).unwrap();
+    // `PdoConfig` but no `PdoAssign`: the assignment is not downloaded
+    assert_eq!(sdos.len(), 6);
+    assert_eq!(sdos[0].idx, ec::SdoIdx::new(0x1C32, 1));
+    assert_eq!(sdos[0].comment, "SM output parameter - Sync mode");
+    let assignment: Vec<_> = sdos
+        .iter()
+        .filter(|s| s.idx.idx == ec::Idx::from(0x1C12))
+        .map(|s| s.data.clone())
+        .collect();
+    assert!(assignment.is_empty());
+    let sync_mode = [(ec::SdoIdx::new(0x1C32, 1), ec::Value::U16(1))];
+    let sdos = device
+        .startup_sdos(&device.assigned_pdos(), &sync_mode)
+        .unwrap();
+    assert_eq!(sdos[0].data, vec![1, 0]);
+}
+
+#[test]
+fn decode_diagnosis_messages_of_weidmueller_coupler() {
+    let xml = std::fs::read_to_string("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    let esi = EtherCatInfo::from_xml_str(&xml).unwrap();
+    let device = &esi.description.devices[0];
+    assert_eq!(device.profiles[0].diag_messages.len(), 66);
+    let texts = device.diag_texts();
+    assert_eq!(texts.len(), 66);
+
+    let mut data = vec![0x00, 0x00, 0x00, 0xE0, 0x02, 0x02, 0x11, 0x00];
+    data.extend_from_slice(&[0; 8]);
+    data.extend_from_slice(&[0x05, 0x00, 0x02, 0x06, 0x00, 0x00, 0x0F]);
+    let msg = DiagHistoryMessage::from_bytes(&data).unwrap();
+    assert_eq!(msg.r#type, DiagType::Error);
+    assert_eq!(
+        texts.format(&msg, &[1031]),
+        "Sync Manager 2 invalid address (3840)"
+    );
 }
 
 #[test]
@@ -49,6 +191,63 @@ fn parse_xml_crated_by_beckhoff() {
     let esi = EtherCatInfo::from_xml_str(&xml_string).unwrap();
     assert_eq!(esi.vendor.id, 2);
     assert_eq!(esi.description.devices.len(), 24);
+    let unknown: Vec<_> = esi.description.devices[0]
+        .unknown
+        .iter()
+        .map(|e| e.name.as_str())
+        .collect();
+    assert_eq!(
+        unknown,
+        vec!["HideType", "HideType", "HideType", "HideType", "URL", "URL", "Eeprom"]
+    );
+    let ados: Vec<_> = esi
+        .description
+        .devices
+        .iter()
+        .filter_map(|d| d.info.as_ref()?.identification_ado)
+        .collect();
+    assert_eq!(ados, vec![0x1000; 5]);
+    let ek1100 = &esi.description.devices[1];
+    assert_eq!(ek1100.ebus_current(), Some(-2000));
+    assert_eq!(EBusBudget::new(vec![ek1100]).balance(), 2000);
+    let ports = ek1100.ports();
+    assert_eq!(
+        ek1100.physics,
+        vec![PortType::Mii, PortType::Ebus, PortType::Mii]
+    );
+    assert_eq!(ports[0].label.as_deref(), Some("X1 IN"));
+    assert_eq!(ports[1].r#type, PortType::Ebus);
+    let ek1110 = esi
+        .description
+        .devices
+        .iter()
+        .find(|d| d.desc == "EK1110")
+        .unwrap();
+    let links = connect_line(&[ek1100, ek1110, ek1100]).unwrap();
+    assert_eq!(links[0].r#type, PortType::Ebus);
+    assert_eq!(links[1].r#type, PortType::Mii);
+    assert!(connect_line(&[ek1110, ek1110]).is_err());
+    let group = &esi.description.groups[0];
+    assert_eq!(group.name.default_text(), Some("System Terminals"));
+    assert_eq!(group.name.get_by_tag("de-DE"), Some("System Klemmen"));
+    assert_eq!(group.name.resolve(&[1036, 1031]), Some("System Klemmen"));
+
+    let first_device = &esi.description.devices[0];
+    assert_eq!(first_device.group_type.as_deref(), Some("SystemBk"));
+    let tree = esi.description.group_tree();
+    let groups: Vec<_> = tree
+        .roots
+        .iter()
+        .map(|n| (n.group.r#type.as_str(), n.devices.len()))
+        .collect();
+    assert_eq!(
+        groups,
+        vec![("System", 11), ("SystemBk", 11), ("Customer", 2)]
+    );
+    assert!(tree.ungrouped.is_empty());
+    let rendered = tree.to_string();
+    assert!(rendered.starts_with("System Terminals\n  "));
+    assert!(rendered.contains("\nSystem Couplers\n  EK1100 EtherCAT Coupler (0.5A E-Bus)\n"));
 }
 
 #[test]
@@ -60,3 +259,153 @@ fn parse_xml_crated_by_igh() {
     let esi = EtherCatInfo::from_xml_str(&xml_string).unwrap();
     assert_eq!(esi.vendor.id, 0x230);
 }
+
+#[test]
+fn parse_xml_strict() {
+    let mut file = File::open("tests/fixtures/Beckhoff_EK11xx.xml").unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    assert!(EtherCatInfo::from_xml_str_strict(&xml_string).is_ok());
+
+    let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    assert!(EtherCatInfo::from_xml_str_strict(&xml_string).is_ok());
+}
+
+#[test]
+fn parse_xml_crated_by_igh_strict() {
+    let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC_from_IgH.xml").unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    let err = EtherCatInfo::from_xml_str_strict(&xml_string).unwrap_err();
+    let SchemaError(violations) = err.get_ref().unwrap().downcast_ref().unwrap();
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0].path, "/EtherCATInfo/Descriptions");
+    assert_eq!(violations[0].message, "missing element `Groups`");
+    assert_eq!(
+        violations[1].path,
+        "/EtherCATInfo/Descriptions/Devices/Device"
+    );
+}
+
+#[test]
+fn parse_xml_crated_by_igh_strict_and_lenient() {
+    let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC_from_IgH.xml").unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    let opts = ParseOptions {
+        strict: true,
+        lenient: true,
+    };
+    let (esi, warnings) = EtherCatInfo::from_xml_str_with_options(&xml_string, &opts).unwrap();
+    assert_eq!(esi.vendor.id, 0x230);
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].message, "missing element `Groups`");
+}
+
+#[test]
+fn process_image_layout_of_weidmueller_coupler() {
+    let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    let esi = EtherCatInfo::from_xml_str(&xml_string).unwrap();
+    let dev = &esi.description.devices[0];
+    let pdos = [dev.rx_pdo[0].idx, dev.tx_pdo[0].idx];
+    let layout = dev.process_image_layout(&pdos).unwrap();
+    let bits = |pdo: &ethercat_esi::Pdo| pdo.entries.iter().map(|e| e.bit_len).sum::<usize>();
+    assert_eq!(layout.output_size, (bits(&dev.rx_pdo[0]) + 7) / 8);
+    assert_eq!(layout.input_size, (bits(&dev.tx_pdo[0]) + 7) / 8);
+    assert_eq!(layout.sms[0].sm, ec::SmIdx::from(2));
+    assert_eq!(layout.sms[1].sm, ec::SmIdx::from(3));
+}
+
+#[test]
+fn igh_c_config_of_weidmueller_coupler() {
+    let mut file = File::open("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    let esi = EtherCatInfo::from_xml_str(&xml_string).unwrap();
+    let dev = &esi.description.devices[0];
+    let pdos = [dev.rx_pdo[0].idx, dev.tx_pdo[0].idx];
+    let code = dev.igh_c_config("slave_0", &pdos).unwrap();
+    assert!(code.contains("    {0, EC_DIR_OUTPUT, 0, NULL, EC_WD_DISABLE},\n"));
+    assert!(code.contains("    {1, EC_DIR_INPUT, 0, NULL, EC_WD_DISABLE},\n"));
+    assert!(code.contains("    {2, EC_DIR_OUTPUT, 1, slave_0_pdos + 0, EC_WD_ENABLE},\n"));
+    assert!(code.contains("    {3, EC_DIR_INPUT, 1, slave_0_pdos + 1, EC_WD_DISABLE},\n"));
+}
+
+#[test]
+fn merge_weidmueller_files() {
+    let fbc = "tests/fixtures/Weidmueller_UR20_FBC.xml";
+    let igh = "tests/fixtures/Weidmueller_UR20_FBC_from_IgH.xml";
+    let (merged, conflicts) = EtherCatInfo::merge_files([fbc, igh]).unwrap();
+    let single = EtherCatInfo::merge_files([fbc]).unwrap().0;
+    assert_eq!(
+        merged.description.devices.len(),
+        single.description.devices.len()
+    );
+    assert_eq!(merged.description.devices[0].source.as_deref(), Some(fbc));
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].kept.as_deref(), Some(fbc));
+    assert_eq!(conflicts[0].dropped.as_deref(), Some(igh));
+    assert!(!conflicts[0].diff.is_empty());
+
+    let beckhoff = "tests/fixtures/Beckhoff_EK11xx.xml";
+    assert!(EtherCatInfo::merge_files([fbc, beckhoff]).is_err());
+}
+
+#[test]
+fn decode_icons() {
+    let dir = "tests/fixtures";
+    let esi = EtherCatInfo::from_file("tests/fixtures/Beckhoff_EK11xx.xml").unwrap();
+    let images = esi
+        .vendor
+        .image
+        .iter()
+        .chain(
+            esi.description
+                .groups
+                .iter()
+                .filter_map(|g| g.image.as_ref()),
+        )
+        .chain(
+            esi.description
+                .devices
+                .iter()
+                .filter_map(|d| d.image.as_ref()),
+        );
+    let mut decoded = 0;
+    for image in images {
+        match image.to_rgba(dir) {
+            Ok(img) => {
+                assert_eq!((img.width, img.height), (16, 14));
+                assert_eq!(img.pixels.len(), 16 * 14 * 4);
+                decoded += 1;
+            }
+            Err(err) => {
+                assert!(matches!(image, Image::Image16x14(_)));
+                assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+            }
+        }
+    }
+    assert!(decoded > 0);
+
+    let xml = std::fs::read_to_string("tests/fixtures/Weidmueller_UR20_FBC.xml").unwrap();
+    let esi = EtherCatInfo::from_xml_str(&xml).unwrap();
+    let image = esi.vendor.image.as_ref().unwrap();
+    let png = image.to_png(dir).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+
+    // Image files are resolved relative to the ESI file
+    let bytes = match image {
+        Image::ImageData16x14(hex) => hex.to_bytes().unwrap(),
+        _ => panic!("Expected image data"),
+    };
+    let tmp = std::env::temp_dir().join(format!("ethercat-esi-icons-{}", std::process::id()));
+    std::fs::create_dir_all(tmp.join("Images")).unwrap();
+    std::fs::write(tmp.join("Images/Coupler.bmp"), bytes).unwrap();
+    let file = Image::ImageFile16x14(r"Images\Coupler.bmp".to_string());
+    assert_eq!(file.to_rgba(&tmp).unwrap(), image.to_rgba(dir).unwrap());
+    std::fs::remove_dir_all(tmp).unwrap();
+}
@@ -0,0 +1,69 @@
+use ethercat_esi::{generate_process_data, PdoIdx};
+use std::fs;
+
+const ESI: &str = "tests/fixtures/Weidmueller_UR20_FBC_from_IgH.xml";
+const GENERATED: &str = "tests/generated/ur20_fbc_ec.rs";
+
+#[allow(dead_code)]
+mod ur20_fbc_ec {
+    include!("generated/ur20_fbc_ec.rs");
+}
+
+use ur20_fbc_ec::{Inputs, Outputs};
+
+fn generate() -> String {
+    let pdos = [0x16FF, 0x1600, 0x1602, 0x1AFF].map(PdoIdx::from);
+    generate_process_data(ESI, 0x4F91_1C30, &pdos).unwrap()
+}
+
+/// Set `UPDATE_GENERATED=1` to rewrite the included file.
+#[test]
+fn included_code_is_up_to_date() {
+    let code = generate();
+    if std::env::var_os("UPDATE_GENERATED").is_some() {
+        fs::write(GENERATED, &code).unwrap();
+    }
+    assert_eq!(fs::read_to_string(GENERATED).unwrap(), code);
+}
+
+#[test]
+fn round_trip_outputs() {
+    let mut outputs = Outputs::default();
+    assert_eq!(Outputs::SIZE, 15);
+
+    outputs.set_entry_7000_01(0x1234_5678);
+    outputs.set_entry_7000_02(u32::MAX);
+    outputs.set_entry_7000_03(0xBEEF);
+    outputs.set_entry_7000_04(7);
+    outputs.set_entry_f200_02(true);
+    outputs.set_entry_f200_10(true);
+    outputs.set_entry_7020_03(true);
+
+    assert_eq!(outputs.entry_7000_01(), 0x1234_5678);
+    assert_eq!(outputs.entry_7000_02(), u32::MAX);
+    assert_eq!(outputs.entry_7000_03(), 0xBEEF);
+    assert_eq!(outputs.entry_7000_04(), 7);
+    assert!(outputs.entry_f200_02());
+    assert!(!outputs.entry_f200_01());
+    assert!(outputs.entry_f200_10());
+    assert!(outputs.entry_7020_03());
+    assert!(!outputs.entry_7020_04());
+
+    assert_eq!(outputs.0[..2], [0x02, 0x80]);
+    assert_eq!(outputs.0[2..6], 0x1234_5678_u32.to_le_bytes());
+    assert_eq!(outputs.0[14], 0x04);
+
+    outputs.set_entry_f200_02(false);
+    outputs.set_entry_7000_02(0);
+    assert!(!outputs.entry_f200_02());
+    assert_eq!(outputs.entry_7000_01(), 0x1234_5678);
+    assert_eq!(outputs.entry_7000_03(), 0xBEEF);
+}
+
+#[test]
+fn read_inputs() {
+    let inputs = Inputs([0x81, 0x00]);
+    assert!(inputs.entry_f100_01());
+    assert!(!inputs.entry_f100_02());
+    assert!(inputs.entry_f100_08());
+}
@@ -0,0 +1,26 @@
+use ethercat_esi::{validate, EtherCatInfo, Rule, Severity};
+use std::{fs::File, io::prelude::*};
+
+fn read_esi(file_name: &str) -> EtherCatInfo {
+    let mut file = File::open(file_name).unwrap();
+    let mut xml_string = String::new();
+    file.read_to_string(&mut xml_string).unwrap();
+    EtherCatInfo::from_xml_str(&xml_string).unwrap()
+}
+
+#[test]
+fn validate_esi_crated_by_beckhoff() {
+    let esi = read_esi("tests/fixtures/Beckhoff_EK11xx.xml");
+    assert!(validate(&esi).is_empty());
+}
+
+#[test]
+fn validate_esi_crated_by_weidmueller_module_information() {
+    let esi = read_esi("tests/fixtures/Weidmueller_UR20_IO.xml");
+    let diagnostics = validate(&esi);
+    assert!(!diagnostics.is_empty());
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.rule == Rule::BitLenMismatch && d.severity == Severity::Error));
+    assert_eq!(diagnostics[0].path, "modules[79]/rx_pdo[0]/entries[10]");
+}